@@ -5,10 +5,7 @@ use tile_engine::for_gamedata::*;
 
 #[derive(Debug)]
 pub struct ProgpuzzGameData {
-    levset: levels::ProgpuzzLevset,
-
-    // TODO: Or better to store "current level" in a higher layer?
-    reload_needed: bool,
+    levset: LevelSet<ProgpuzzMovementLogic>,
 
     save_game_data: GenericProgSaveGame,
 }
@@ -18,25 +15,28 @@ impl BaseGameData for ProgpuzzGameData {
     type SaveGame = GenericProgSaveGame;
 
     fn new() -> Self {
-        let levset = levels::ProgpuzzLevset::new();
-        let num_levels = levset.num_levels();
+        let levset = levels::level_set();
+        let progression = levset.progression();
         ProgpuzzGameData {
             levset,
-            reload_needed: false,
-            save_game_data: GenericProgSaveGame::new(num_levels),
+            save_game_data: GenericProgSaveGame::new(progression, default_save_storage("prog_puzz")),
         }
     }
 
     fn advance_scene(&mut self, continuation: SceneConclusion) {
-        self.levset.advance_scene(continuation);
+        let unlocked = self.save_game_data.get_unlocked_levels();
+        self.levset.advance_scene(continuation, &unlocked);
     }
 
     fn load_scene(&mut self) -> Scene::<Self::MovementLogic> {
         log::debug!("Progpuzz loading scene");
-        self.reload_needed = false;
         self.levset.load_scene()
     }
 
+    fn load_level_scene(&mut self, lev_idx: u16) -> Option<Scene<Self::MovementLogic>> {
+        self.levset.load_level_scene(lev_idx)
+    }
+
     fn save_game(&mut self) -> &mut Self::SaveGame {
         &mut self.save_game_data
     }
@@ -44,10 +44,7 @@ impl BaseGameData for ProgpuzzGameData {
 
 impl LevelChooser for ProgpuzzGameData {
     fn get_level_str(&self) -> String {
-        match self.levset.current_levid {
-            levels::ProgpuzzSceneId::LevCodingArena(lev_num) => format!("Level: {}", lev_num),
-            _ => panic!(),
-        }
+        self.levset.get_level_str()
     }
 
     fn num_levels(&self) -> u16 {
@@ -58,13 +55,19 @@ impl LevelChooser for ProgpuzzGameData {
         self.levset.get_current_level()
     }
 
+    fn progression(&self) -> Progression {
+        self.levset.progression()
+    }
+
     fn reload_needed(&self) -> bool {
-        self.reload_needed
+        self.levset.reload_needed()
     }
 
     fn goto_level(&mut self, lev_idx: u16) {
-        log::debug!("Progpuzz going to level {lev_idx}");
         self.levset.goto_level(lev_idx);
-        self.reload_needed = true;
+    }
+
+    fn level_title(&self, lev_idx: u16) -> Option<String> {
+        self.levset.level_title(lev_idx)
     }
 }
@@ -0,0 +1,317 @@
+// Import worlds and programs from Karel the Robot, so existing Karel exercises can be played.
+//
+// Worlds are in the Stanford Karel format, one item per line, with corners numbered from 1
+// and street 1 along the bottom:
+//
+//     Dimension: (5, 3)
+//     Wall: (2, 1) east
+//     Beeper: (4, 2) 1
+//     Karel: (1, 1) east
+//
+// Karel's walls run between corners, so each corner becomes every second square of the map,
+// with the squares between corners left open unless there's a wall there. So one Karel
+// move() is two F instrs. Beepers become marks, and several beepers on a corner are one mark.
+//
+// Programs are statements in Karel's Java-like syntax, e.g. the body of run():
+//
+//     move();
+//     repeat (3) { turnLeft(); }
+//     while (frontIsClear()) { move(); putBeeper(); }
+//
+// The prog_puzz language has no way to leave a loop or check the front before moving, so
+// while is only accepted as the last statement, with a body of one move() followed only by
+// putBeeper() or pickBeeper(). Once the bot is blocked, the move fails before anything else
+// in the body, and putting or picking again on the same corner changes nothing, which leaves
+// the world as Karel would. A body putting a beeper before moving would also mark the last
+// corner, which Karel never reaches the body for, so it's refused.
+
+use tile_engine::for_gamedata::*;
+
+use super::movement_logic::ProgpuzzMovementLogic;
+use super::objs::obj_registry;
+
+/// Contents of a Karel world file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KarelWorld {
+    // Number of avenues (columns) and streets (rows) of corners.
+    w: u16,
+    h: u16,
+    // Corner and which side of it the wall is on.
+    walls: Vec<((u16, u16), Facing)>,
+    beepers: Vec<(u16, u16)>,
+    karel: ((u16, u16), Facing),
+}
+
+impl KarelWorld {
+    pub fn from_text(txt: &str) -> Result<Self, String> {
+        let (mut dims, mut karel) = (None, None);
+        let (mut walls, mut beepers) = (vec![], vec![]);
+        for line in txt.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (key, val) = line.split_once(':').ok_or_else(|| format!("Expected 'Key: value' in world line '{line}'"))?;
+            match key.trim() {
+                "Dimension" => dims = Some(parse_coords(val)?.0),
+                "Wall" => {
+                    let (corner, rest) = parse_coords(val)?;
+                    walls.push((corner, parse_dir(rest)?));
+                },
+                "Beeper" => {
+                    let (corner, rest) = parse_coords(val)?;
+                    let count = rest.trim().parse::<u32>().map_err(|_| format!("Unrecognised beeper count in '{line}'"))?;
+                    if count > 0 {
+                        beepers.push(corner);
+                    }
+                },
+                "Karel" => {
+                    let (corner, rest) = parse_coords(val)?;
+                    karel = Some((corner, parse_dir(rest)?));
+                },
+                // Only affect how Karel runs, not the world.
+                "BeeperBag" | "Speed" => (),
+                _ => return Err(format!("Unrecognised world line '{line}'")),
+            }
+        }
+
+        let (w, h) = dims.ok_or("World has no Dimension")?;
+        let karel = karel.ok_or("World has no Karel")?;
+        let world = Self { w, h, walls, beepers, karel };
+        let corners = world.walls.iter().map(|(corner, _)| corner).chain(&world.beepers).chain([&world.karel.0]);
+        if let Some((x, y)) = corners.into_iter().find(|(x, y)| !(1..=w).contains(x) || !(1..=h).contains(y)) {
+            return Err(format!("Corner ({x}, {y}) is outside the {w}x{h} world"));
+        }
+        Ok(world)
+    }
+
+    /// Map square for a corner.
+    pub fn map_pos(&self, (x, y): (u16, u16)) -> MapCoord {
+        MapCoord::from_xy(2 * x as i16 - 1, 2 * (self.h - y) as i16 + 1)
+    }
+
+    /// Squares with beepers, e.g. to require the same marks as a world's intended end state.
+    pub fn beeper_squares(&self) -> Vec<MapCoord> {
+        self.beepers.iter().map(|corner| self.map_pos(*corner)).collect()
+    }
+
+    /// Square map of prog_puzz objs, with Karel as Progbot. Walled off outside the world.
+    pub fn to_arena(&self) -> Arena<ProgpuzzMovementLogic> {
+        let sz = (2 * self.w.max(self.h) + 1) as usize;
+        let mut rows = vec![vec!['#'; sz]; sz];
+        let mut set = |pos: MapCoord, ch: char| rows[pos.y as usize][pos.x as usize] = ch;
+
+        // Corners, and the squares between neighbouring corners.
+        for x in 1..=self.w {
+            for y in 1..=self.h {
+                let pos = self.map_pos((x, y));
+                set(pos, ' ');
+                if x < self.w {
+                    set(pos + Facing::E, ' ');
+                }
+                if y < self.h {
+                    set(pos + Facing::N, ' ');
+                }
+            }
+        }
+        for (corner, dir) in &self.walls {
+            set(self.map_pos(*corner) + *dir, '#');
+        }
+        for corner in &self.beepers {
+            set(self.map_pos(*corner), '.');
+        }
+        let (karel_corner, karel_dir) = self.karel;
+        let karel_ch = match karel_dir {
+            Facing::N => '^',
+            Facing::E => '>',
+            Facing::S => 'v',
+            _ => '<',
+        };
+        set(self.map_pos(karel_corner), karel_ch);
+
+        let rows = rows.into_iter().map(|row| row.into_iter().collect::<String>()).collect::<Vec<_>>();
+        let mut arena = Arena::from_rows_and_key(&rows, obj_registry().map_key());
+        // Map key has no char for Karel on a beeper.
+        if self.beepers.contains(&karel_corner) {
+            let mark = obj_registry().obj("Mark", Facing::default()).unwrap();
+            arena.spawn_obj_under_movs_at(self.map_pos(karel_corner), mark);
+        }
+        arena
+    }
+}
+
+// Parse "(x, y)" at the start of txt, and return the rest.
+fn parse_coords(txt: &str) -> Result<((u16, u16), &str), String> {
+    let (inside, rest) = txt.trim().strip_prefix('(').and_then(|txt| txt.split_once(')'))
+        .ok_or_else(|| format!("Expected (x, y) in '{}'", txt.trim()))?;
+    let (x, y) = inside.split_once(',').ok_or_else(|| format!("Expected (x, y) in '({inside})'"))?;
+    let parse_num = |num: &str| num.trim().parse::<u16>().map_err(|_| format!("Unrecognised number '{}'", num.trim()));
+    Ok(((parse_num(x)?, parse_num(y)?), rest))
+}
+
+fn parse_dir(txt: &str) -> Result<Facing, String> {
+    match txt.trim().to_lowercase().as_str() {
+        "north" => Ok(Facing::N),
+        "east" => Ok(Facing::E),
+        "south" => Ok(Facing::S),
+        "west" => Ok(Facing::W),
+        other => Err(format!("Unrecognised direction '{other}'")),
+    }
+}
+
+/// Convert a Karel program to a prog. See the top of this file for what's supported.
+pub fn prog_from_karel(txt: &str) -> Result<Prog, String> {
+    let tokens = tokenize(txt)?;
+    let mut parser = KarelParser { tokens: &tokens, pos: 0 };
+    let instrs = parser.parse_statements(true)?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(Prog::from(instrs)),
+        Some(token) => Err(format!("Unexpected '{token}'")),
+    }
+}
+
+// Words and numbers, and punctuation as separate tokens. Comments are dropped.
+fn tokenize(txt: &str) -> Result<Vec<String>, String> {
+    let mut tokens = vec![];
+    for line in txt.lines() {
+        let mut chars = line.split("//").next().unwrap().chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                _ if ch.is_whitespace() => (),
+                '(' | ')' | '{' | '}' | ';' => tokens.push(ch.to_string()),
+                _ if ch.is_alphanumeric() || ch == '_' => {
+                    let mut word = ch.to_string();
+                    while let Some(next) = chars.next_if(|next| next.is_alphanumeric() || *next == '_') {
+                        word.push(next);
+                    }
+                    tokens.push(word);
+                },
+                _ => return Err(format!("Unexpected '{ch}'")),
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct KarelParser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+}
+
+impl KarelParser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Result<&str, String> {
+        let token = self.tokens.get(self.pos).ok_or("Program ended unexpectedly")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &[&str]) -> Result<(), String> {
+        for expected_token in expected {
+            let token = self.next()?;
+            if token != *expected_token {
+                return Err(format!("Expected '{expected_token}' but found '{token}'"));
+            }
+        }
+        Ok(())
+    }
+
+    // Statements until '}' or end of program. While is only allowed last in the whole program.
+    fn parse_statements(&mut self, top_level: bool) -> Result<Vec<Instr>, String> {
+        use prog_fn_ops::*;
+        let mut instrs = vec![];
+        while let Some(token) = self.peek() {
+            if token == "}" {
+                break;
+            }
+            match self.next()? {
+                "repeat" => {
+                    self.expect(&["("])?;
+                    let count = self.next()?.parse::<usize>().map_err(|_| "Expected number of repeats".to_string())?;
+                    self.expect(&[")", "{"])?;
+                    let body = self.parse_statements(false)?;
+                    self.expect(&["}"])?;
+                    instrs.extend(repeated(body, count)?);
+                },
+                "while" => {
+                    self.expect(&["(", "frontIsClear", "(", ")", ")", "{"])?;
+                    let body = self.parse_statements(false)?;
+                    self.expect(&["}"])?;
+                    if !top_level || self.peek().is_some() {
+                        return Err("while is only supported as the last statement".to_string());
+                    }
+                    let faithful = body.split_first().is_some_and(|(first, rest)|
+                        *first == x2(&[F]) && rest.iter().all(|instr| *instr == Mark || *instr == Unmark)
+                    );
+                    if !faithful {
+                        return Err("while body must be move() followed only by putBeeper() or pickBeeper()".to_string());
+                    }
+                    instrs.push(LOOP(&body));
+                },
+                command => {
+                    let instr = match command {
+                        "move" => x2(&[F]),
+                        "turnLeft" => L,
+                        "turnRight" => R,
+                        "putBeeper" => Mark,
+                        "pickBeeper" => Unmark,
+                        _ => return Err(format!("Unsupported command '{command}'")),
+                    };
+                    self.expect(&["(", ")", ";"])?;
+                    instrs.push(instr);
+                },
+            }
+        }
+        Ok(instrs)
+    }
+}
+
+// Body repeated count times, using the repeating instr for that count if there is one.
+fn repeated(body: Vec<Instr>, count: usize) -> Result<Vec<Instr>, String> {
+    use prog_fn_ops::*;
+    Ok(match count {
+        0 => vec![],
+        1 => body,
+        2 => vec![x2(&body)],
+        5 => vec![loop5(&body)],
+        3..=20 => vec![group(&(0..count).flat_map(|_| body.clone()).collect::<Vec<_>>())],
+        _ => return Err(format!("Can't repeat {count} times")),
+    })
+}
+
+/// Name to save progress on a Karel level under. The same for the same world and goal,
+/// whichever level number the import gets, and different for different exercises.
+pub fn karel_save_id(world_txt: &str, goal_txt: Option<&str>) -> String {
+    let txt = format!("{world_txt}\0{}", goal_txt.unwrap_or_default());
+    format!("karel-{:016x}", checksum(txt.as_bytes()))
+}
+
+/// Level from Karel files: the world, optionally the world as it should end up (its beepers
+/// give the marks to leave), and optionally a program to start with.
+///
+/// Without a goal world there's nothing to achieve, so the level is a sandbox.
+pub fn karel_level(world_txt: &str, goal_txt: Option<&str>, prog_txt: Option<&str>) -> Result<CodingArena<ProgpuzzMovementLogic>, String> {
+    use supply_ops::*;
+    let world = KarelWorld::from_text(world_txt).map_err(|err| format!("Karel world: {err}"))?;
+    let mut arena = world.to_arena();
+    if let Some(goal_txt) = goal_txt {
+        let goal = KarelWorld::from_text(goal_txt).map_err(|err| format!("Karel goal world: {err}"))?;
+        if (goal.w, goal.h) != (world.w, world.h) {
+            return Err("Karel goal world is a different size".to_string());
+        }
+        arena = arena.with_win_condition(WinCondition::MarkedExactly(goal.beeper_squares()));
+    }
+    let start_prog = match prog_txt {
+        Some(prog_txt) => prog_from_karel(prog_txt).map_err(|err| format!("Karel program: {err}"))?,
+        None => Prog::default(),
+    };
+
+    // Enough for most exercises, plus whatever the start prog uses.
+    let mut supply = vec![(F, 8), (L, 4), (R, 4), (Mark, 4), (Unmark, 4), (x2, 4), (group, 2), (loop5, 2), (LOOP, 2), (Else, 2), (IfMark, 2)];
+    for (op, count) in start_prog.supply_counts() {
+        match supply.iter_mut().find(|(bin_op, _)| *bin_op == op) {
+            Some((_, bin_count)) => *bin_count += count,
+            None => supply.push((op, count)),
+        }
+    }
+    Ok(CodingArena::new::<0>(arena, Coding::from_vec(&supply).with_start_prog(start_prog)))
+}
@@ -1,476 +1,621 @@
-use std::collections::HashMap;
-
 use super::objs::*;
+use super::movement_logic::ProgpuzzMovementLogic;
+use super::karel;
 
 use tile_engine::for_gamedata::*;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum ProgpuzzSceneId {
-    LevCodingArena(u16), // Level index from 1 to N. (Not 0 to N-1.)
-    Win,
-}
-
-#[derive(Debug)]
-pub struct ProgpuzzLevset {
-    pub current_levid: ProgpuzzSceneId,
-}
-
-impl ProgpuzzLevset {
-    pub fn new() -> ProgpuzzLevset {
-        let s_: Option<String> = tile_engine::infra::get_arg("--start-at=");
-        let i_: Option<u16> = s_.map(|s| s.parse::<u16>().ok()).flatten();
-        let starting_lev_num : u16 = i_.unwrap_or(1);
-        ProgpuzzLevset { current_levid: ProgpuzzSceneId::LevCodingArena(starting_lev_num) }
+/// All levels in order, with the win screen after the last, and any level imported
+/// from Karel files.
+pub fn level_set() -> LevelSet<ProgpuzzMovementLogic> {
+    let start_at = tile_engine::infra::get_arg("--start-at=").and_then(|lev_num| lev_num.parse::<u16>().ok());
+    let win = Scene::from_splash_string("Congratulations. You've completed all the levels. Press [enter] to play through again".to_string());
+    let (mut levels, mut progression) = (levels(), progression());
+    // Imported level is playable straight away, and keeps its progress whatever else is imported.
+    if let Some((level, save_id)) = karel_level() {
+        levels.push(level);
+        progression = progression.with_chapter("Karel").with_level(levels.len() as u16, UnlockRule::Start).with_save_id(&save_id);
     }
-
-    pub fn goto_level(&mut self, lev_idx: u16) {
-        self.current_levid = ProgpuzzSceneId::LevCodingArena(lev_idx);
+    let level_set = LevelSet::new(
+        levels.into_iter().map(|level| LevelEntry::new(Scene::CodingArena(level))).collect(),
+        win,
+    ).with_progression(progression);
+    match start_at {
+        Some(lev_num) => level_set.starting_at(lev_num),
+        None => level_set,
     }
+}
 
-    pub fn get_current_level(&self) -> u16 {
-        match self.current_levid {
-            ProgpuzzSceneId::LevCodingArena(levnum) => levnum,
-            ProgpuzzSceneId::Win => self.num_levels()
-        }
-    }
-
-    pub fn advance_scene(&mut self, continuation: SceneConclusion) {
-        self.current_levid = match (self.current_levid, continuation) {
-            (ProgpuzzSceneId::LevCodingArena(levnum), SceneConclusion::Succeed) if levnum >= self.levels().len() as u16 => ProgpuzzSceneId::Win,
-            (ProgpuzzSceneId::LevCodingArena(levnum), SceneConclusion::Succeed) => ProgpuzzSceneId::LevCodingArena(levnum+1),
-            (ProgpuzzSceneId::Win, SceneConclusion::Continue) => Self::new().current_levid,
-            _ => panic!()
-        };
-    }
-
-    fn levels(&self) -> Vec<CodingArena<super::movement_logic::ProgpuzzMovementLogic>> {
-        let progpuzz_key = HashMap::from([
-            // NB: Better to move this into obj? Combined with obj.char types?
-            (' ', vec![ new_floor() ]),
-            ('#', vec![ new_floor(), new_wall() ]),
-            ('^', vec![ new_floor(), new_progbot(CoordDelta::from_xy(0, -1)) ]),
-            ('>', vec![ new_floor(), new_progbot(CoordDelta::from_xy(1, 0)) ]),
-            ('v', vec![ new_floor(), new_progbot(CoordDelta::from_xy(0, 1)) ]),
-            ('<', vec![ new_floor(), new_progbot(CoordDelta::from_xy(-1, 0)) ]),
-            ('w', vec![ new_door_win() ]),
-            /*
-            */
-        ]);
-
-        // TODO: Separate debug levels..?
-        let lev1_coding = if std::env::args().collect::<Vec<_>>().contains(&"--debug-coding=A".to_string()) {
-            let mut coding;
-            {
-                use supply_ops::*;
-                coding = Coding::from_vec(&[
-                    (F, 1),
-                    (L, 1),
-                    (R, 1),
-                    (group, 1),
-                    (x2, 1),
-                ]);
-            }
-
-            {
-                use prog_ops::*;
-                coding.prog = Prog::from(vec![group, x2]);
-                coding.prog.instrs[0] = Instr::Parent(ParentOpcode::group, Prog::from("R"));
-                coding.prog.instrs[1] = Instr::Parent(ParentOpcode::x2, Prog::from("x2"));
-                coding.prog.instrs[1][0] = Instr::Parent(ParentOpcode::x2, Prog::from("F"));
-            }
-
-            coding
-        } else if std::env::args().collect::<Vec<_>>().contains(&"--debug-coding=B".to_string()) {
-            use supply_ops::*;
-            Coding::from_vec(&[
-                (F, 2),
-                (L, 2),
-                (R, 2),
-                (group, 2),
-                (x2, 2),
-                (LOOP, 2),
-                (Else, 2),
-            ])
-        } else {
-            Coding::from_vec(&[(F, 2),])
-        };
-
-        use supply_ops::*;
-        vec![
-            // TODO: Avoid needing to specify HEIGHT explicitly.
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#              #", // FF
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#     w        #",
-                    "#              #",
-                    "#     ^        #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "################",
-                ], progpuzz_key.clone()),
-                lev1_coding,
-            ),
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#              #",
-                    "#              #", // FFFLLLFF
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#       w      #",
-                    "#              #",
-                    "#              #",
-                    "#     ^        #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 5), (R, 1)]),
-            ),
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#              #",
-                    "#              #", // RFFLFFFRF
-                    "#              #", // ??
-                    "#              #",
-                    "#              #",
-                    "#     #  w     #",
-                    "#      #       #",
-                    "#     #        #",
-                    "#     ^  #     #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 6), (L, 1), (R, 2)]),
-            ),
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#              #",
-                    "#              #", // LOOP(FFRFL)
-                    "#              #", // Maybe introduce an easier loop first?
-                    "#        w     #",
-                    "#       #      #",
-                    "#              #",
-                    "#      #       #",
-                    "#              #",
-                    "#     #        #",
-                    "#        #     #",
-                    "#              #",
-                    "#     ^  #     #",
-                    "#              #",
-                    "#              #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 3), (L, 2), (R, 2), (LOOP, 1)]),
-            ),
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#      #       #",
-                    "#      #       #",
-                    "#     w#>      #",
-                    "#   ########   #",
-                    "#   #      #   #",
-                    "#   #      #   #",
-                    "#   #      #   #",
-                    "#   #      #   #",
-                    "#   #      #   #",
-                    "#   #      #   #",
-                    "#   ########   #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 9), (L, 1), (R, 1), (LOOP, 1)]),
-            ),
-
-
-
-
-
-
-
-
-
-
-
-
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "# ##############",
-                    " #>     #      #",
-                    "#w##### #      #",
-                    "# #     #      #",
-                    "# #   # #      #",
-                    "# #   # #      #",
-                    "# # #          #",
-                    "# # #          #",
-                    "#       #      #",
-                    " # #### #      #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 8), (L, 1), (R, 1), (LOOP, 1)]),
-            ),
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#              #",
-                    "#   #          #", // RFFFL LOOP(FFFFFFFFFFR)
-                    "#         #    #", // Now less brute-forcy?
-                    "#          #   #",
-                    "#   #       # ##",
-                    "# #           w#",
-                    "#      #########",
-                    "#  #           #",
-                    "#     #        #",
-                    "#          #   #",
-                    "#  #           #",
-                    "#^             #",
-                    "#        #     #",
-                    "#              #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 13), (L, 2), (R, 2), (LOOP, 1)]),
-            ),
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#              #",
-                    "##            ##",  // LOOP(FFFFFFFFFFFFFFFFFFFFFFFR)
-                    "#              #",  // Successfully requiring spiral?
-                    "#   #   # #    #",
-                    "#          #   #",
-                    "#     ##       #",
-                    "#  #     #     #",
-                    "#    #^# #     #",
-                    "#  #     #     #",
-                    "#  #  ###  #   #",
-                    "#          #   #",
-                    "#   ###   #    #",
-                    "####   ###   # #",
-                    "#w             #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 13), (L, 2), (R, 2), (LOOP, 1)]),
-            ),
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#              #",
-                    "#              #",  // Need to tweak x2 to make them definitely necessary?
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#    >         #",
-                    "#     #        #",
-                    "#     w        #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 3), (L, 0), (R, 2), (x2, 3)]),
-            ),
-            // CodingArena::new::<16>(
-            //     Arena::from_map_and_key(&[
-            //         "################",
-            //         "#              #",  // For this and L11 review number of F and x2 available..
-            //         "#              #",  // Did Mum solve this level this way? I can't remember. I can't.
-            //         "#              #",
-            //         "#              #",
-            //         "#              #",
-            //         "# #            #",
-            //         "###            #",
-            //         "#w#   v        #",
-            //         "# #            #",
-            //         "# #            #",
-            //         "#              #",
-            //         "#              #",
-            //         "#              #",
-            //         "#              #",
-            //         "################",
-            //     ], progpuzz_key.clone()),
-            //     Coding::from_vec(&[(F, 6), (L, 3), (R, 0), (x2, 5)]),
-            // ),
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#              #",
-                    "#              #",  // For this and L10 review number of F and x2 available..
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "# #            #",
-                    "# #            #",
-                    "#w#   v        #",
-                    "# #            #",
-                    "# #            #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "#              #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 6), (L, 3), (R, 0), (x2, 5)]),
-            ),
-
-
-
-
-
-
-
-
-
-
-
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#              #",  // Need more instr in loop, or a group?
-                    "#              #",  // LOOP(F ELSE(RFFL))
-                    "#              #",
-                    "#              #",
-                    "# ############ #",
-                    "#              #",
-                    "#     # #w#    #",
-                    "#     # # #    #",
-                    "#     # # #    #",
-                    "#     # # #    #",
-                    "#     # # #    #",
-                    "#     # # #    #",
-                    "#     # # #    #",
-                    "#     #^# #    #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 5), (L, 2), (R, 2), (Else, 1), (LOOP, 1)]),
-            ),
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#              #",  // LOOP(F ELSE(R))
-                    "#              #",  // Example of else. Does it need to be any more restricted?
-                    "#              #",
-                    "#       #      #",
-                    "#       #      #",
-                    "#       #      #",
-                    "#   ^   #w     #",
-                    "#       #      #",
-                    "#       #      #",
-                    "#       #      #",
-                    "#       #      #",
-                    "#       #      #",
-                    "#       #      #",
-                    "#       #      #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 2), (L, 2), (R, 2), (Else, 1), (LOOP, 1)]),
-            ),
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#              #",
-                    "#>            ##",  // LOOP(F ELSE(R) F ELSE(LF) )
-                    "#  ###         #",  // Should it show an easier path?
-                    "#              #",
-                    "###            #",
-                    "#            ###",
-                    "#      ##      #",
-                    "#      ##      #",
-                    "#            # #",
-                    "#####        # #",
-                    "#            # #",
-                    "####         # #",
-                    "#   w       ## #",
-                    "#              #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 4), (L, 2), (R, 2), (Else, 4), (LOOP, 1)]),
-            ),
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#>             #",
-                    "############ ###", // loop(group(L,F),else(R,F),else(R,F))
-                    "#         #  # #", // Difficult use of else?
-                    "#### ###### ## #",
-                    "#         # #  #",
-                    "# #######     ##",
-                    "# #     # #### #",
-                    "# # ###        #",
-                    "# # ##  # ######",
-                    "# # w####      #",
-                    "# ####   ####  #",
-                    "# #    #       #",
-                    "# ## ###########",
-                    "#  #           #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 4), (L, 2), (R, 2), (Else, 2), (LOOP, 1), (group, 1)]),
-            ),
-            CodingArena::new::<16>(
-                Arena::from_map_and_key(&[
-                    "################",
-                    "#              #",
-                    "# ############ #", // LOOP[R,F,F,Else[L,F],L,F,L,F,Else[L,F,L,LOOP[F]],R,R,F]
-                    "# #          # #",
-                    "# #          # #",
-                    "# #    w     # #",
-                    "# #          # #",
-                    "# #          # #",
-                    "# #          # #",
-                    "# #          # #",
-                    "# #          # #",
-                    "# #          # #",
-                    "#^#          # #",
-                    "####### ###### #",
-                    "#              #",
-                    "################",
-                ], progpuzz_key.clone()),
-                Coding::from_vec(&[(F, 8), (L, 6), (R, 6), (Else, 4), (LOOP, 2), (group, 2)]),
-            ),
-        ]
-    }
+/// Chapters, in order, by which instrs they introduce. Each level unlocks the next.
+pub fn progression() -> Progression {
+    let after = |lev_idx| UnlockRule::AfterAny(vec![lev_idx]);
+    Progression::default()
+        .with_chapter("First steps")
+        .with_level(1, UnlockRule::Start)
+        .with_level(2, after(1))
+        .with_level(3, after(2))
+        .with_chapter("Loops")
+        .with_level(4, after(3))
+        .with_level(5, after(4))
+        .with_level(6, after(5))
+        .with_level(7, after(6))
+        .with_level(8, after(7))
+        .with_chapter("Doubling")
+        .with_level(9, after(8))
+        .with_level(10, after(9))
+        .with_chapter("Else")
+        .with_level(11, after(10))
+        .with_level(12, after(11))
+        .with_level(13, after(12))
+        .with_level(14, after(13))
+        .with_level(15, after(14))
+        .with_chapter("Objectives")
+        .with_level(16, after(15))
+        .with_chapter("Marking")
+        .with_level(17, after(16))
+        .with_level(18, after(17))
+        .with_chapter("Edges")
+        .with_level(19, after(18))
+        .with_chapter("Debugging")
+        .with_level(20, after(19))
+        .with_chapter("Budget")
+        .with_level(21, after(20))
+}
 
-    pub fn num_levels(&self) -> u16 {
-        self.levels().len() as u16
-    }
+/// Level imported from Karel the Robot files named on the command line, if any:
+/// --karel-world=FILE, and optionally --karel-goal=FILE and --karel-prog=FILE.
+/// See karel.rs for the formats. Also the id to save its progress under.
+///
+/// None, after logging why, if the files can't be read or imported.
+fn karel_level() -> Option<(CodingArena<ProgpuzzMovementLogic>, String)> {
+    let world_path = tile_engine::infra::get_arg("--karel-world=")?;
+    let import = || -> Result<_, String> {
+        let read = |path: String| std::fs::read_to_string(&path).map_err(|err| format!("Error reading {path}: {err}"));
+        let world = read(world_path)?;
+        let goal = tile_engine::infra::get_arg("--karel-goal=").map(read).transpose()?;
+        let prog = tile_engine::infra::get_arg("--karel-prog=").map(read).transpose()?;
+        let level = karel::karel_level(&world, goal.as_deref(), prog.as_deref())?;
+        Ok((level, karel::karel_save_id(&world, goal.as_deref())))
+    };
+    import().inspect_err(|err| log::error!("Not adding Karel level: {err}")).ok()
+}
 
-    pub fn load_scene(&self) -> Scene<super::movement_logic::ProgpuzzMovementLogic> {
-        // NB: Would like to implement thin walls between squares, not walls filling whole squares.
-        match self.current_levid {
-            ProgpuzzSceneId::LevCodingArena(n) => Scene::CodingArena(self.levels()[n as usize -1].clone()),
-            ProgpuzzSceneId::Win => {
-                Scene::from_splash_string("Congratulations. You've completed all the levels. Press [enter] to play through again".to_string())
-            },
-        }
-    }
+fn levels() -> Vec<CodingArena<ProgpuzzMovementLogic>> {
+    let progpuzz_key = obj_registry().map_key();
+
+    use supply_ops::*;
+    vec![
+        // TODO: Avoid needing to specify HEIGHT explicitly.
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #", // FF
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#     w        #",
+                "#              #",
+                "#     ^        #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 2),]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",
+                "#              #", // FFFLLLFF
+                "#              #",
+                "#              #",
+                "#              #",
+                "#       w      #",
+                "#              #",
+                "#              #",
+                "#     ^        #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 5), (R, 1)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",
+                "#              #", // RFFLFFFRF
+                "#              #", // ??
+                "#              #",
+                "#              #",
+                "#     #  w     #",
+                "#      #       #",
+                "#     #        #",
+                "#     ^  #     #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 6), (L, 1), (R, 2)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",
+                "#              #", // LOOP(FFRFL)
+                "#              #", // Maybe introduce an easier loop first?
+                "#        w     #",
+                "#       #      #",
+                "#              #",
+                "#      #       #",
+                "#              #",
+                "#     #        #",
+                "#        #     #",
+                "#              #",
+                "#     ^  #     #",
+                "#              #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 3), (L, 2), (R, 2), (LOOP, 1)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#      #       #",
+                "#      #       #",
+                "#     w#>      #",
+                "#   ########   #",
+                "#   #      #   #",
+                "#   #      #   #",
+                "#   #      #   #",
+                "#   #      #   #",
+                "#   #      #   #",
+                "#   #      #   #",
+                "#   ########   #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 9), (L, 1), (R, 1), (LOOP, 1)]),
+        ),
+
+
+
+
+
+
+
+
+
+
+
+
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "# ##############",
+                " #>     #      #",
+                "#w##### #      #",
+                "# #     #      #",
+                "# #   # #      #",
+                "# #   # #      #",
+                "# # #          #",
+                "# # #          #",
+                "#       #      #",
+                " # #### #      #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 8), (L, 1), (R, 1), (LOOP, 1)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",
+                "#   #          #", // RFFFL LOOP(FFFFFFFFFFR)
+                "#         #    #", // Now less brute-forcy?
+                "#          #   #",
+                "#   #       # ##",
+                "# #           w#",
+                "#      #########",
+                "#  #           #",
+                "#     #        #",
+                "#          #   #",
+                "#  #           #",
+                "#^             #",
+                "#        #     #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 13), (L, 2), (R, 2), (LOOP, 1)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",
+                "##            ##",  // LOOP(FFFFFFFFFFFFFFFFFFFFFFFR)
+                "#              #",  // Successfully requiring spiral?
+                "#   #   # #    #",
+                "#          #   #",
+                "#     ##       #",
+                "#  #     #     #",
+                "#    #^# #     #",
+                "#  #     #     #",
+                "#  #  ###  #   #",
+                "#          #   #",
+                "#   ###   #    #",
+                "####   ###   # #",
+                "#w             #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 13), (L, 2), (R, 2), (LOOP, 1)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",
+                "#              #",  // Need to tweak x2 to make them definitely necessary?
+                "#              #",
+                "#              #",
+                "#              #",
+                "#    >         #",
+                "#     #        #",
+                "#     w        #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 3), (L, 0), (R, 2), (x2, 3)]),
+        ),
+        // CodingArena::new::<16>(
+        //     Arena::from_map_and_key(&[
+        //         "################",
+        //         "#              #",  // For this and L11 review number of F and x2 available..
+        //         "#              #",  // Did Mum solve this level this way? I can't remember. I can't.
+        //         "#              #",
+        //         "#              #",
+        //         "#              #",
+        //         "# #            #",
+        //         "###            #",
+        //         "#w#   v        #",
+        //         "# #            #",
+        //         "# #            #",
+        //         "#              #",
+        //         "#              #",
+        //         "#              #",
+        //         "#              #",
+        //         "################",
+        //     ], progpuzz_key.clone()),
+        //     Coding::from_vec(&[(F, 6), (L, 3), (R, 0), (x2, 5)]),
+        // ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",
+                "#              #",  // For this and L10 review number of F and x2 available..
+                "#              #",
+                "#              #",
+                "#              #",
+                "# #            #",
+                "# #            #",
+                "#w#   v        #",
+                "# #            #",
+                "# #            #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 6), (L, 3), (R, 0), (x2, 5)]),
+        ),
+
+
+
+
+
+
+
+
+
+
+
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",  // Need more instr in loop, or a group?
+                "#              #",  // LOOP(F ELSE(RFFL))
+                "#              #",
+                "#              #",
+                "# ############ #",
+                "#              #",
+                "#     # #w#    #",
+                "#     # # #    #",
+                "#     # # #    #",
+                "#     # # #    #",
+                "#     # # #    #",
+                "#     # # #    #",
+                "#     # # #    #",
+                "#     #^# #    #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 5), (L, 2), (R, 2), (Else, 1), (LOOP, 1)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",  // LOOP(F ELSE(R))
+                "#              #",  // Example of else. Second map stops a route just for the first.
+                "#              #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#   ^   #w     #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 2), (L, 2), (R, 2), (Else, 1), (LOOP, 1)]),
+        ).with_test_arena(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",  // Same prog, starting from somewhere else.
+                "#              #",
+                "# ^            #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #w     #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "################",
+            ], progpuzz_key.clone()),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",
+                "#>            ##",  // LOOP(F ELSE(R) F ELSE(LF) )
+                "#  ###         #",  // Should it show an easier path?
+                "#              #",
+                "###            #",
+                "#            ###",
+                "#      ##      #",
+                "#      ##      #",
+                "#            # #",
+                "#####        # #",
+                "#            # #",
+                "####         # #",
+                "#   w       ## #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 4), (L, 2), (R, 2), (Else, 4), (LOOP, 1)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#>             #",
+                "############ ###", // loop(group(L,F),else(R,F),else(R,F))
+                "#         #  # #", // Difficult use of else?
+                "#### ###### ## #",
+                "#         # #  #",
+                "# #######     ##",
+                "# #     # #### #",
+                "# # ###        #",
+                "# # ##  # ######",
+                "# # w####      #",
+                "# ####   ####  #",
+                "# #    #       #",
+                "# ## ###########",
+                "#  #           #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 4), (L, 2), (R, 2), (Else, 2), (LOOP, 1), (group, 1)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",
+                "# ############ #", // LOOP[R,F,F,Else[L,F],L,F,L,F,Else[L,F,L,LOOP[F]],R,R,F]
+                "# #          # #",
+                "# #          # #",
+                "# #    w     # #",
+                "# #          # #",
+                "# #          # #",
+                "# #          # #",
+                "# #          # #",
+                "# #          # #",
+                "# #          # #",
+                "#^#          # #",
+                "####### ###### #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 8), (L, 6), (R, 6), (Else, 4), (LOOP, 2), (group, 2)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#  w           #",  // LOOP(F ELSE(R))
+                "#              #",  // Passes exit first, before collecting gems.
+                "#              #",
+                "#              #",
+                "#             *#",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#  ^           #",
+                "#       *      #",
+                "################",
+            ], progpuzz_key.clone()).with_win_condition(WinCondition::All(vec![WinCondition::CollectAll, WinCondition::ReachGoal])),
+            Coding::from_vec(&[(F, 2), (L, 2), (R, 2), (Else, 1), (LOOP, 1)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",  // LOOP(Mark F)
+                "#              #",  // Paint a line.
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#>             #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()).with_win_condition(WinCondition::marked_pattern(&[
+                "################",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#++++++++++++++#",
+            ])),
+            Coding::from_vec(&[(F, 1), (L, 1), (R, 1), (Mark, 1), (LOOP, 1)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",  // LOOP(F IfMark(R))
+                "# .         .  #",  // Marks show where to turn.
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "#     w     .  #",
+                "#              #",
+                "#              #",
+                "#              #",
+                "# ^            #",
+                "#              #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 1), (R, 1), (IfMark, 1), (LOOP, 1)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#       #      #",  // LOOP(F)
+                "#       #      #",  // Out one side, in the other.
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "     w  #  >    ",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "################",
+            ], progpuzz_key.clone()).with_edge_policy(EdgePolicy::Wrap),
+            Coding::from_vec(&[(F, 1), (LOOP, 1)]),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",  // LOOP#[F#, Else#[R]]
+                "#              #",  // Fix the bug: follows the wall the wrong way round.
+                "# ^            #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #w     #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "#       #      #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(L, 1), (R, 1)]).with_start_prog(Prog::from_text("LOOP#[F#, Else#[L]]")),
+        ),
+        CodingArena::new::<16>(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",  // LOOP(F ELSE(L))
+                "#              #",  // Like level 12 mirrored, but at most 4 instrs.
+                "#              #",
+                "#      #       #",
+                "#      #       #",
+                "#      #       #",
+                "#     w#   ^   #",
+                "#      #       #",
+                "#      #       #",
+                "#      #       #",
+                "#      #       #",
+                "#      #       #",
+                "#      #       #",
+                "#      #       #",
+                "################",
+            ], progpuzz_key.clone()),
+            Coding::from_vec(&[(F, 2), (L, 2), (R, 2), (Else, 1), (LOOP, 1)]).with_rule(ProgRule::MaxInstrs(4)),
+        ).with_test_arena(
+            Arena::from_map_and_key(&[
+                "################",
+                "#              #",  // Same prog, starting from somewhere else.
+                "#              #",
+                "#            ^ #",
+                "#      #       #",
+                "#      #       #",
+                "#      #       #",
+                "#      #       #",
+                "#      #       #",
+                "#      #       #",
+                "#      #       #",
+                "#     w#       #",
+                "#      #       #",
+                "#      #       #",
+                "#      #       #",
+                "################",
+            ], progpuzz_key.clone()),
+        ),
+    ]
 }
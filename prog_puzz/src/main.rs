@@ -17,6 +17,7 @@
 /// If I finish the game I will specify how the game is distributed.
 
 mod game_data;
+mod karel;
 mod levels;
 mod objs;
 mod movement_logic;
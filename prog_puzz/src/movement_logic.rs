@@ -49,7 +49,7 @@ pub enum ProgpuzzAI {
     Prog, // Controlled by program assembled by player.
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct ProgpuzzMovementLogic;
 
 impl BaseMovementLogic for ProgpuzzMovementLogic
@@ -81,13 +81,16 @@ impl BaseMovementLogic for ProgpuzzMovementLogic
     }
 
     fn move_mov(map: &mut Arena<Self>, mov: RosterIndex, _cmd: InputCmd) -> SceneContinuation {
+        // Instrs like IfMark are decided by what bot senses from where it is now.
+        let senses = Senses { on_mark: map.is_marked(map[mov].pos()) };
         let props = &mut map[mov].logical_props.custom_props;
         match props.ai {
             ProgpuzzAI::Prog => {
                 if props.about_to_exec_init_instr {
                     props.about_to_exec_init_instr = false;
+                    props.prog.skip_unmet_conditions(senses);
                 } else {
-                    props.prog.advance_next_instr();
+                    props.prog.advance_next_instr_sensing(senses);
 
                     if props.prog.finished() {
                         log::debug!("Bot reached end of program.");
@@ -105,22 +108,40 @@ impl BaseMovementLogic for ProgpuzzMovementLogic
                         match action_op {
                             // Move forward
                             ActionOpcode::F => {
-                                let target_pos = map[mov].pos() + map[mov].logical_props.dir;
-                                if map.passable(target_pos) {
-                                    log::debug!("Bot move F. {} -> {}", map[mov].pos(), target_pos);
+                                let pos = map[mov].pos();
+                                let target_pos = pos + map[mov].logical_props.dir;
+                                // Bot dies moving into anything deadly. No prog puzz objs are,
+                                // so currently that's only past the edge of a map with EdgePolicy::Deadly.
+                                if map.any_has_effect(target_pos, Effect::Kill) {
+                                    map.push_event(ArenaEvent::Died { obj: mov, pos });
+                                    return SceneContinuation::Break(SceneConclusion::Fail);
+                                } else if map.passable(target_pos) {
                                     map.move_obj_to(mov, target_pos);
                                 } else {
-                                    log::debug!("Bot blocked F. {} -/-> {}", map[mov].pos(), target_pos);
+                                    map.push_event(ArenaEvent::Blocked { obj: mov, pos, target: target_pos });
                                     map[mov].logical_props.custom_props.prog.curr_op_mut().unwrap().as_action_data_mut().blocked = true;
                                 }
                             },
                             ActionOpcode::L => {
                                 map[mov].logical_props.dir.rotate_l();
-                                log::debug!("Bot rotate L. {} -> {}", map[mov].logical_props.prev_dir , map[mov].logical_props.dir);
                             },
                             ActionOpcode::R => {
                                 map[mov].logical_props.dir.rotate_r();
-                                log::debug!("Bot rotate R. {} -> {}", map[mov].logical_props.prev_dir , map[mov].logical_props.dir);
+                            },
+                            ActionOpcode::Mark => {
+                                let pos = map[mov].pos();
+                                if map.is_marked(pos) {
+                                    map[mov].logical_props.custom_props.prog.curr_op_mut().unwrap().as_action_data_mut().blocked = true;
+                                } else {
+                                    let mark = super::objs::obj_registry().obj("Mark", Facing::default()).unwrap();
+                                    map.spawn_obj_under_movs_at(pos, mark);
+                                }
+                            },
+                            ActionOpcode::Unmark => {
+                                let pos = map[mov].pos();
+                                if !map.unmark(pos) {
+                                    map[mov].logical_props.custom_props.prog.curr_op_mut().unwrap().as_action_data_mut().blocked = true;
+                                }
                             },
                             ActionOpcode::No => {
                                 panic!();
@@ -132,12 +153,7 @@ impl BaseMovementLogic for ProgpuzzMovementLogic
                     },
                 }
 
-                // Conclude scene successfully if hero finds with goal.
-                if map.any_has_effect(map[mov].pos(), Effect::Win) {
-                    return SceneContinuation::Break(SceneConclusion::Succeed)
-                }
-
-                // Continue scene without concluding.
+                // Continue scene without concluding. Arena checks if hero has now won.
                 return SceneContinuation::Continue(());
             },
             ProgpuzzAI::Stay => {
@@ -6,17 +6,27 @@ use crate::movement_logic::{ProgpuzzCustomProps, ProgpuzzMovementLogic};
 
 use super::objs::*;
 
+pub fn new_obj(name: &str) -> FreeObj<ProgpuzzCustomProps> {
+    obj_registry().obj(name, Facing::default()).unwrap_or_else(|| panic!("Unknown obj type: {name}"))
+}
+
+pub fn new_progbot_with_prog(dir: Facing, prog: &Prog) -> FreeObj<ProgpuzzCustomProps> {
+    let mut bot = obj_registry().obj("Progbot", dir).unwrap();
+    bot.logical_props.custom_props.prog = prog.clone();
+    bot
+}
+
 fn basic_test_key() -> HashMap<char, Vec<FreeObj<crate::movement_logic::ProgpuzzCustomProps>>> {
     use prog_ops::*;
     let prog = Prog::from(vec![F,F,R,F]);
     HashMap::from([
-        (' ', vec![ new_floor() ]),
-        ('#', vec![ new_floor(), new_wall() ]),
-        ('^', vec![ new_floor(), new_progbot_with_prog(CoordDelta::from_xy(0, -1), &prog) ]),
-        ('>', vec![ new_floor(), new_progbot_with_prog(CoordDelta::from_xy(1, 0), &prog) ]),
-        ('v', vec![ new_floor(), new_progbot_with_prog(CoordDelta::from_xy(0, 1), &prog) ]),
-        ('<', vec![ new_floor(), new_progbot_with_prog(CoordDelta::from_xy(-1, 0), &prog) ]),
-        ('w', vec![ new_door_win() ]),
+        (' ', vec![ new_obj("Floor") ]),
+        ('#', vec![ new_obj("Floor"), new_obj("Wall") ]),
+        ('^', vec![ new_obj("Floor"), new_progbot_with_prog(Facing::N, &prog) ]),
+        ('>', vec![ new_obj("Floor"), new_progbot_with_prog(Facing::E, &prog) ]),
+        ('v', vec![ new_obj("Floor"), new_progbot_with_prog(Facing::S, &prog) ]),
+        ('<', vec![ new_obj("Floor"), new_progbot_with_prog(Facing::W, &prog) ]),
+        ('w', vec![ new_obj("Goal") ]),
     ])
 }
 
@@ -120,7 +130,7 @@ fn basic_move() {
     assert_eq!(ProgpuzzMovementLogic::get_active_idx(coding_arena(&state)).unwrap(), 2);
     assert_eq!(hero_prog(&state).unwrap_curr_op(), &R);
     assert_eq!(hero(&state).pos(), MapCoord::from_xy(4, 2));
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(1, 0));
+    assert_eq!(hero(&state).logical_props.dir, Facing::E);
 
     // F
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
@@ -148,7 +158,7 @@ fn test_group() {
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
     assert_eq!(hero_prog(&state).unwrap_curr_op(), &R);
 
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(1, 0));
+    assert_eq!(hero(&state).logical_props.dir, Facing::E);
 
     // group:F, first F
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None); // x2 instr unimplemented!()
@@ -165,7 +175,42 @@ fn test_group() {
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
     assert_eq!(hero_prog(&state).unwrap_curr_op(), &R);
 
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(0, 1));
+    assert_eq!(hero(&state).logical_props.dir, Facing::S);
+}
+
+#[test]
+fn replay_solution() {
+    initialise_logging_for_tests();
+
+    use supply_ops::*;
+    let lev = CodingArena::new::<16>(basic_map(0), Coding::from_vec(&[(F, 5), (R, 1)]));
+    // Solution must fit in level's supply.
+    assert!(lev.clone().into_replay(Prog::from_text("[R,R]")).is_err());
+
+    // Starts running straight away, and can't be edited.
+    let mut state = Scene::CodingArena(lev.clone().into_replay(Prog::from_text("[F,F,R,F,F,F]")).unwrap());
+    assert!(state.is_replay());
+    assert!(state.coding().is_none());
+    assert!(matches!(state, Scene::CodingArena(CodingArena{phase: CodingRunningPhase::Running, ..})));
+    assert_eq!(hero(&state).pos(), MapCoord::from_xy(4, 4));
+
+    for _ in 0..10 {
+        if coding_arena(&state).is_running() {
+            state.advance(InputCmd::Tick);
+        }
+    }
+    assert!(matches!(state, Scene::CodingArena(CodingArena{phase: CodingRunningPhase::Won, ..})));
+    // Replays aren't recorded as attempts, and don't go on to the next level.
+    assert_eq!(state.consume_outcome_to_store(), None);
+    state.advance(InputCmd::Continue);
+    assert_eq!(state.ready_for_next_level(), Some(SceneConclusion::Continue));
+
+    // Leaving part way through.
+    let mut state = Scene::CodingArena(lev.into_replay(Prog::from_text("[F,F]")).unwrap());
+    state.advance(InputCmd::Tick);
+    state.advance(InputCmd::Cancel);
+    assert_eq!(state.consume_outcome_to_store(), None);
+    assert_eq!(state.ready_for_next_level(), Some(SceneConclusion::Continue));
 }
 
 #[test]
@@ -184,7 +229,7 @@ fn repeat_x2() {
 
     // R
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(1, 0));
+    assert_eq!(hero(&state).logical_props.dir, Facing::E);
 
     assert_eq!(hero_prog(&state).unwrap_curr_op(), &R);
 
@@ -200,7 +245,7 @@ fn repeat_x2() {
 
     // R
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(0, 1));
+    assert_eq!(hero(&state).logical_props.dir, Facing::S);
     assert_eq!(hero_prog(&state).unwrap_curr_op(), &R);
 }
 
@@ -215,37 +260,37 @@ fn repeat_x2_rotate() {
 
     // Start running, no other effect
     state.advance(InputCmd::Continue); assert_eq!(state.ready_for_next_level(), None);
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(0, -1));
+    assert_eq!(hero(&state).logical_props.dir, Facing::N);
 
     // R
     assert_eq!(hero_prog(&state).unwrap_curr_op(), &R);
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(1, 0));
+    assert_eq!(hero(&state).logical_props.dir, Facing::E);
 
     // R
     assert_eq!(hero_prog(&state).unwrap_curr_op(), &R);
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(0, 1));
+    assert_eq!(hero(&state).logical_props.dir, Facing::S);
 
     // L
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
     assert_eq!(hero_prog(&state).unwrap_curr_op(), &L);
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(1, 0));
+    assert_eq!(hero(&state).logical_props.dir, Facing::E);
 
     // R
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
     assert_eq!(hero_prog(&state).unwrap_curr_op(), &R);
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(0, 1));
+    assert_eq!(hero(&state).logical_props.dir, Facing::S);
 
     // R
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
     // assert_eq!(hero_prog(&state).unwrap_curr_op(), &R);
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(-1, 0));
+    assert_eq!(hero(&state).logical_props.dir, Facing::W);
 
     // L
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
     // assert_eq!(hero_prog(&state).unwrap_curr_op(), &L);
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(0, 1));
+    assert_eq!(hero(&state).logical_props.dir, Facing::S);
 }
 
 #[test]
@@ -261,11 +306,11 @@ fn nested_repeat() {
     // Start running, no other effect
     state.advance(InputCmd::Continue); assert_eq!(state.ready_for_next_level(), None);
     assert_eq!(hero(&state).pos(), MapCoord::from_xy(4, 4));
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(0, -1));
+    assert_eq!(hero(&state).logical_props.dir, Facing::N);
 
     // R
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
-    assert_eq!(hero(&state).logical_props.dir, CoordDelta::from_xy(1, 0));
+    assert_eq!(hero(&state).logical_props.dir, Facing::E);
 
     // F
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
@@ -283,3 +328,538 @@ fn nested_repeat() {
     state.advance(InputCmd::Tick); assert_eq!(state.ready_for_next_level(), None);
     assert_eq!(hero(&state).pos(), MapCoord::from_xy(8, 4));
 }
+
+fn hero_events<'a>(state: &'a Scene<ProgpuzzMovementLogic>) -> &'a [ArenaEvent] {
+    match state {
+        Scene::CodingArena(CodingArena {curr_arena: Some(arena), .. }) => arena.events(),
+        _ => panic!("Can't find events. Arena may not be running."),
+    }
+}
+
+#[test]
+fn arena_events() {
+    initialise_logging_for_tests();
+
+    use prog_ops::*;
+    let mut state = get_basic_lev_with_prog(Prog::from(vec![L,F,F,F,F]));
+    state.advance(InputCmd::Continue);
+    let bot = coding_arena(&state).curr_arena.as_ref().unwrap().hero();
+
+    // L
+    state.advance(InputCmd::Tick);
+    assert_eq!(hero_events(&state), &[ArenaEvent::Rotated { obj: bot, from: Facing::N, to: Facing::W }]);
+
+    // F
+    state.advance(InputCmd::Tick);
+    assert_eq!(hero_events(&state), &[ArenaEvent::Moved { obj: bot, from: MapCoord::from_xy(4, 4), to: MapCoord::from_xy(3, 4) }]);
+
+    // F, F
+    state.advance(InputCmd::Tick);
+    state.advance(InputCmd::Tick);
+    assert_eq!(hero(&state).pos(), MapCoord::from_xy(1, 4));
+
+    // F, blocked by wall
+    state.advance(InputCmd::Tick);
+    assert_eq!(hero_events(&state), &[ArenaEvent::Blocked { obj: bot, pos: MapCoord::from_xy(1, 4), target: MapCoord::from_xy(0, 4) }]);
+}
+
+#[test]
+fn arena_events_reached_goal() {
+    initialise_logging_for_tests();
+
+    use prog_ops::*;
+    let mut state = get_basic_lev_with_prog(Prog::from(vec![F,F,R,F,F,F]));
+    state.advance(InputCmd::Continue);
+    for _ in 0..6 {
+        state.advance(InputCmd::Tick);
+    }
+    assert!(matches!(state, Scene::CodingArena(CodingArena{phase: CodingRunningPhase::Won, ..})));
+    assert!(matches!(hero_events(&state).last(), Some(ArenaEvent::ReachedGoal { pos, .. }) if *pos == MapCoord::from_xy(7, 2)));
+}
+
+#[test]
+fn arena_search() {
+    initialise_logging_for_tests();
+
+    let arena = basic_map(0);
+    let bot_pos = MapCoord::from_xy(4, 4);
+    let win_pos = MapCoord::from_xy(7, 2);
+
+    // Open room, so a straight line of sight and a path of manhattan length.
+    assert!(arena.line_of_sight(bot_pos, win_pos));
+    let path = arena.shortest_path(bot_pos, win_pos, |pos| arena.passable(pos)).unwrap();
+    assert_eq!(path.len(), 5);
+    assert_eq!(path.last(), Some(&win_pos));
+
+    // Walls block sight, and off-map squares are never reached.
+    assert!(!arena.line_of_sight(MapCoord::from_xy(0, 1), MapCoord::from_xy(0, 5)));
+    assert!(arena.line_of_sight(MapCoord::from_xy(1, 1), MapCoord::from_xy(12, 5)));
+    assert_eq!(arena.reachable_from(bot_pos, |pos| arena.passable(pos)).len(), 12 * 5);
+    assert_eq!(arena.shortest_path(bot_pos, MapCoord::from_xy(0, 0), |pos| arena.passable(pos)), None);
+}
+
+// Whether winning can involve reaching the exit, unlike e.g. just painting a pattern.
+fn needs_exit(win_condition: &WinCondition) -> bool {
+    match win_condition {
+        WinCondition::ReachGoal => true,
+        WinCondition::All(conds) | WinCondition::Any(conds) => conds.iter().any(needs_exit),
+        _ => false,
+    }
+}
+
+#[test]
+fn all_levels_have_reachable_exit() {
+    initialise_logging_for_tests();
+
+    for lev_idx in 1..=crate::levels::level_set().num_levels() {
+        let level = shipped_level(lev_idx);
+        for test_idx in 0..level.num_tests() {
+            let arena = level.test_arena(test_idx);
+            if !needs_exit(arena.win_condition()) {
+                continue;
+            }
+            let bot_pos = arena[arena.hero()].pos();
+            let reachable = arena.reachable_from(bot_pos, |pos| arena.passable(pos));
+            assert!(
+                arena.map_locs().any(|(x, y, loc)| loc.any_effect(Effect::Win) && reachable.contains(&MapCoord::from_xy(x, y))),
+                "No reachable exit in level {} map {}", lev_idx, test_idx + 1
+            );
+        }
+    }
+}
+
+// Level as shipped in the game, numbered from 1.
+fn shipped_level(lev_idx: u16) -> CodingArena<ProgpuzzMovementLogic> {
+    match crate::levels::level_set().load_level_scene(lev_idx) {
+        Some(Scene::CodingArena(level)) => level,
+        _ => panic!("No coding level {lev_idx}"),
+    }
+}
+
+// Run prog on level until it stops, or gives up.
+fn run_to_end(mut coding_arena: CodingArena<ProgpuzzMovementLogic>, prog: &str) -> CodingArena<ProgpuzzMovementLogic> {
+    coding_arena.coding.restore_prog(Prog::from_text(prog)).unwrap();
+    coding_arena.advance(InputCmd::Continue);
+    for _ in 0..200 {
+        if coding_arena.is_running() {
+            coding_arena.advance(InputCmd::Tick);
+        }
+    }
+    coding_arena
+}
+
+#[test]
+fn every_map_must_be_solved() {
+    initialise_logging_for_tests();
+
+    use supply_ops::*;
+    // Bot starts further from the goal in the second map.
+    let lev = CodingArena::new::<16>(basic_map(0), Coding::from_vec(&[(F, 6), (R, 1), (group, 1)]))
+        .with_test_arena(basic_map(1));
+    assert_eq!(lev.num_tests(), 2);
+    assert_eq!(lev.test_results, [None, None]);
+
+    // Route for first map only.
+    let ran = run_to_end(lev.clone(), "F,F,R,F,F,F");
+    assert_eq!(ran.phase, CodingRunningPhase::Died);
+    assert_eq!(ran.test_results, [Some(true), Some(false)]);
+    assert_eq!(ran.curr_test, 1);
+
+    // Fails first map, so second isn't run.
+    let ran = run_to_end(lev.clone(), "F,F,F,R,F,F,F");
+    assert_eq!(ran.phase, CodingRunningPhase::Died);
+    assert_eq!(ran.test_results, [Some(false), None]);
+
+    // Else and loops solve any map of a level, e.g. following the wall, in few instrs.
+    let level = shipped_level(12);
+    assert_eq!(level.num_tests(), 2);
+    let ran = run_to_end(level, "LOOP[F, Else[R]]");
+    assert_eq!(ran.phase, CodingRunningPhase::Won);
+    assert_eq!(ran.test_results, [Some(true), Some(true)]);
+    // Level 12 has no budget, so longer solutions saved before still run.
+    let longer = run_to_end(shipped_level(12), "LOOP[F, Else[R], F]");
+    assert!(longer.coding.rule_violations().is_empty());
+    assert_ne!(longer.phase, CodingRunningPhase::Coding);
+
+    // Same idea with a budget of 4 instrs.
+    let too_long = run_to_end(shipped_level(21), "LOOP[F, Else[L], F]");
+    assert_eq!(too_long.phase, CodingRunningPhase::Coding);
+    assert_eq!(too_long.test_results, [None, None]);
+    assert_eq!(too_long.coding.rule_violations(), ["Use at most 4 instrs, not 5."]);
+    let ran = run_to_end(shipped_level(21), "LOOP[F, Else[L]]");
+    assert_eq!(ran.phase, CodingRunningPhase::Won);
+    assert_eq!(ran.test_results, [Some(true), Some(true)]);
+}
+
+#[test]
+fn fix_the_bug_level() {
+    initialise_logging_for_tests();
+
+    let level = shipped_level(20);
+    assert_eq!(level.coding.prog, Prog::from_text("LOOP#[F#, Else#[L]]"));
+    // As given, the bot goes round the wrong way forever.
+    let ran = run_to_end(level.clone(), "LOOP#[F#, Else#[L]]");
+    assert_ne!(ran.phase, CodingRunningPhase::Won);
+    let ran = run_to_end(level.clone(), "LOOP#[F#, Else#[R]]");
+    assert_eq!(ran.phase, CodingRunningPhase::Won);
+    // Only the unlocked instr can be changed.
+    assert!(level.clone().coding.restore_prog(Prog::from_text("LOOP[F, Else[R]]")).is_err());
+}
+
+#[test]
+fn win_conditions() {
+    initialise_logging_for_tests();
+
+    use supply_ops::*;
+    use WinCondition::*;
+    let lev = |win_condition: WinCondition| {
+        let mut arena = basic_map(0).with_win_condition(win_condition);
+        arena.spawn_obj_at(5, 2, new_obj("Gem"));
+        CodingArena::new::<16>(arena, Coding::from_vec(&[(F, 6), (L, 1), (R, 1)]))
+    };
+    let hero_pos = |ran: &CodingArena<ProgpuzzMovementLogic>| {
+        let arena = ran.curr_arena.as_ref().unwrap();
+        arena[arena.hero()].pos()
+    };
+    let to_goal = "F,F,R,F,F,F";
+
+    // Gem is on the way to the goal, so picked up and removed from map.
+    let ran = run_to_end(lev(All(vec![CollectAll, ReachGoal])), to_goal);
+    assert_eq!(ran.phase, CodingRunningPhase::Won);
+    assert_eq!(ran.curr_arena.as_ref().unwrap().num_collectibles(), 0);
+    let mut ran = lev(All(vec![CollectAll, ReachGoal]));
+    ran.coding.restore_prog(Prog::from_text(to_goal)).unwrap();
+    ran.advance(InputCmd::Continue);
+    for _ in 0..4 {
+        ran.advance(InputCmd::Tick);
+    }
+    let bot = ran.curr_arena.as_ref().unwrap().hero();
+    assert!(ran.curr_arena.as_ref().unwrap().events().contains(&ArenaEvent::PickedUp { obj: bot, pos: MapCoord::from_xy(5, 2), name: "Gem".to_string() }));
+
+    // Reaching goal isn't enough with gems left.
+    let ran = run_to_end(lev(All(vec![CollectAll, ReachGoal])), "F,R,F,F,F,L,F");
+    assert_eq!(hero_pos(&ran), MapCoord::from_xy(7, 2));
+    assert_eq!(ran.phase, CodingRunningPhase::Died);
+
+    // Waypoints must be visited in order. Won on the way, before reaching goal.
+    let ran = run_to_end(lev(VisitInOrder(vec![MapCoord::from_xy(4, 2), MapCoord::from_xy(6, 2)])), to_goal);
+    assert_eq!(ran.phase, CodingRunningPhase::Won);
+    assert_eq!(hero_pos(&ran), MapCoord::from_xy(6, 2));
+    let ran = run_to_end(lev(VisitInOrder(vec![MapCoord::from_xy(6, 2), MapCoord::from_xy(4, 2)])), to_goal);
+    assert_eq!(ran.phase, CodingRunningPhase::Died);
+
+    // Must end at goal facing the right way.
+    let ran = run_to_end(lev(All(vec![ReachGoal, Facing(tile_engine::for_gamedata::Facing::N)])), to_goal);
+    assert_eq!(ran.phase, CodingRunningPhase::Died);
+    let ran = run_to_end(lev(Any(vec![ReachGoal, Facing(tile_engine::for_gamedata::Facing::W)])), "L");
+    assert_eq!(ran.phase, CodingRunningPhase::Won);
+
+    // Level only won by going round again after collecting every gem.
+    let ran = run_to_end(shipped_level(16), "LOOP[F, Else[R]]");
+    assert_eq!(ran.phase, CodingRunningPhase::Won);
+    assert!(ran.curr_arena.as_ref().unwrap().hero_trail().len() > 40);
+}
+
+#[test]
+fn marking() {
+    initialise_logging_for_tests();
+
+    use supply_ops::*;
+    let lev = || {
+        let mut arena = basic_map(0);
+        arena.spawn_obj_at(4, 3, new_obj("Mark"));
+        CodingArena::new::<16>(arena, Coding::from_vec(&[(F, 1), (L, 1), (R, 1), (Mark, 2), (Unmark, 2), (Else, 2)]))
+    };
+    let bot = |ran: &CodingArena<ProgpuzzMovementLogic>| {
+        let arena = ran.curr_arena.as_ref().unwrap();
+        arena[arena.hero()].clone()
+    };
+
+    // Unmarking an unmarked square is blocked, like marking a marked one.
+    let ran = run_to_end(lev(), "F, Unmark, Else[L], Unmark, Else[R]");
+    assert_eq!(bot(&ran).logical_props.dir, Facing::E);
+    assert_eq!(ran.curr_arena.as_ref().unwrap().marked_squares(), []);
+    let ran = run_to_end(lev(), "Mark, Mark, Else[L]");
+    assert_eq!(bot(&ran).logical_props.dir, Facing::W);
+    assert_eq!(ran.curr_arena.as_ref().unwrap().marked_squares(), [MapCoord::from_xy(4, 3), MapCoord::from_xy(4, 4)]);
+
+    // Mark goes under bot, so bot stays on top.
+    let arena = ran.curr_arena.as_ref().unwrap();
+    let bot_pos = MapCoord::from_xy(4, 4);
+    assert_eq!(arena.get(bot_pos).unwrap().len(), 3);
+    assert_eq!(arena.get(bot_pos).unwrap()[2].logical_props.name, "Progbot");
+    assert_eq!(arena[arena.hero()].pos(), bot_pos);
+
+    // Paint a pattern, and follow marks to the exit.
+    let ran = run_to_end(shipped_level(17), "LOOP[Mark, F]");
+    assert_eq!(ran.phase, CodingRunningPhase::Won);
+    assert_eq!(ran.curr_arena.as_ref().unwrap().marked_squares().len(), 14);
+    let ran = run_to_end(shipped_level(18), "LOOP[F, IfMark[R]]");
+    assert_eq!(ran.phase, CodingRunningPhase::Won);
+}
+
+const KAREL_WORLD: &str = "
+    Dimension: (4, 2)
+    Wall: (2, 1) east
+    Beeper: (3, 2) 1
+    Karel: (1, 1) east
+    BeeperBag: INFINITE
+";
+
+#[test]
+fn karel_world_import() {
+    use crate::karel::KarelWorld;
+
+    // Corners are every second square, with walls between them.
+    let world = KarelWorld::from_text(KAREL_WORLD).unwrap();
+    let arena = world.to_arena();
+    assert_eq!(arena.as_ascii_rows(), [
+        "#########",
+        "#    .  #",
+        "# # # # #",
+        "#>  #   #",
+        "#########",
+        "#########",
+        "#########",
+        "#########",
+        "#########",
+    ]);
+    assert_eq!(world.map_pos((3, 2)), MapCoord::from_xy(5, 1));
+    assert_eq!(world.beeper_squares(), [MapCoord::from_xy(5, 1)]);
+
+    assert!(KarelWorld::from_text("Dimension: (4, 2)").is_err());
+    assert!(KarelWorld::from_text("Dimension: (4, 2)\nKarel: (5, 1) east").is_err());
+    assert!(KarelWorld::from_text("Dimension: (4, 2)\nKarel: (1, 1) up").is_err());
+    assert!(KarelWorld::from_text("Dimension: (4, 2)\nKarel: (1, 1) east\nBeepers: (1, 1) 1").is_err());
+}
+
+#[test]
+fn karel_prog_import() {
+    initialise_logging_for_tests();
+    use crate::karel::{karel_level, karel_save_id, prog_from_karel};
+
+    let prog = prog_from_karel("
+        move(); // Only way past the wall is round it.
+        turnLeft();
+        move();
+        turnRight();
+        move();
+        pickBeeper();
+    ").unwrap();
+    assert_eq!(prog, Prog::from_text("x2[F], L, x2[F], R, x2[F], Unmark"));
+    assert_eq!(prog_from_karel("repeat (2) { move(); } repeat(3) { turnLeft(); }"), Ok(Prog::from_text("x2[x2[F]], group[L, L, L]")));
+    assert_eq!(prog_from_karel("turnLeft(); while (frontIsClear()) { move(); putBeeper(); }"), Ok(Prog::from_text("L, LOOP[x2[F], Mark]")));
+
+    // While can't be left, so only allowed at the end, and only if that leaves the world as Karel would.
+    assert!(prog_from_karel("while (frontIsClear()) { move(); } turnLeft();").is_err());
+    assert!(prog_from_karel("repeat (2) { while (frontIsClear()) { move(); } }").is_err());
+    assert!(prog_from_karel("while (frontIsClear()) { move(); turnLeft(); }").is_err());
+    assert!(prog_from_karel("while (frontIsClear()) { putBeeper(); move(); }").is_err());
+    assert!(prog_from_karel("while (frontIsClear()) { move(); move(); }").is_err());
+    assert!(prog_from_karel("while (frontIsClear()) { putBeeper(); }").is_err());
+    assert!(prog_from_karel("move()").is_err());
+    assert!(prog_from_karel("jump();").is_err());
+    assert!(prog_from_karel("move(); }").is_err());
+
+    // Goal world has the beeper picked up.
+    let goal = "Dimension: (4, 2)\nKarel: (3, 2) east";
+    let level = karel_level(KAREL_WORLD, Some(goal), Some("move(); turnLeft(); move(); turnRight(); move(); pickBeeper();")).unwrap();
+    assert_eq!(level.coding.prog, prog);
+    let ran = run_to_end(level.clone(), &prog.to_string());
+    assert_eq!(ran.phase, CodingRunningPhase::Won);
+    let ran = run_to_end(level, "x2[F], x2[F]");
+    assert_eq!(ran.phase, CodingRunningPhase::Died);
+    assert!(karel_level(KAREL_WORLD, Some("Dimension: (3, 2)\nKarel: (1, 1) east"), None).is_err());
+
+    // While marks every corner Karel moves to, and no others.
+    let world = "Dimension: (4, 1)\nKarel: (1, 1) east";
+    let goal = "Dimension: (4, 1)\nBeeper: (2, 1) 1\nBeeper: (3, 1) 1\nBeeper: (4, 1) 1\nKarel: (4, 1) east";
+    let level = karel_level(world, Some(goal), Some("while (frontIsClear()) { move(); putBeeper(); }")).unwrap();
+    let ran = run_to_end(level.clone(), &level.coding.prog.to_string());
+    assert_eq!(ran.phase, CodingRunningPhase::Won);
+    let goal_world = crate::karel::KarelWorld::from_text(goal).unwrap();
+    assert_eq!(ran.curr_arena.as_ref().unwrap().marked_squares(), goal_world.beeper_squares());
+
+    // Progress is saved by which exercise it is.
+    assert_eq!(karel_save_id(world, Some(goal)), karel_save_id(world, Some(goal)));
+    assert_ne!(karel_save_id(world, Some(goal)), karel_save_id(world, None));
+    assert_ne!(karel_save_id(world, None), karel_save_id(KAREL_WORLD, None));
+}
+
+#[test]
+fn progression_covers_all_levels() {
+    let progression = crate::levels::progression();
+    assert_eq!(progression.validate(), Ok(()));
+    assert_eq!(progression.num_levels(), crate::levels::level_set().num_levels());
+}
+
+#[test]
+fn level_set_advances() {
+    use prog_ops::*;
+    use std::collections::HashSet;
+    let lev = |prog: &Prog| match get_basic_lev_with_prog(prog.clone()) {
+        Scene::CodingArena(coding_arena) => coding_arena,
+        _ => unreachable!(),
+    };
+    let splash = |txt: &str| Scene::from_splash_string(txt.to_string());
+    let level_at = |level_set: &mut LevelSet<ProgpuzzMovementLogic>| match level_set.load_scene() {
+        Scene::CodingArena(coding_arena) => Some(coding_arena.coding.prog),
+        _ => None,
+    };
+    let (prog1, prog2) = (Prog::from(vec![F]), Prog::from(vec![R]));
+    let mut level_set = LevelSet::new(vec![
+        LevelEntry::new(Scene::CodingArena(lev(&prog1))).with_title("One").with_outro(splash("Well done")),
+        LevelEntry::new(Scene::CodingArena(lev(&prog2))).with_intro(splash("Two")).with_retry(splash("Try again")),
+    ], splash("Win")).with_opening(splash("Hello"));
+    let all_unlocked = HashSet::from([1, 2]);
+
+    assert_eq!(level_set.pos, LevelSetPos::Opening);
+    assert_eq!(level_set.get_current_level(), 1);
+    level_set.advance_scene(SceneConclusion::Continue, &all_unlocked);
+    assert_eq!(level_set.pos, LevelSetPos::Level(1));
+    assert_eq!(level_at(&mut level_set), Some(prog1.clone()));
+    assert_eq!(level_set.get_level_str(), "Level 1: One");
+
+    // Level without retry screen is simply restarted.
+    level_set.advance_scene(SceneConclusion::Fail, &all_unlocked);
+    assert_eq!(level_set.pos, LevelSetPos::Level(1));
+    level_set.advance_scene(SceneConclusion::Succeed, &all_unlocked);
+    assert_eq!(level_set.pos, LevelSetPos::Outro(1));
+    level_set.advance_scene(SceneConclusion::Continue, &all_unlocked);
+    assert_eq!(level_set.pos, LevelSetPos::Intro(2));
+    assert_eq!(level_set.get_current_level(), 2);
+    level_set.advance_scene(SceneConclusion::Continue, &all_unlocked);
+    assert_eq!(level_at(&mut level_set), Some(prog2.clone()));
+    level_set.advance_scene(SceneConclusion::Fail, &all_unlocked);
+    assert_eq!(level_set.pos, LevelSetPos::Retry(2));
+    level_set.advance_scene(SceneConclusion::Continue, &all_unlocked);
+    assert_eq!(level_set.pos, LevelSetPos::Level(2));
+    level_set.advance_scene(SceneConclusion::Succeed, &all_unlocked);
+    assert_eq!(level_set.pos, LevelSetPos::Win);
+    level_set.advance_scene(SceneConclusion::Continue, &all_unlocked);
+    assert_eq!(level_set.pos, LevelSetPos::Opening);
+
+    // Locked levels are skipped.
+    level_set.goto_level(1);
+    assert!(level_set.reload_needed());
+    assert_eq!(level_at(&mut level_set), Some(prog1));
+    assert!(!level_set.reload_needed());
+    level_set.advance_scene(SceneConclusion::Succeed, &all_unlocked);
+    level_set.advance_scene(SceneConclusion::Continue, &HashSet::from([1]));
+    assert_eq!(level_set.pos, LevelSetPos::Win);
+
+    assert!(level_set.load_level_scene(2).is_some());
+    assert!(level_set.load_level_scene(3).is_none());
+}
+
+// Map with no border walls. Bot at (2,2) facing north.
+fn get_edge_lev(edge_policy: EdgePolicy, prog: Prog) -> Scene<ProgpuzzMovementLogic> {
+    use supply_ops::*;
+    let arena = Arena::from_map_and_key(&[
+        "    w",
+        "     ",
+        "  ^  ",
+    ], basic_test_key()).with_edge_policy(edge_policy);
+    let mut coding = Coding::from_vec(&[(F, 3)]);
+    coding.prog = prog;
+    Scene::CodingArena(CodingArena::new::<3>(arena, coding))
+}
+
+#[test]
+fn edge_solid() {
+    initialise_logging_for_tests();
+
+    use prog_ops::*;
+    let mut state = get_edge_lev(EdgePolicy::Solid, Prog::from(vec![F,F,F]));
+    state.advance(InputCmd::Continue);
+    state.advance(InputCmd::Tick);
+    state.advance(InputCmd::Tick);
+    assert_eq!(hero(&state).pos(), MapCoord::from_xy(2, 0));
+
+    // Edge blocks like a wall.
+    state.advance(InputCmd::Tick);
+    assert_eq!(hero(&state).pos(), MapCoord::from_xy(2, 0));
+    assert!(matches!(hero_events(&state), [ArenaEvent::Blocked { target, .. }] if *target == MapCoord::from_xy(2, -1)));
+}
+
+#[test]
+fn edge_deadly() {
+    initialise_logging_for_tests();
+
+    use prog_ops::*;
+    let mut state = get_edge_lev(EdgePolicy::Deadly, Prog::from(vec![F,F,F]));
+    state.advance(InputCmd::Continue);
+    state.advance(InputCmd::Tick);
+    state.advance(InputCmd::Tick);
+    assert!(matches!(state, Scene::CodingArena(CodingArena{phase: CodingRunningPhase::Running, ..})));
+
+    state.advance(InputCmd::Tick);
+    assert!(matches!(state, Scene::CodingArena(CodingArena{phase: CodingRunningPhase::Died, ..})));
+}
+
+#[test]
+fn edge_wrap() {
+    initialise_logging_for_tests();
+
+    use prog_ops::*;
+    let mut state = get_edge_lev(EdgePolicy::Wrap, Prog::from(vec![F,F,F]));
+    state.advance(InputCmd::Continue);
+    state.advance(InputCmd::Tick);
+    state.advance(InputCmd::Tick);
+
+    // Off top edge, back in at bottom.
+    state.advance(InputCmd::Tick);
+    assert_eq!(hero(&state).pos(), MapCoord::from_xy(2, 2));
+    assert_eq!(hero_events(&state), &[ArenaEvent::Moved { obj: hero(&state).roster_idx(), from: MapCoord::from_xy(2, 0), to: MapCoord::from_xy(2, 2) }]);
+
+    // Paths can wrap too.
+    let arena = &coding_arena(&state).init_arena;
+    let path = arena.shortest_path(MapCoord::from_xy(0, 0), MapCoord::from_xy(4, 0), |pos| arena.passable(pos)).unwrap();
+    assert_eq!(path, vec![MapCoord::from_xy(4, 0)]);
+
+    // Exit is only reachable by wrapping round past the edge.
+    let ran = run_to_end(shipped_level(19), "LOOP[F]");
+    assert_eq!(ran.phase, CodingRunningPhase::Won);
+    assert_eq!(ran.curr_arena.as_ref().unwrap().hero_trail().len(), 11);
+}
+
+#[test]
+fn facing_conversions() {
+    for facing in Facing::ALL {
+        assert_eq!(Facing::try_from(CoordDelta::from(facing)), Ok(facing));
+    }
+    assert_eq!(Facing::try_from(CoordDelta::from_xy(2, 0)), Err(CoordDelta::from_xy(2, 0)));
+    assert_eq!(Facing::try_from(CoordDelta::from_xy(0, 0)), Err(CoordDelta::from_xy(0, 0)));
+
+    let mut facing = Facing::N;
+    facing.rotate_l();
+    assert_eq!(facing, Facing::W);
+    facing.reverse();
+    assert_eq!(facing, Facing::E);
+    assert_eq!(Facing::NE.rotated_by(-3), Facing::W);
+    assert!(Facing::SW.is_diagonal());
+    assert_eq!(MapCoord::from_xy(3, 3) + Facing::SE, MapCoord::from_xy(4, 4));
+}
+
+#[test]
+fn obj_registry_from_json() {
+    let registry = obj_registry();
+    let wall = registry.obj("Wall", Facing::default()).unwrap();
+    assert_eq!(wall.logical_props.pass, Pass::Solid);
+    let bot = registry.obj("Progbot", Facing::W).unwrap();
+    assert!(bot.logical_props.custom_props.is_hero());
+    assert_eq!(bot.logical_props.dir, Facing::W);
+    assert!(registry.obj("Unicorn", Facing::default()).is_none());
+
+    // Map key stacks objs lowest first, with facing.
+    let key = registry.map_key();
+    assert_eq!(key[&'#'].iter().map(|obj| obj.logical_props.name.as_str()).collect::<Vec<_>>(), ["Floor", "Wall"]);
+    assert_eq!(key[&'v'][1].logical_props.dir, Facing::S);
+
+    // Errors name what was wrong.
+    let no_ai = |_: &str| None::<ProgpuzzCustomProps>;
+    let err = ObjRegistry::from_json(r#"{"objs": [{"name": "Rock", "pass": "Squishy"}], "key": {}}"#, no_ai).unwrap_err();
+    assert!(err.contains("Squishy"), "{err}");
+    let err = ObjRegistry::from_json(r#"{"objs": [], "key": {"r": ["Rock"]}}"#, no_ai).unwrap_err();
+    assert!(err.contains("Rock"), "{err}");
+}
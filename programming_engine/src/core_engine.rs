@@ -21,22 +21,135 @@ struct Engine<GameData: BaseGameData> {
 
     /// Overarching ui. Instantiates different uis for different scenes.
     ui: Ui,
+
+    /// Scenes to go back to, most recent last. E.g. level under stats screen, under a replay.
+    paused_scenes: Vec<Scene<GameData::MovementLogic>>,
+
+    /// Prog text last stored in save game for current scene, to only save on changes.
+    stored_prog: Option<String>,
 }
 
 impl<GameData: game_data::BaseGameData> Engine<GameData> {
     pub fn new() -> Engine<GameData> {
         let mut game_data = GameData::new();
-        let scene = game_data.load_scene();
-        Engine::<GameData> {
+        Self::transfer_save_files(&mut game_data);
+        let scene = Self::first_scene(&mut game_data);
+        let mut engine = Engine::<GameData> {
             game_data,
             scene,
             ui: Ui::new(),
+            paused_scenes: vec![],
+            stored_prog: None,
+        };
+        engine.restore_prog();
+        engine
+    }
+
+    /// Choose profile first, if save game has profiles. Else go straight to first level.
+    fn first_scene(game_data: &mut GameData) -> Scene<GameData::MovementLogic> {
+        let save_game = game_data.save_game();
+        match save_game.current_profile() {
+            Some(profile) => {
+                let selected_idx = save_game.profiles().iter().position(|name| *name == profile).unwrap_or(0);
+                Scene::ProfilePicker(ProfilePicker::new(selected_idx))
+            },
+            None => game_data.load_scene(),
+        }
+    }
+
+    /// Import and/or export progress from the command line.
+    fn transfer_save_files(game_data: &mut GameData) {
+        if let Some(path) = get_arg("--import-save=") {
+            let result = std::fs::read_to_string(&path).map_err(|err| err.to_string())
+                .and_then(|blob| game_data.save_game().import_progress(&blob));
+            match result {
+                Ok(()) => log::info!("Imported progress from {path}"),
+                Err(err) => log::error!("Couldn't import progress from {path}: {err}"),
+            }
+        }
+        if let Some(path) = get_arg("--export-save=") {
+            let result = game_data.save_game().export_progress().ok_or("Nothing to export".to_string())
+                .and_then(|blob| std::fs::write(&path, blob + "\n").map_err(|err| err.to_string()));
+            match result {
+                Ok(()) => log::info!("Exported progress to {path}"),
+                Err(err) => log::error!("Couldn't export progress to {path}: {err}"),
+            }
+        }
+    }
+
+    /// Put back the prog the player last had in this level, if any.
+    fn restore_prog(&mut self) {
+        let lev_idx = self.game_data.get_current_level();
+        let saved_prog = self.game_data.save_game().get_current_prog(lev_idx);
+        let Some(coding) = self.scene.coding_mut() else {
+            self.stored_prog = None;
+            return;
+        };
+        if let Some(prog_txt) = saved_prog {
+            let restored = Prog::try_from_text(&prog_txt).and_then(|prog| coding.restore_prog(prog));
+            if let Err(err) = restored {
+                log::warn!("Not restoring saved prog for level {lev_idx}: {err}");
+            }
+        }
+        // Treat prog shown, start or restored, as the stored one, so it's only saved once edited.
+        self.stored_prog = Some(coding.prog.to_string());
+    }
+
+    /// Go back to level stats were shown from, or to level of a program chosen from its history.
+    fn leave_stats(&mut self) {
+        let Scene::Stats(stats) = &self.scene else {
+            return;
+        };
+        if let Some(prog_txt) = stats.chosen_prog.clone() {
+            let lev_idx = stats.lev_idx;
+            self.game_data.save_game().store_current_prog(lev_idx, &prog_txt);
+            // Loaded below, as when level chooser used.
+            self.game_data.goto_level(lev_idx);
+            self.paused_scenes.clear();
+        } else {
+            self.scene = self.paused_scenes.pop().expect("Stats shown from a level");
+        }
+    }
+
+    /// Show a replay of prog on level, in place of current scene.
+    fn start_replay(&mut self, lev_idx: u16, prog_txt: &str) {
+        let replay = match self.game_data.load_level_scene(lev_idx) {
+            Some(Scene::CodingArena(coding_arena)) => Prog::try_from_text(prog_txt)
+                .and_then(|prog| coding_arena.into_replay(prog)),
+            _ => Err("This level can't be replayed".to_string()),
+        };
+        match replay {
+            Ok(coding_arena) => {
+                let paused_scene = std::mem::replace(&mut self.scene, Scene::CodingArena(coding_arena));
+                self.paused_scenes.push(paused_scene);
+            },
+            Err(err) => {
+                log::warn!("Can't replay prog for level {lev_idx}: {err}");
+                if let Scene::Stats(stats) = &mut self.scene {
+                    stats.message = Some(format!("Can't replay that program: {err}"));
+                }
+            },
+        }
+    }
+
+    /// Save prog in progress if it has changed, so leaving the level doesn't lose it.
+    fn store_prog_if_changed(&mut self) {
+        let Some(prog_txt) = self.scene.coding().map(|coding| coding.prog.to_string()) else {
+            return;
+        };
+        if self.stored_prog.as_ref() != Some(&prog_txt) {
+            let lev_idx = self.game_data.get_current_level();
+            self.game_data.save_game().store_current_prog(lev_idx, &prog_txt);
+            self.stored_prog = Some(prog_txt);
         }
     }
 
     /// Collect input. Draw frame. Advance logical Engine state, if tick scheduled.
     /// NB: Move into Ui
     pub async fn do_frame(&mut self) {
+        // Before ui, in case level chooser changes current level.
+        self.store_prog_if_changed();
+
         self.ui.do_frame(&mut self.scene, &mut self.game_data).await;
 
         // Record any during scene
@@ -48,14 +161,40 @@ impl<GameData: game_data::BaseGameData> Engine<GameData> {
             );
         }
 
+        if self.scene.consume_stats_request() {
+            let lev_idx = self.game_data.get_current_level();
+            let paused_scene = std::mem::replace(&mut self.scene, Scene::Stats(Stats::new(lev_idx)));
+            self.paused_scenes.push(paused_scene);
+        }
+
+        if let Some((lev_idx, prog_txt)) = self.scene.consume_replay_request() {
+            self.start_replay(lev_idx, &prog_txt);
+        }
+
         // If scene concluded, calculcate next scene/level
         if let Some(scene_ending) = self.scene.ready_for_next_level() {
-            self.scene = self.game_data.load_next_scene(scene_ending);
+            match self.scene {
+                // Profile chosen, start playing.
+                Scene::ProfilePicker(_) => {
+                    self.scene = self.game_data.load_scene();
+                    self.restore_prog();
+                },
+                Scene::Stats(_) => self.leave_stats(),
+                // Back to screen replay was started from.
+                _ if self.scene.is_replay() => {
+                    self.scene = self.paused_scenes.pop().expect("Replay started from another scene");
+                },
+                _ => {
+                    self.scene = self.game_data.load_next_scene(scene_ending);
+                    self.restore_prog();
+                },
+            }
         }
 
         // If scene concluded, or level chooser used goto level, load new scene.
         if self.game_data.reload_needed() {
             self.scene = self.game_data.load_scene();
+            self.restore_prog();
         }
     }
 }
@@ -79,8 +218,9 @@ pub fn get_arg(prefix: &str) -> Option<String> {
 
 /// Arguments:
 ///  --rust-log=...
-///  --debug-coding=...
 ///  --start-at=...
+///  --import-save=FILE
+///  --export-save=FILE
 pub async fn run<GameData: game_data::BaseGameData>()
 {
     if let Some(log_opts) = get_arg("--rust-log=") {
@@ -4,6 +4,7 @@
 
 use super::scene::{Scene, Arena, SceneConclusion, CodingArena};
 use super::savegame::*;
+use super::progression::Progression;
 
 // TODO: Don't need to for the first two games, but can move Pass and
 // Effect in here. Or better, make a SimpleObjectInteractions type
@@ -59,10 +60,15 @@ pub trait BaseGameData : LevelChooser {
     // TODO: Would using RefCell be better than being mutable?
     fn load_scene(&mut self) -> Scene<Self::MovementLogic>;
 
+    /// Load scene for a level without going to it, e.g. to replay a stored solution.
+    /// None if the game can't load levels out of order.
+    fn load_level_scene(&mut self, _lev_idx: u16) -> Option<Scene<Self::MovementLogic>> {
+        None
+    }
+
+    // Levels are unlocked by the save game as they're solved, according to the game's progression.
     fn load_next_scene(&mut self, continuation: SceneConclusion) -> Scene<Self::MovementLogic> {
         self.advance_scene(continuation);
-        let new_lev = self.get_current_level();
-        self.save_game().unlock_level(new_lev);
         self.load_scene()
     }
 
@@ -82,6 +88,11 @@ pub trait LevelChooser {
         0
     }
 
+    // Chapters and unlock rules. By default each level unlocks the next.
+    fn progression(&self) -> Progression {
+        Progression::linear(self.num_levels())
+    }
+
     fn reload_needed(&self) -> bool {
         assert!(self.num_levels() == 0);
         false
@@ -91,6 +102,11 @@ pub trait LevelChooser {
         assert!(self.num_levels() == 0)
     }
 
+    // Name of level to show player, if it has one.
+    fn level_title(&self, _lev_idx: u16) -> Option<String> {
+        None
+    }
+
     // Get string to display current level, in games with Arena or CodingArena.
     fn get_level_str(&self) -> String {
         String::new()
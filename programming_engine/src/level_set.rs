@@ -0,0 +1,216 @@
+// Ordered list of levels, with optional scenes shown around each, and moving between them.
+//
+// Games supply the scenes. LevelSet works out which scene comes next and
+// answers what LevelChooser needs, so a game's BaseGameData can mostly delegate to it.
+//
+// Both engines have a copy of this file, as they don't share a crate. Keep the copies the same.
+
+use std::collections::HashSet;
+
+use crate::for_gamedata::BaseMovementLogic;
+use crate::progression::Progression;
+use crate::scene::{Scene, SceneConclusion};
+
+/// One level, and the scenes shown around it.
+#[derive(Clone, Debug)]
+pub struct LevelEntry<MovementLogic: BaseMovementLogic> {
+    pub title: Option<String>,
+    pub scene: Scene<MovementLogic>,
+    /// Shown before the level.
+    pub intro: Option<Scene<MovementLogic>>,
+    /// Shown after solving the level.
+    pub outro: Option<Scene<MovementLogic>>,
+    /// Shown after failing the level, before trying again.
+    pub retry: Option<Scene<MovementLogic>>,
+}
+
+impl<MovementLogic: BaseMovementLogic> LevelEntry<MovementLogic> {
+    pub fn new(scene: Scene<MovementLogic>) -> Self {
+        Self { title: None, scene, intro: None, outro: None, retry: None }
+    }
+
+    pub fn with_title(self, title: &str) -> Self {
+        Self { title: Some(title.to_string()), ..self }
+    }
+
+    pub fn with_intro(self, intro: Scene<MovementLogic>) -> Self {
+        Self { intro: Some(intro), ..self }
+    }
+
+    pub fn with_outro(self, outro: Scene<MovementLogic>) -> Self {
+        Self { outro: Some(outro), ..self }
+    }
+
+    pub fn with_retry(self, retry: Scene<MovementLogic>) -> Self {
+        Self { retry: Some(retry), ..self }
+    }
+}
+
+/// Which scene of a LevelSet is current. Levels are numbered from 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LevelSetPos {
+    /// Before the first level.
+    Opening,
+    Intro(u16),
+    Level(u16),
+    Outro(u16),
+    Retry(u16),
+    /// After the last level.
+    Win,
+}
+
+#[derive(Clone, Debug)]
+pub struct LevelSet<MovementLogic: BaseMovementLogic> {
+    levels: Vec<LevelEntry<MovementLogic>>,
+    opening: Option<Scene<MovementLogic>>,
+    win: Scene<MovementLogic>,
+    // Linear if not specified.
+    progression: Option<Progression>,
+    // Level to start at, and to play again from after winning.
+    start_lev: u16,
+    pub pos: LevelSetPos,
+    reload_needed: bool,
+}
+
+impl<MovementLogic: BaseMovementLogic> LevelSet<MovementLogic> {
+    pub fn new(levels: Vec<LevelEntry<MovementLogic>>, win: Scene<MovementLogic>) -> Self {
+        assert!(!levels.is_empty(), "Level set needs at least one level");
+        let mut level_set = Self {
+            levels,
+            opening: None,
+            win,
+            progression: None,
+            start_lev: 1,
+            pos: LevelSetPos::Opening,
+            reload_needed: false,
+        };
+        level_set.pos = level_set.restart_pos();
+        level_set
+    }
+
+    /// Scene shown before the first level, and again after winning.
+    pub fn with_opening(mut self, opening: Scene<MovementLogic>) -> Self {
+        self.opening = Some(opening);
+        self.pos = self.restart_pos();
+        self
+    }
+
+    pub fn with_progression(self, progression: Progression) -> Self {
+        Self { progression: Some(progression), ..self }
+    }
+
+    /// Start at a later level, skipping the opening. E.g. for testing.
+    pub fn starting_at(mut self, lev_idx: u16) -> Self {
+        assert!(self.entry(lev_idx).is_some(), "No level {lev_idx} to start at");
+        self.start_lev = lev_idx;
+        self.pos = self.pos_for_level(lev_idx);
+        self
+    }
+
+    pub fn entry(&self, lev_idx: u16) -> Option<&LevelEntry<MovementLogic>> {
+        self.levels.get((lev_idx as usize).checked_sub(1)?)
+    }
+
+    fn restart_pos(&self) -> LevelSetPos {
+        if self.opening.is_some() && self.start_lev == 1 {
+            LevelSetPos::Opening
+        } else {
+            self.pos_for_level(self.start_lev)
+        }
+    }
+
+    // First scene of level: its intro if it has one.
+    fn pos_for_level(&self, lev_idx: u16) -> LevelSetPos {
+        match self.entry(lev_idx) {
+            Some(LevelEntry { intro: Some(_), .. }) => LevelSetPos::Intro(lev_idx),
+            _ => LevelSetPos::Level(lev_idx),
+        }
+    }
+
+    // First scene of next level after solving one, or the win scene if there are no more.
+    fn pos_after_level(&self, lev_idx: u16, unlocked: &HashSet<u16>) -> LevelSetPos {
+        match self.progression().next_level(lev_idx, unlocked) {
+            Some(next_lev) => self.pos_for_level(next_lev),
+            None => LevelSetPos::Win,
+        }
+    }
+
+    /// Move to next scene given how the current one ended, and which levels are unlocked.
+    pub fn advance_scene(&mut self, continuation: SceneConclusion, unlocked: &HashSet<u16>) {
+        use LevelSetPos::*;
+        let entry = |lev_idx| self.entry(lev_idx).expect("Current level is in level set");
+        self.pos = match (self.pos, continuation) {
+            (Opening, _) => self.pos_for_level(self.start_lev),
+            (Win, _) => self.restart_pos(),
+            (Intro(lev_idx), _) => Level(lev_idx),
+            (Level(lev_idx), conclusion) if conclusion.solved() && entry(lev_idx).outro.is_some() => Outro(lev_idx),
+            (Level(lev_idx), conclusion) if conclusion.solved() => self.pos_after_level(lev_idx, unlocked),
+            (Level(lev_idx), conclusion) if conclusion.failed() && entry(lev_idx).retry.is_some() => Retry(lev_idx),
+            (Level(lev_idx), _) => Level(lev_idx),
+            (Outro(lev_idx), _) => self.pos_after_level(lev_idx, unlocked),
+            (Retry(lev_idx), _) => Level(lev_idx),
+        };
+        log::debug!("Level set advanced to {:?}", self.pos);
+    }
+
+    pub fn load_scene(&mut self) -> Scene<MovementLogic> {
+        use LevelSetPos::*;
+        self.reload_needed = false;
+        let entry = |lev_idx| self.entry(lev_idx).expect("Current level is in level set");
+        match self.pos {
+            Opening => self.opening.clone().expect("Level set has opening scene"),
+            Intro(lev_idx) => entry(lev_idx).intro.clone().expect("Level has intro scene"),
+            Level(lev_idx) => entry(lev_idx).scene.clone(),
+            Outro(lev_idx) => entry(lev_idx).outro.clone().expect("Level has outro scene"),
+            Retry(lev_idx) => entry(lev_idx).retry.clone().expect("Level has retry scene"),
+            Win => self.win.clone(),
+        }
+    }
+
+    /// Scene for a level, without going to it.
+    pub fn load_level_scene(&self, lev_idx: u16) -> Option<Scene<MovementLogic>> {
+        Some(self.entry(lev_idx)?.scene.clone())
+    }
+
+    pub fn num_levels(&self) -> u16 {
+        self.levels.len() as u16
+    }
+
+    /// Level being played, or about to be played from the opening.
+    pub fn get_current_level(&self) -> u16 {
+        use LevelSetPos::*;
+        match self.pos {
+            Intro(lev_idx) | Level(lev_idx) | Outro(lev_idx) | Retry(lev_idx) => lev_idx,
+            Opening => self.start_lev,
+            Win => self.num_levels(),
+        }
+    }
+
+    pub fn progression(&self) -> Progression {
+        self.progression.clone().unwrap_or_else(|| Progression::linear(self.num_levels()))
+    }
+
+    /// Whether goto_level was used since the scene was last loaded.
+    pub fn reload_needed(&self) -> bool {
+        self.reload_needed
+    }
+
+    /// Straight to the level itself, skipping any intro.
+    pub fn goto_level(&mut self, lev_idx: u16) {
+        log::debug!("Level set going to level {lev_idx}");
+        assert!(self.entry(lev_idx).is_some(), "No level {lev_idx} to go to");
+        self.pos = LevelSetPos::Level(lev_idx);
+        self.reload_needed = true;
+    }
+
+    pub fn level_title(&self, lev_idx: u16) -> Option<String> {
+        self.entry(lev_idx)?.title.clone()
+    }
+
+    pub fn get_level_str(&self) -> String {
+        match self.level_title(self.get_current_level()) {
+            Some(title) => format!("Level {}: {title}", self.get_current_level()),
+            None => format!("Level: {}", self.get_current_level()),
+        }
+    }
+}
@@ -12,11 +12,17 @@
 
 mod core_engine;
 mod game_data;
+mod level_set;
 mod logging;
 mod map_coords;
 mod savegame;
+mod progression;
+mod save_storage;
+mod save_export;
+mod share_code;
 mod scene;
 mod obj;
+mod obj_defs;
 mod ui;
 pub mod simple_custom_props;
 
@@ -27,13 +33,20 @@ pub use core_engine::run;
 // NB: Check which things ought to be exported, which shouldn't be needed.
 pub mod for_gamedata {
     pub use super::obj::*;
+    pub use super::obj_defs::*;
     pub use super::game_data::*;
+    pub use super::level_set::*;
     pub use super::scene::*;
     pub use super::map_coords::*;
     pub use super::simple_custom_props::*;
     pub use super::scene::Arena;
     pub use super::scene::arena::RosterIndex;
+    pub use super::scene::arena::EdgePolicy;
     pub use super::savegame::*;
+    pub use super::progression::*;
+    pub use super::save_storage::*;
+    pub use super::share_code::*;
+    pub use super::save_export::checksum;
 }
 
 pub mod infra {
@@ -1,4 +1,4 @@
-use super::map_coords::CoordDelta;
+use super::map_coords::Facing;
 use super::simple_custom_props;
 use super::for_gamedata::BaseCustomProps;
 
@@ -29,11 +29,11 @@ pub struct LogicalProps<CustomProps: BaseCustomProps> {
 
     // Internal status for ents which have a current movement direction.
     // Also used to calculate current facing direction, to rotate texture.
-    pub dir: CoordDelta,
+    pub dir: Facing,
 
     // Internal status for ents which have a current movement direction.
     // Also used to calculate current facing direction, to rotate texture.
-    pub prev_dir: CoordDelta,
+    pub prev_dir: Facing,
 
     // Custom properties used by movement logic.
     pub custom_props: CustomProps,
@@ -52,9 +52,9 @@ impl<CustomProps: BaseCustomProps> LogicalProps<CustomProps> {
         Self {
             name: "????".to_string(),
 
-            dir: CoordDelta::from_xy(0, 0),
+            dir: Facing::default(),
 
-            prev_dir: CoordDelta::from_xy(0, 0),
+            prev_dir: Facing::default(),
 
             custom_props: CustomProps::default(),
 
@@ -0,0 +1,229 @@
+// How levels group into chapters and unlock each other.
+//
+// Declared by each game alongside its levels. The save game uses it to unlock
+// levels as others are solved, the game to choose which level comes next, and
+// the level chooser to draw chapters and the links between levels.
+//
+// Both engines have a copy of this file, as they don't share a crate. Keep the copies the same.
+
+use std::collections::HashSet;
+
+/// When a level becomes playable.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnlockRule {
+    /// Playable from the start.
+    Start,
+    /// Once any one of these levels is solved. Several levels unlocking after the
+    /// same level make alternative branches, and one level after several joins them.
+    AfterAny(Vec<u16>),
+    /// Once this many non-bonus levels of a chapter are solved. Chapter is its
+    /// index in Progression::chapters.
+    ChapterSolved { chapter: usize, count: usize },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgressionLevel {
+    pub lev_idx: u16,
+    pub unlock: UnlockRule,
+    /// Optional extra level. Doesn't count towards solving its chapter, and isn't
+    /// gone to automatically after solving the level before.
+    pub bonus: bool,
+    /// Name progress is saved under instead of the level number, for levels which
+    /// aren't always the same, e.g. ones imported from files.
+    pub save_id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chapter {
+    pub title: String,
+    pub levels: Vec<ProgressionLevel>,
+}
+
+/// Chapters of levels, in the order they're meant to be played, and their unlock rules.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Progression {
+    pub chapters: Vec<Chapter>,
+}
+
+impl Progression {
+    /// One chapter where each level unlocks the next, as for games without chapters.
+    pub fn linear(num_levels: u16) -> Self {
+        (1..=num_levels).fold(Self::default().with_chapter(""), |progression, lev_idx| {
+            let unlock = if lev_idx == 1 {UnlockRule::Start} else {UnlockRule::AfterAny(vec![lev_idx - 1])};
+            progression.with_level(lev_idx, unlock)
+        })
+    }
+
+    /// Start a new chapter. Following levels are added to it.
+    pub fn with_chapter(mut self, title: &str) -> Self {
+        self.chapters.push(Chapter { title: title.to_string(), levels: vec![] });
+        self
+    }
+
+    pub fn with_level(self, lev_idx: u16, unlock: UnlockRule) -> Self {
+        self.with_progression_level(ProgressionLevel { lev_idx, unlock, bonus: false, save_id: None })
+    }
+
+    pub fn with_bonus_level(self, lev_idx: u16, unlock: UnlockRule) -> Self {
+        self.with_progression_level(ProgressionLevel { lev_idx, unlock, bonus: true, save_id: None })
+    }
+
+    /// Save progress on the level just added under save_id instead of its number.
+    pub fn with_save_id(mut self, save_id: &str) -> Self {
+        let level = self.chapters.last_mut().and_then(|chapter| chapter.levels.last_mut()).expect("Save id given before any level");
+        level.save_id = Some(save_id.to_string());
+        self
+    }
+
+    fn with_progression_level(mut self, level: ProgressionLevel) -> Self {
+        self.chapters.last_mut().expect("Level declared before any chapter").levels.push(level);
+        self
+    }
+
+    /// All levels in the order declared, with the index of their chapter.
+    pub fn levels(&self) -> impl Iterator<Item = (usize, &ProgressionLevel)> {
+        self.chapters.iter().enumerate()
+            .flat_map(|(chapter_idx, chapter)| chapter.levels.iter().map(move |level| (chapter_idx, level)))
+    }
+
+    pub fn num_levels(&self) -> u16 {
+        self.levels().count() as u16
+    }
+
+    pub fn find_level(&self, lev_idx: u16) -> Option<(usize, &ProgressionLevel)> {
+        self.levels().find(|(_, level)| level.lev_idx == lev_idx)
+    }
+
+    /// Check each level from 1 to num_levels is declared once, and rules refer to real levels.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut lev_idxs = self.levels().map(|(_, level)| level.lev_idx).collect::<Vec<_>>();
+        lev_idxs.sort();
+        if lev_idxs != (1..=self.num_levels()).collect::<Vec<_>>() {
+            return Err(format!("Levels should be 1 to {} each declared once, not {lev_idxs:?}", self.num_levels()));
+        }
+        for (_, level) in self.levels() {
+            let valid = match &level.unlock {
+                UnlockRule::Start => true,
+                UnlockRule::AfterAny(prereqs) => !prereqs.is_empty() && prereqs.iter().all(|prereq| lev_idxs.contains(prereq)),
+                UnlockRule::ChapterSolved { chapter, count } => self.chapters.get(*chapter)
+                    .is_some_and(|chapter| *count <= chapter.levels.iter().filter(|level| !level.bonus).count()),
+            };
+            if !valid {
+                return Err(format!("Level {} has an unlock rule which can never be met: {:?}", level.lev_idx, level.unlock));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether level's unlock rule is met, given which levels are solved.
+    pub fn is_unlockable(&self, lev_idx: u16, solved: &HashSet<u16>) -> bool {
+        let Some((_, level)) = self.find_level(lev_idx) else {
+            return false;
+        };
+        match &level.unlock {
+            UnlockRule::Start => true,
+            UnlockRule::AfterAny(prereqs) => prereqs.iter().any(|prereq| solved.contains(prereq)),
+            UnlockRule::ChapterSolved { chapter, count } => self.chapters.get(*chapter).is_some_and(|chapter|
+                chapter.levels.iter().filter(|level| !level.bonus && solved.contains(&level.lev_idx)).count() >= *count
+            ),
+        }
+    }
+
+    pub fn unlockable_levels(&self, solved: &HashSet<u16>) -> Vec<u16> {
+        self.levels().map(|(_, level)| level.lev_idx).filter(|lev_idx| self.is_unlockable(*lev_idx, solved)).collect()
+    }
+
+    /// Level to go on to after solving one: the next unlocked non-bonus level in order, if any.
+    pub fn next_level(&self, after: u16, unlocked: &HashSet<u16>) -> Option<u16> {
+        self.levels()
+            .skip_while(|(_, level)| level.lev_idx != after)
+            .skip(1)
+            .find(|(_, level)| !level.bonus && unlocked.contains(&level.lev_idx))
+            .map(|(_, level)| level.lev_idx)
+    }
+
+    /// Pairs of (earlier level, level it unlocks), for drawing. A chapter unlocking a
+    /// level is linked from the last non-bonus level in the chapter.
+    pub fn links(&self) -> Vec<(u16, u16)> {
+        self.levels().flat_map(|(_, level)| {
+            let from = match &level.unlock {
+                UnlockRule::Start => vec![],
+                UnlockRule::AfterAny(prereqs) => prereqs.clone(),
+                UnlockRule::ChapterSolved { chapter, .. } => self.chapters.get(*chapter).into_iter()
+                    .flat_map(|chapter| chapter.levels.iter().rev().find(|level| !level.bonus))
+                    .map(|level| level.lev_idx)
+                    .collect(),
+            };
+            from.into_iter().map(|from| (from, level.lev_idx))
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use UnlockRule::*;
+
+    // Two routes through first chapter, a bonus level, and a chapter opened by solving two levels.
+    fn branching() -> Progression {
+        Progression::default()
+            .with_chapter("Basics")
+            .with_level(1, Start)
+            .with_level(2, AfterAny(vec![1]))
+            .with_level(3, AfterAny(vec![1]))
+            .with_bonus_level(4, AfterAny(vec![2, 3]))
+            .with_chapter("Loops")
+            .with_level(5, ChapterSolved { chapter: 0, count: 2 })
+            .with_level(6, AfterAny(vec![5]))
+    }
+
+    fn set(lev_idxs: &[u16]) -> HashSet<u16> {
+        lev_idxs.iter().copied().collect()
+    }
+
+    #[test]
+    fn linear() {
+        let progression = Progression::linear(3);
+        assert_eq!(progression.validate(), Ok(()));
+        assert_eq!(progression.num_levels(), 3);
+        assert_eq!(progression.unlockable_levels(&set(&[])), [1]);
+        assert_eq!(progression.unlockable_levels(&set(&[1, 2])), [1, 2, 3]);
+        assert_eq!(progression.next_level(1, &set(&[1, 2])), Some(2));
+        assert_eq!(progression.next_level(3, &set(&[1, 2, 3])), None);
+        assert_eq!(progression.links(), [(1, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn branches_and_chapters() {
+        let progression = branching();
+        assert_eq!(progression.validate(), Ok(()));
+        assert_eq!(progression.find_level(5).map(|(chapter_idx, _)| chapter_idx), Some(1));
+
+        assert_eq!(progression.unlockable_levels(&set(&[1])), [1, 2, 3]);
+        // Either branch opens the bonus level.
+        assert_eq!(progression.unlockable_levels(&set(&[2])), [1, 4]);
+        assert_eq!(progression.unlockable_levels(&set(&[3])), [1, 4]);
+        // Bonus levels don't count towards the chapter.
+        assert_eq!(progression.unlockable_levels(&set(&[1, 4])), [1, 2, 3]);
+        assert_eq!(progression.unlockable_levels(&set(&[1, 3])), [1, 2, 3, 4, 5]);
+
+        // Bonus level is skipped when going on, as are locked levels.
+        assert_eq!(progression.next_level(3, &set(&[1, 2, 3, 4])), None);
+        assert_eq!(progression.next_level(3, &set(&[1, 2, 3, 4, 5])), Some(5));
+        assert_eq!(progression.next_level(1, &set(&[1, 3])), Some(3));
+
+        assert_eq!(progression.links(), [(1, 2), (1, 3), (2, 4), (3, 4), (3, 5), (5, 6)]);
+    }
+
+    #[test]
+    fn validate_rejects_bad_declarations() {
+        let missing = Progression::default().with_chapter("").with_level(1, Start).with_level(3, AfterAny(vec![1]));
+        assert!(missing.validate().is_err());
+        let repeated = Progression::linear(2).with_level(2, Start);
+        assert!(repeated.validate().is_err());
+        let unknown_prereq = Progression::default().with_chapter("").with_level(1, Start).with_level(2, AfterAny(vec![7]));
+        assert!(unknown_prereq.validate().is_err());
+        let too_many = Progression::linear(2).with_chapter("").with_level(3, ChapterSolved { chapter: 0, count: 3 });
+        assert!(too_many.validate().is_err());
+    }
+}
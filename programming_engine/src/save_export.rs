@@ -0,0 +1,182 @@
+// Export all save data to one text code, and import it back.
+//
+// Lets players move progress between browsers or machines, and testers send
+// their save to us. The code is "PROGSAVE1:" then a checksum then base64 json
+// of all save entries. On import the code is checked, then migrated as if it
+// had been loaded from storage.
+
+use std::collections::HashMap;
+
+use nanoserde::{DeJson, SerJson};
+
+use crate::savegame::*;
+
+const EXPORT_PREFIX: &str = "PROGSAVE1";
+
+/// Encode save entries as a single line of text.
+pub fn export_save_entries(entries: &SaveEntries) -> String {
+    let json = entries.clone().into_iter().collect::<HashMap<String, String>>().serialize_json();
+    format!("{EXPORT_PREFIX}:{:016x}:{}", checksum(json.as_bytes()), base64_encode(json.as_bytes()))
+}
+
+/// Decode and validate exported save entries, migrating them to the current version.
+///
+/// Err with a message for the player if the code is garbled, has been edited, or
+/// was made by a newer version of the game.
+pub fn import_save_entries(blob: &str) -> Result<SaveEntries, String> {
+    let mut fields = blob.trim().splitn(3, ':');
+    let (Some(prefix), Some(checksum_txt), Some(payload)) = (fields.next(), fields.next(), fields.next()) else {
+        return Err("That doesn't look like a save code.".to_string());
+    };
+    if prefix != EXPORT_PREFIX {
+        return Err("That doesn't look like a save code.".to_string());
+    }
+
+    let json_bytes = base64_decode(payload).ok_or("Save code is garbled. Was all of it copied?")?;
+    if u64::from_str_radix(checksum_txt, 16).ok() != Some(checksum(&json_bytes)) {
+        return Err("Save code is garbled or has been edited.".to_string());
+    }
+    let json = String::from_utf8(json_bytes).map_err(|_| "Save code is garbled.")?;
+    let entries: SaveEntries = HashMap::<String, String>::deserialize_json(&json)
+        .map_err(|_| "Save code is garbled.")?
+        .into_iter().collect();
+
+    let Some(version) = stored_version(&entries) else {
+        return Err("Save code doesn't contain any progress.".to_string());
+    };
+    if version_newer_than_current(&version) {
+        return Err(format!("Save code is from a newer version of the game ({version}). Update to import it."));
+    }
+    let migrated = migrate_save_entries(entries);
+    if migrated.get(VERSION_KEY).map(String::as_str) != Some(SAVE_VERSION) {
+        return Err(format!("Save code is from an unrecognised version of the game ({version})."));
+    }
+    for (lev_idx, key) in level_keys(&migrated, "_record") {
+        if LevelRecord::deserialize_json(&migrated[&key]).is_err() {
+            return Err(format!("Save code has unreadable progress for level {lev_idx}."));
+        }
+    }
+    Ok(migrated)
+}
+
+fn version_newer_than_current(version: &str) -> bool {
+    let parse = |txt: &str| txt.split('.').map(|part| part.parse::<u32>().ok()).collect::<Option<Vec<_>>>();
+    match (parse(version), parse(SAVE_VERSION)) {
+        (Some(version), Some(current)) => version > current,
+        _ => false,
+    }
+}
+
+/// FNV-1a. Catches accidental edits and truncation, not deliberate tampering.
+pub fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn base64_encode(bytes: &[u8]) -> String {
+    let mut txt = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (idx, byte)| bits | (*byte as u32) << (16 - 8 * idx));
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                txt.push(BASE64_CHARS[(bits >> (18 - 6 * idx) & 0x3f) as usize] as char);
+            } else {
+                txt.push('=');
+            }
+        }
+    }
+    txt
+}
+
+pub(crate) fn base64_decode(txt: &str) -> Option<Vec<u8>> {
+    let txt = txt.trim_end_matches('=');
+    let mut bytes = vec![];
+    for chunk in txt.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut bits = 0u32;
+        for (idx, ch) in chunk.iter().enumerate() {
+            let val = BASE64_CHARS.iter().position(|base64_ch| base64_ch == ch)? as u32;
+            bits |= val << (18 - 6 * idx);
+        }
+        for idx in 0..chunk.len() - 1 {
+            bytes.push((bits >> (16 - 8 * idx)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(pairs: &[(&str, &str)]) -> SaveEntries {
+        pairs.iter().map(|(key, val)| (key.to_string(), val.to_string())).collect()
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        assert_eq!(base64_encode(b"Man"), "TWFu");
+        assert_eq!(base64_encode(b"Ma"), "TWE=");
+        assert_eq!(base64_encode(b"M"), "TQ==");
+        for txt in ["", "M", "Ma", "Man", "Many hands"] {
+            assert_eq!(base64_decode(&base64_encode(txt.as_bytes())).unwrap(), txt.as_bytes());
+        }
+        assert_eq!(base64_decode("TW!u"), None);
+    }
+
+    #[test]
+    fn export_import_round_trip() {
+        let saved = entries(&[
+            ("version", SAVE_VERSION),
+            ("Level1_record", r#"{"unlocked":true,"attempts":2,"current_prog":"[F,x2[F]]"}"#),
+            ("Level2_record", r#"{"unlocked":true}"#),
+        ]);
+        let blob = export_save_entries(&saved);
+        assert!(blob.starts_with("PROGSAVE1:"));
+        assert!(!blob.contains(char::is_whitespace));
+        assert_eq!(import_save_entries(&blob), Ok(saved.clone()));
+        // Surrounding whitespace from copy and paste is ignored.
+        assert_eq!(import_save_entries(&format!("  {blob}\n")), Ok(saved));
+    }
+
+    #[test]
+    fn import_migrates_old_versions() {
+        let blob = export_save_entries(&entries(&[("Level1", "unlocked"), ("Level2", "unlocked")]));
+        let imported = import_save_entries(&blob).unwrap();
+        assert_eq!(imported.keys().collect::<Vec<_>>(), ["Level1_record", "Level2_record", "version"]);
+    }
+
+    #[test]
+    fn import_rejects_invalid() {
+        let blob = export_save_entries(&entries(&[("version", SAVE_VERSION), ("Level1_record", r#"{"unlocked":true}"#)]));
+
+        assert!(import_save_entries("").is_err());
+        assert!(import_save_entries("hello").is_err());
+        assert!(import_save_entries(&blob.replace("PROGSAVE1", "PROGSAVE9")).is_err());
+        // Truncated.
+        assert!(import_save_entries(&blob[..blob.len() - 6]).is_err());
+
+        // Edited without updating checksum.
+        let tampered_json = r#"{"version":"1.7.0","Level9_record":"{\"unlocked\":true}"}"#;
+        let (checksum_part, _) = blob.rsplit_once(':').unwrap();
+        let tampered = format!("{checksum_part}:{}", base64_encode(tampered_json.as_bytes()));
+        assert_eq!(import_save_entries(&tampered), Err("Save code is garbled or has been edited.".to_string()));
+
+        let newer = export_save_entries(&entries(&[("version", "99.0.0"), ("Level1_record", "{}")]));
+        assert_eq!(
+            import_save_entries(&newer),
+            Err("Save code is from a newer version of the game (99.0.0). Update to import it.".to_string()),
+        );
+
+        let unknown = export_save_entries(&entries(&[("version", "1.0.0")]));
+        assert!(import_save_entries(&unknown).unwrap_err().contains("unrecognised version"));
+
+        let corrupt_record = export_save_entries(&entries(&[("version", SAVE_VERSION), ("Level3_record", "not json")]));
+        assert_eq!(import_save_entries(&corrupt_record), Err("Save code has unreadable progress for level 3.".to_string()));
+
+        assert!(import_save_entries(&export_save_entries(&SaveEntries::new())).is_err());
+    }
+}
@@ -0,0 +1,413 @@
+// Backends for where save games are kept.
+//
+// Save games are key-value pairs. In the browser they go in local storage,
+// natively in a file in the user's data directory, and in tests in memory.
+// ProfileSaveStorage keeps each player's save separately within one backend.
+//
+// Browser local storage is shared with anything else on the same site, so only
+// keys the save game uses (see is_save_key) are ever listed or replaced.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use nanoserde::{DeJson, SerJson};
+
+use crate::savegame::{SaveEntries, VERSION_KEY};
+
+/// Key-value store which save games are kept in.
+pub trait SaveStorage: std::fmt::Debug {
+    fn get(&self, key: &str) -> Option<String>;
+
+    fn set(&mut self, key: &str, val: &str);
+
+    fn remove(&mut self, key: &str);
+
+    /// All stored key-value pairs.
+    fn entries(&self) -> SaveEntries;
+
+    /// Replace everything stored, e.g. after migrating to a new save version.
+    /// Only keys which are dropped or changed are touched.
+    fn replace_entries(&mut self, entries: &SaveEntries) {
+        let old_entries = self.entries();
+        for key in old_entries.keys().filter(|key| !entries.contains_key(*key)) {
+            self.remove(key);
+        }
+        for (key, val) in entries.iter().filter(|(key, val)| old_entries.get(*key) != Some(*val)) {
+            self.set(key, val);
+        }
+    }
+}
+
+/// Whether key is one save games use: profile keys, or unprefixed keys from
+/// before profiles existed.
+pub fn is_save_key(key: &str) -> bool {
+    [PROFILES_KEY, CURRENT_PROFILE_KEY, VERSION_KEY].contains(&key)
+        || key.starts_with(PROFILE_KEY_PREFIX)
+        || key.starts_with("Level")
+}
+
+/// Browser local storage, via quad-storage. Natively quad-storage uses a file
+/// in the current directory.
+#[derive(Debug, Default)]
+pub struct LocalSaveStorage;
+
+impl LocalSaveStorage {
+    fn storage(&self) -> std::sync::MutexGuard<'_, quad_storage::LocalStorage> {
+        quad_storage::STORAGE.lock().unwrap()
+    }
+}
+
+impl SaveStorage for LocalSaveStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.storage().get(key)
+    }
+
+    fn set(&mut self, key: &str, val: &str) {
+        self.storage().set(key, val)
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.storage().remove(key)
+    }
+
+    // Only this game's keys, as other pages on the site may use local storage too.
+    fn entries(&self) -> SaveEntries {
+        let storage = self.storage();
+        (0..storage.len())
+            .filter_map(|idx| storage.key(idx))
+            .filter(|key| is_save_key(key))
+            .filter_map(|key| storage.get(&key).map(|val| (key, val)))
+            .collect()
+    }
+}
+
+/// Save data in a json file. Written after every change.
+#[derive(Debug)]
+pub struct FileSaveStorage {
+    path: PathBuf,
+    entries: SaveEntries,
+}
+
+impl FileSaveStorage {
+    /// Open save file, or start an empty one if it doesn't exist or can't be read.
+    pub fn open(path: PathBuf) -> Self {
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(json) => HashMap::<String, String>::deserialize_json(&json)
+                .map(|entries| entries.into_iter().collect())
+                .unwrap_or_else(|err| {
+                    log::warn!("Ignoring unreadable save file {}: {err}", path.display());
+                    SaveEntries::new()
+                }),
+            Err(_) => SaveEntries::new(),
+        };
+        Self { path, entries }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Save file for game in per-user data directory, e.g. ~/.local/share/{game_name}/save.json
+    /// on Linux. None if the platform's data directory can't be found.
+    pub fn user_save_path(game_name: &str) -> Option<PathBuf> {
+        let env_dir = |var: &str| std::env::var_os(var).filter(|dir| !dir.is_empty()).map(PathBuf::from);
+        let data_dir = if cfg!(target_os = "windows") {
+            env_dir("APPDATA")
+        } else if cfg!(target_os = "macos") {
+            env_dir("HOME").map(|home| home.join("Library/Application Support"))
+        } else {
+            env_dir("XDG_DATA_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".local/share")))
+        };
+        data_dir.map(|dir| dir.join(game_name).join("save.json"))
+    }
+
+    fn write(&self) {
+        let entries: HashMap<String, String> = self.entries.clone().into_iter().collect();
+        let result = self.path.parent().map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&self.path, entries.serialize_json()));
+        if let Err(err) = result {
+            log::error!("Failed to write save file {}: {err}", self.path.display());
+        }
+    }
+}
+
+impl SaveStorage for FileSaveStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.entries.get(key).cloned()
+    }
+
+    fn set(&mut self, key: &str, val: &str) {
+        self.entries.insert(key.to_string(), val.to_string());
+        self.write();
+    }
+
+    fn remove(&mut self, key: &str) {
+        if self.entries.remove(key).is_some() {
+            self.write();
+        }
+    }
+
+    fn entries(&self) -> SaveEntries {
+        self.entries.clone()
+    }
+
+    fn replace_entries(&mut self, entries: &SaveEntries) {
+        self.entries = entries.clone();
+        self.write();
+    }
+}
+
+/// Save data which only lasts as long as the program. Used in tests.
+#[derive(Debug, Default)]
+pub struct MemSaveStorage {
+    entries: SaveEntries,
+}
+
+impl SaveStorage for MemSaveStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.entries.get(key).cloned()
+    }
+
+    fn set(&mut self, key: &str, val: &str) {
+        self.entries.insert(key.to_string(), val.to_string());
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.entries.remove(key);
+    }
+
+    fn entries(&self) -> SaveEntries {
+        self.entries.clone()
+    }
+}
+
+impl From<SaveEntries> for MemSaveStorage {
+    fn from(entries: SaveEntries) -> Self {
+        Self { entries }
+    }
+}
+
+/// One player's save data, kept alongside other players' in the same storage.
+///
+/// Each profile's keys are stored as "Profile:{name}/{key}". The list of profiles
+/// and the one last used are stored unprefixed. Anything saved before profiles
+/// existed becomes the first profile's.
+#[derive(Debug)]
+pub struct ProfileSaveStorage {
+    root: Box<dyn SaveStorage>,
+    profile: String,
+}
+
+const PROFILES_KEY: &str = "profiles";
+const CURRENT_PROFILE_KEY: &str = "current_profile";
+const DEFAULT_PROFILE: &str = "Player 1";
+const MAX_PROFILE_NAME_LEN: usize = 20;
+const PROFILE_KEY_PREFIX: &str = "Profile:";
+
+impl ProfileSaveStorage {
+    pub fn new(mut root: Box<dyn SaveStorage>) -> Self {
+        let profiles = match root.get(PROFILES_KEY) {
+            Some(json) => Vec::<String>::deserialize_json(&json).unwrap_or_else(|err| {
+                log::error!("Unreadable profile list, recovering from saved keys: {err}");
+                Self::profiles_with_keys(&root.entries())
+            }),
+            None => {
+                Self::adopt_unprefixed_entries(root.as_mut(), DEFAULT_PROFILE);
+                vec![DEFAULT_PROFILE.to_string()]
+            },
+        };
+        let profiles = if profiles.is_empty() {vec![DEFAULT_PROFILE.to_string()]} else {profiles};
+        let profile = root.get(CURRENT_PROFILE_KEY)
+            .filter(|profile| profiles.contains(profile))
+            .unwrap_or_else(|| profiles[0].clone());
+
+        let mut storage = Self { root, profile };
+        storage.set_profiles(&profiles);
+        storage.root.set(CURRENT_PROFILE_KEY, &storage.profile.clone());
+        storage
+    }
+
+    /// Name of profile currently being read and written.
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+
+    /// All profile names, in order created.
+    pub fn profiles(&self) -> Vec<String> {
+        self.root.get(PROFILES_KEY)
+            .and_then(|json| Vec::<String>::deserialize_json(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn select_profile(&mut self, name: &str) -> Result<(), String> {
+        if !self.profiles().iter().any(|profile| profile == name) {
+            return Err(format!("No profile called '{name}'."));
+        }
+        self.profile = name.to_string();
+        self.root.set(CURRENT_PROFILE_KEY, name);
+        Ok(())
+    }
+
+    /// Add an empty profile. Doesn't select it.
+    pub fn create_profile(&mut self, name: &str) -> Result<(), String> {
+        let name = self.validate_new_name(name)?;
+        let mut profiles = self.profiles();
+        profiles.push(name);
+        self.set_profiles(&profiles);
+        Ok(())
+    }
+
+    /// Rename profile, keeping its save data.
+    pub fn rename_profile(&mut self, old_name: &str, new_name: &str) -> Result<(), String> {
+        if new_name.trim() == old_name {
+            return Ok(());
+        }
+        let new_name = self.validate_new_name(new_name)?;
+        let mut profiles = self.profiles();
+        let Some(idx) = profiles.iter().position(|profile| profile == old_name) else {
+            return Err(format!("No profile called '{old_name}'."));
+        };
+        profiles[idx] = new_name.clone();
+
+        let (old_prefix, new_prefix) = (Self::prefix(old_name), Self::prefix(&new_name));
+        let entries = self.root.entries().into_iter().map(|(key, val)| match key.strip_prefix(&old_prefix) {
+            Some(unprefixed) => (format!("{new_prefix}{unprefixed}"), val),
+            None => (key, val),
+        }).collect();
+        self.root.replace_entries(&entries);
+        self.set_profiles(&profiles);
+        if self.profile == old_name {
+            self.profile = new_name;
+            self.root.set(CURRENT_PROFILE_KEY, &self.profile.clone());
+        }
+        Ok(())
+    }
+
+    /// Delete profile and all its save data. If it was selected, selects the first
+    /// remaining profile. The only profile can't be deleted.
+    pub fn delete_profile(&mut self, name: &str) -> Result<(), String> {
+        let mut profiles = self.profiles();
+        if !profiles.iter().any(|profile| profile == name) {
+            return Err(format!("No profile called '{name}'."));
+        }
+        if profiles.len() == 1 {
+            return Err("Can't delete the only profile.".to_string());
+        }
+        profiles.retain(|profile| profile != name);
+
+        let prefix = Self::prefix(name);
+        let entries = self.root.entries().into_iter().filter(|(key, _)| !key.starts_with(&prefix)).collect();
+        self.root.replace_entries(&entries);
+        self.set_profiles(&profiles);
+        if self.profile == name {
+            self.select_profile(&profiles[0].clone())?;
+        }
+        Ok(())
+    }
+
+    // Everything in the underlying storage, for all profiles.
+    #[cfg(test)]
+    pub(crate) fn root_entries(&self) -> SaveEntries {
+        self.root.entries()
+    }
+
+    fn prefix(profile: &str) -> String {
+        format!("{PROFILE_KEY_PREFIX}{profile}/")
+    }
+
+    fn set_profiles(&mut self, profiles: &[String]) {
+        self.root.set(PROFILES_KEY, &profiles.to_vec().serialize_json());
+    }
+
+    fn validate_new_name(&self, name: &str) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            Err("Profile name can't be empty.".to_string())
+        } else if name.chars().count() > MAX_PROFILE_NAME_LEN {
+            Err(format!("Profile name can't be longer than {MAX_PROFILE_NAME_LEN} letters."))
+        } else if name.contains('/') {
+            Err("Profile name can't contain '/'.".to_string())
+        } else if self.profiles().iter().any(|profile| profile == name) {
+            Err(format!("There's already a profile called '{name}'."))
+        } else {
+            Ok(name.to_string())
+        }
+    }
+
+    // Move save data from before profiles existed into the given profile.
+    fn adopt_unprefixed_entries(root: &mut dyn SaveStorage, profile: &str) {
+        let entries = root.entries();
+        if entries.is_empty() {
+            return;
+        }
+        log::info!("Moving existing save data into profile '{profile}'");
+        let prefix = Self::prefix(profile);
+        root.replace_entries(&entries.into_iter().map(|(key, val)| (format!("{prefix}{key}"), val)).collect());
+    }
+
+    // Profile names which have saved keys, if profile list is lost.
+    fn profiles_with_keys(entries: &SaveEntries) -> Vec<String> {
+        let mut profiles: Vec<String> = vec![];
+        for key in entries.keys() {
+            if let Some((profile, _)) = key.strip_prefix(PROFILE_KEY_PREFIX).and_then(|rest| rest.split_once('/'))
+                && !profiles.iter().any(|known| known == profile) {
+                profiles.push(profile.to_string());
+            }
+        }
+        profiles
+    }
+}
+
+impl SaveStorage for ProfileSaveStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.root.get(&(Self::prefix(&self.profile) + key))
+    }
+
+    fn set(&mut self, key: &str, val: &str) {
+        self.root.set(&(Self::prefix(&self.profile) + key), val)
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.root.remove(&(Self::prefix(&self.profile) + key))
+    }
+
+    fn entries(&self) -> SaveEntries {
+        let prefix = Self::prefix(&self.profile);
+        self.root.entries().into_iter()
+            .filter_map(|(key, val)| key.strip_prefix(&prefix).map(|unprefixed| (unprefixed.to_string(), val)))
+            .collect()
+    }
+
+    fn replace_entries(&mut self, entries: &SaveEntries) {
+        let prefix = Self::prefix(&self.profile);
+        let mut all_entries: SaveEntries = self.root.entries().into_iter().filter(|(key, _)| !key.starts_with(&prefix)).collect();
+        all_entries.extend(entries.iter().map(|(key, val)| (format!("{prefix}{key}"), val.clone())));
+        self.root.replace_entries(&all_entries);
+    }
+}
+
+/// Usual storage for platform: local storage in the browser, else a file in the
+/// user's data directory.
+///
+/// The first time the file is used, anything saved in quad-storage's native file
+/// by previous versions is copied into it.
+pub fn default_save_storage(game_name: &str) -> Box<dyn SaveStorage> {
+    if cfg!(target_arch = "wasm32") {
+        return Box::new(LocalSaveStorage);
+    }
+    let Some(path) = FileSaveStorage::user_save_path(game_name) else {
+        log::warn!("No user data directory found, saving with quad-storage");
+        return Box::new(LocalSaveStorage);
+    };
+
+    let is_new = !path.exists();
+    let mut storage = FileSaveStorage::open(path);
+    if is_new {
+        let prev_entries = LocalSaveStorage.entries();
+        if !prev_entries.is_empty() {
+            log::info!("Copying previous save data to {}", storage.path().display());
+            storage.replace_entries(&prev_entries);
+        }
+    }
+    Box::new(storage)
+}
@@ -1,5 +1,12 @@
+use std::collections::BTreeMap;
+
 use quad_timestamp::*;
 use chrono::*;
+use nanoserde::{DeJson, SerJson};
+
+use crate::save_export::{export_save_entries, import_save_entries};
+use crate::save_storage::{ProfileSaveStorage, SaveStorage};
+use crate::progression::Progression;
 
 pub trait BaseSaveGame : std::fmt::Debug {
     // Levels available to go to, if levels are identified by numeric index. Else empty set.
@@ -8,6 +15,51 @@ pub trait BaseSaveGame : std::fmt::Debug {
     fn unlock_level(&mut self, _lev_idx: u16);
 
     fn store_outcome(&mut self, lev_idx: u16, outcome: OutcomeToStore);
+
+    // Program last being worked on in level, as text, if any.
+    fn get_current_prog(&self, lev_idx: u16) -> Option<String>;
+
+    fn store_current_prog(&mut self, lev_idx: u16, prog: &str);
+
+    // Winning program with fewest instrs, as text, if level has been won.
+    fn get_best_prog(&self, lev_idx: u16) -> Option<String>;
+
+    // All progress as one line of text, for moving between browsers or machines.
+    // None if this game doesn't save anything.
+    fn export_progress(&self) -> Option<String>;
+
+    // Replace all progress with exported text. Err with message for player if invalid.
+    fn import_progress(&mut self, blob: &str) -> Result<(), String>;
+
+    // Everything stored about level, for showing stats and history.
+    fn get_level_record(&self, lev_idx: u16) -> Option<LevelRecord>;
+
+    // Profiles, for keeping separate progress for each player. Other fns above
+    // apply to the current profile. Treated as unsupported if no current profile.
+
+    fn current_profile(&self) -> Option<String> {
+        None
+    }
+
+    fn profiles(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn select_profile(&mut self, _name: &str) -> Result<(), String> {
+        Err("This game doesn't have profiles.".to_string())
+    }
+
+    fn create_profile(&mut self, _name: &str) -> Result<(), String> {
+        Err("This game doesn't have profiles.".to_string())
+    }
+
+    fn rename_profile(&mut self, _old_name: &str, _new_name: &str) -> Result<(), String> {
+        Err("This game doesn't have profiles.".to_string())
+    }
+
+    fn delete_profile(&mut self, _name: &str) -> Result<(), String> {
+        Err("This game doesn't have profiles.".to_string())
+    }
 }
 
 #[derive(Debug)]
@@ -24,6 +76,29 @@ impl BaseSaveGame for UnimplementedSaveGame {
 
     fn store_outcome(&mut self, _lev_idx: u16, _outcome: OutcomeToStore) {
     }
+
+    fn get_current_prog(&self, _lev_idx: u16) -> Option<String> {
+        None
+    }
+
+    fn store_current_prog(&mut self, _lev_idx: u16, _prog: &str) {
+    }
+
+    fn get_best_prog(&self, _lev_idx: u16) -> Option<String> {
+        None
+    }
+
+    fn export_progress(&self) -> Option<String> {
+        None
+    }
+
+    fn import_progress(&mut self, _blob: &str) -> Result<(), String> {
+        Err("This game doesn't have saved progress.".to_string())
+    }
+
+    fn get_level_record(&self, _lev_idx: u16) -> Option<LevelRecord> {
+        None
+    }
 }
 
 // Results of level to store in save game.
@@ -43,35 +118,294 @@ impl OutcomeToStore {
     }
 }
 
+/// Everything stored about one level. Saved as json under "Level{n}_record", or
+/// "Level_{save_id}_record" for levels with a save id in the progression.
+#[derive(Clone, Debug, Default, PartialEq, DeJson, SerJson)]
+pub struct LevelRecord {
+    #[nserde(default)]
+    pub unlocked: bool,
+    // Number of times level was run, including repeats.
+    #[nserde(default)]
+    pub attempts: u32,
+    #[nserde(default)]
+    pub outcomes: Vec<OutcomeRecord>,
+    // Winning solution with fewest instrs.
+    pub best_solution: Option<String>,
+    // Program as player last left it, whether or not it was run.
+    pub current_prog: Option<String>,
+}
+
+/// One result of running a level, plus how many times the same result
+/// happened again straight afterwards.
+#[derive(Clone, Debug, PartialEq, DeJson, SerJson)]
+pub struct OutcomeRecord {
+    pub datetime: String,
+    // Save version at time of outcome.
+    pub version: String,
+    pub outcome: String,
+    pub solution: String,
+    #[nserde(default)]
+    pub repeats: u32,
+}
+
+impl LevelRecord {
+    pub fn add_outcome(&mut self, datetime: &str, version: &str, outcome: &OutcomeToStore) {
+        self.attempts += 1;
+        match self.outcomes.last_mut() {
+            Some(prev) if prev.outcome == outcome.outcome && prev.solution == outcome.solution => prev.repeats += 1,
+            _ => self.outcomes.push(OutcomeRecord {
+                datetime: datetime.to_string(),
+                version: version.to_string(),
+                outcome: outcome.outcome.clone(),
+                solution: outcome.solution.clone(),
+                repeats: 0,
+            }),
+        }
+
+        // "Won" as recorded by CodingArena.
+        let is_better = |best: &String| solution_len(&outcome.solution) < solution_len(best);
+        if outcome.outcome == "Won" && self.best_solution.as_ref().is_none_or(is_better) {
+            self.best_solution = Some(outcome.solution.clone());
+        }
+    }
+
+    pub fn solved(&self) -> bool {
+        self.best_solution.is_some()
+    }
+
+    pub fn first_played(&self) -> Option<&str> {
+        self.outcomes.first().map(|outcome| outcome.datetime.as_str())
+    }
+
+    pub fn last_played(&self) -> Option<&str> {
+        self.outcomes.last().map(|outcome| outcome.datetime.as_str())
+    }
+
+    pub fn stats(&self) -> LevelStats {
+        let count = |outcome: &str| self.outcomes.iter()
+            .filter(|record| record.outcome == outcome)
+            .map(|record| 1 + record.repeats)
+            .sum();
+        let first_won = self.outcomes.iter().find(|record| record.outcome == "Won");
+        let secs_to_first_solve = match (self.first_played().and_then(parse_datetime), first_won.and_then(|won| parse_datetime(&won.datetime))) {
+            (Some(first), Some(won)) => Some((won - first).num_seconds()),
+            _ => None,
+        };
+        LevelStats {
+            attempts: self.attempts,
+            wins: count("Won"),
+            fails: count("Die"),
+            secs_to_first_solve,
+            solution_lens: self.outcomes.iter().map(|record| (record.outcome.clone(), solution_len(&record.solution))).collect(),
+        }
+    }
+}
+
+/// Summary of a level's outcomes, e.g. to see how a player iterated towards a solution.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelStats {
+    pub attempts: u32,
+    pub wins: u32,
+    pub fails: u32,
+    // From first attempt to first win, if won.
+    pub secs_to_first_solve: Option<i64>,
+    // Outcome and number of instrs of each recorded outcome, oldest first.
+    pub solution_lens: Vec<(String, usize)>,
+}
+
+impl LevelStats {
+    /// Fraction of runs which won, not counting runs which were cancelled.
+    pub fn win_ratio(&self) -> Option<f32> {
+        let finished = self.wins + self.fails;
+        (finished > 0).then(|| self.wins as f32 / finished as f32)
+    }
+}
+
+// Datetime as stored in OutcomeRecord, e.g. "2025-12-20 10:00:00 UTC".
+fn parse_datetime(datetime: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(datetime.trim_end_matches(" UTC"), "%Y-%m-%d %H:%M:%S").ok()
+}
+
+// Number of instrs in solution text like "[F,loop[F,R]]".
+pub(crate) fn solution_len(solution: &str) -> usize {
+    solution.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).count()
+}
+
+/// Save game key-value pairs, as stored.
+pub type SaveEntries = BTreeMap<String, String>;
+
+/// Current format of save data.
+///
+/// Versions are the engine version which introduced each format:
+/// 1.6.1: Plain "Level1" key for unlock.
+/// 1.6.2: "Level1_unlocked" keys. "Level1_solutions" keys, but nothing recorded.
+/// 1.6.3: "Level1_outcomes" keys, free text lines of outcomes.
+/// 1.7.0: "Level1_record" keys, LevelRecord json.
+pub const SAVE_VERSION: &str = "1.7.0";
+
+pub(crate) const VERSION_KEY: &str = "version";
+
+// Upgrades save data from the given version to the next one.
+type Migration = (&'static str, fn(&mut SaveEntries));
+
+const MIGRATIONS: [Migration; 3] = [
+    ("1.6.1", migrate_from_1_6_1),
+    ("1.6.2", migrate_from_1_6_2),
+    ("1.6.3", migrate_from_1_6_3),
+];
+
+/// Upgrade save data from any previous version to SAVE_VERSION.
+///
+/// Data from an unrecognised version is left alone, apart from a warning.
+pub fn migrate_save_entries(mut entries: SaveEntries) -> SaveEntries {
+    let Some(version) = stored_version(&entries) else {
+        // Nothing saved yet.
+        return entries;
+    };
+
+    if version != SAVE_VERSION {
+        let Some(first_step) = MIGRATIONS.iter().position(|(from_version, _)| *from_version == version) else {
+            log::warn!("Not migrating save data from unrecognised version {version}");
+            return entries;
+        };
+        for (from_version, migrate) in &MIGRATIONS[first_step..] {
+            log::info!("Migrating save data from version {from_version}");
+            migrate(&mut entries);
+        }
+    }
+
+    entries.insert(VERSION_KEY.to_string(), SAVE_VERSION.to_string());
+    entries
+}
+
+// Version of stored data, or None if there isn't any. 1.6.1 didn't store a version.
+pub(crate) fn stored_version(entries: &SaveEntries) -> Option<String> {
+    if let Some(version) = entries.get(VERSION_KEY) {
+        Some(version.clone())
+    } else if !level_keys(entries, "").is_empty() {
+        Some("1.6.1".to_string())
+    } else {
+        None
+    }
+}
+
+// Level numbers and keys for keys like "Level{n}{suffix}".
+pub(crate) fn level_keys(entries: &SaveEntries, suffix: &str) -> Vec<(u16, String)> {
+    entries.keys().filter_map(|key| {
+        let lev_idx = key.strip_prefix("Level")?.strip_suffix(suffix)?.parse::<u16>().ok()?;
+        Some((lev_idx, key.clone()))
+    }).collect()
+}
+
+fn migrate_from_1_6_1(entries: &mut SaveEntries) {
+    for (lev_idx, key) in level_keys(entries, "") {
+        entries.remove(&key);
+        entries.insert(format!("Level{lev_idx}_unlocked"), "unlocked".to_string());
+    }
+}
+
+fn migrate_from_1_6_2(entries: &mut SaveEntries) {
+    for (_, key) in level_keys(entries, "_solutions") {
+        entries.remove(&key);
+    }
+}
+
+fn migrate_from_1_6_3(entries: &mut SaveEntries) {
+    let mut records = BTreeMap::<u16, LevelRecord>::new();
+
+    for (lev_idx, key) in level_keys(entries, "_unlocked") {
+        entries.remove(&key);
+        records.entry(lev_idx).or_default().unlocked = true;
+    }
+
+    // Lines like "\n{datetime} ({version}): {outcome}: {solution}", with a "." appended for each repeat.
+    for (lev_idx, key) in level_keys(entries, "_outcomes") {
+        let txt = entries.remove(&key).unwrap_or_default();
+        let record = records.entry(lev_idx).or_default();
+        for line in txt.lines().filter(|line| !line.is_empty()) {
+            let Some((datetime, rest)) = line.split_once(" (") else { continue };
+            let Some((version, rest)) = rest.split_once("): ") else { continue };
+            let Some((outcome, solution_and_repeats)) = rest.split_once(": ") else { continue };
+            let solution = solution_and_repeats.trim_end_matches('.');
+            let repeats = solution_and_repeats.len() - solution.len();
+            let outcome = OutcomeToStore::new(outcome.to_string(), solution.to_string());
+            for _ in 0..=repeats {
+                record.add_outcome(datetime, version, &outcome);
+            }
+        }
+    }
+
+    for (lev_idx, record) in records {
+        entries.insert(format!("Level{lev_idx}_record"), record.serialize_json());
+    }
+}
+
 #[derive(Debug)]
 pub struct GenericProgSaveGame {
-    num_levels: u16,
-    most_recent: Option<OutcomeToStore>,
+    // Levels and how they unlock.
+    progression: Progression,
+    storage: ProfileSaveStorage,
 }
 
 impl GenericProgSaveGame {
-    pub fn new(num_levels: u16) -> Self {
-        let mut save_game_data = Self {num_levels, most_recent: None};
-        save_game_data.unlock_level(1);
-        // TODO: Handle values from previous version?
-        save_game_data.storage().set(&save_game_data.version_key(), save_game_data.current_version());
+    pub fn new(progression: Progression, storage: Box<dyn SaveStorage>) -> Self {
+        let mut save_game_data = Self {progression, storage: ProfileSaveStorage::new(storage)};
+        save_game_data.init_profile();
         save_game_data
     }
 
-    fn version_key(&self) -> &str {
-        "version"
+    // Bring current profile's save data up to date, and make sure it can play the first levels.
+    fn init_profile(&mut self) {
+        self.migrate_storage();
+        self.unlock_progressed_levels();
     }
 
-    fn level_unlocked_key(&self, lev_idx: u16) -> String {
-        format!("Level{lev_idx}_unlocked")
+    // Unlock any levels whose unlock rules are met by levels solved so far.
+    // Unlocks are stored, so levels stay unlocked if rules change.
+    fn unlock_progressed_levels(&mut self) {
+        let solved = (1..=self.progression.num_levels()).filter(|lev_idx| self.level_record(*lev_idx).solved()).collect();
+        for lev_idx in self.progression.unlockable_levels(&solved) {
+            self.unlock_level(lev_idx);
+        }
+    }
+
+    // Levels with a save id keep their record whichever number they have.
+    fn level_record_key(&self, lev_idx: u16) -> String {
+        match self.progression.find_level(lev_idx).and_then(|(_, level)| level.save_id.as_ref()) {
+            Some(save_id) => format!("Level_{save_id}_record"),
+            None => format!("Level{lev_idx}_record"),
+        }
+    }
+
+    /// Stored record for level, or a new empty one.
+    pub fn level_record(&self, lev_idx: u16) -> LevelRecord {
+        let Some(json) = self.storage.get(&self.level_record_key(lev_idx)) else {
+            return LevelRecord::default();
+        };
+        LevelRecord::deserialize_json(&json).unwrap_or_else(|err| {
+            log::warn!("Discarding unreadable save record for level {lev_idx}: {err}");
+            LevelRecord::default()
+        })
     }
 
-    fn _level_outcomes_key(&self, lev_idx: u16) -> String {
-        format!("Level{lev_idx}_outcomes")
+    fn set_level_record(&mut self, lev_idx: u16, record: &LevelRecord) {
+        self.storage.set(&self.level_record_key(lev_idx), &record.serialize_json());
     }
 
-    fn storage(&self) -> std::sync::MutexGuard<quad_storage::LocalStorage> {
-        quad_storage::STORAGE.lock().unwrap()
+    // Upgrade whatever is in storage to the current save version. Only keys the
+    // migration changed are written or removed.
+    fn migrate_storage(&mut self) {
+        let entries = self.storage.entries();
+        let migrated = migrate_save_entries(entries.clone());
+        for key in entries.keys().filter(|key| !migrated.contains_key(*key)) {
+            self.storage.remove(key);
+        }
+        for (key, val) in migrated.iter().filter(|(key, val)| entries.get(*key) != Some(*val)) {
+            self.storage.set(key, val);
+        }
+        if self.storage.get(VERSION_KEY).is_none() {
+            self.storage.set(VERSION_KEY, SAVE_VERSION);
+        }
     }
 
     fn datetime_str(&self) -> String {
@@ -79,38 +413,432 @@ impl GenericProgSaveGame {
         log::debug!("Timestamp: {datetime}");
         datetime.to_string()
     }
-
-    fn current_version(&self) -> &str {
-        // Version based on engine versions.
-        // Expecting 1.6.1: Plain key "Level1" key for unlock and no solution key.
-        // Expecting 1.6.2: With keys "Level1_unlocked" and "Level1_solutions"
-        // Expecting 1.6.3: Actually recording solutions for levels.
-        return "1.6.3";
-    }
 }
 
 impl BaseSaveGame for GenericProgSaveGame {
     fn unlock_level(&mut self, lev_idx: u16) {
-        self.storage().set(&self.level_unlocked_key(lev_idx), "unlocked");
+        let mut record = self.level_record(lev_idx);
+        if !record.unlocked {
+            record.unlocked = true;
+            self.set_level_record(lev_idx, &record);
+        }
     }
 
     fn get_unlocked_levels(&self) -> std::collections::HashSet<u16> {
-        (1..self.num_levels).filter(|lev_idx| self.storage().get(&self.level_unlocked_key(*lev_idx)).is_some()).collect()
+        (1..=self.progression.num_levels()).filter(|lev_idx| self.level_record(*lev_idx).unlocked).collect()
     }
 
     fn store_outcome(&mut self, lev_idx: u16, outcome: OutcomeToStore) {
-        let datetime = self.datetime_str();
-        let additional_txt = if Some(&outcome) == self.most_recent.as_ref() {
-            ".".to_string()
-        } else {
-            format!("\n{datetime} ({}): {}: {}", self.current_version(), outcome.outcome, outcome.solution)
-        };
-        log::debug!("Storing in save game: {additional_txt}");
+        log::debug!("Storing in save game: Level {lev_idx}: {}: {}", outcome.outcome, outcome.solution);
+        let mut record = self.level_record(lev_idx);
+        record.add_outcome(&self.datetime_str(), SAVE_VERSION, &outcome);
+        record.current_prog = Some(outcome.solution);
+        self.set_level_record(lev_idx, &record);
+        if record.solved() {
+            self.unlock_progressed_levels();
+        }
+    }
+
+    fn get_current_prog(&self, lev_idx: u16) -> Option<String> {
+        self.level_record(lev_idx).current_prog
+    }
+
+    fn store_current_prog(&mut self, lev_idx: u16, prog: &str) {
+        let mut record = self.level_record(lev_idx);
+        if record.current_prog.as_deref() != Some(prog) {
+            record.current_prog = Some(prog.to_string());
+            self.set_level_record(lev_idx, &record);
+        }
+    }
+
+    fn get_best_prog(&self, lev_idx: u16) -> Option<String> {
+        self.level_record(lev_idx).best_solution
+    }
+
+    fn export_progress(&self) -> Option<String> {
+        Some(export_save_entries(&self.storage.entries()))
+    }
+
+    fn import_progress(&mut self, blob: &str) -> Result<(), String> {
+        let entries = import_save_entries(blob)?;
+        self.storage.replace_entries(&entries);
+        self.unlock_progressed_levels();
+        Ok(())
+    }
+
+    fn get_level_record(&self, lev_idx: u16) -> Option<LevelRecord> {
+        Some(self.level_record(lev_idx))
+    }
+
+    fn current_profile(&self) -> Option<String> {
+        Some(self.storage.profile().to_string())
+    }
+
+    fn profiles(&self) -> Vec<String> {
+        self.storage.profiles()
+    }
+
+    fn select_profile(&mut self, name: &str) -> Result<(), String> {
+        self.storage.select_profile(name)?;
+        self.init_profile();
+        Ok(())
+    }
+
+    fn create_profile(&mut self, name: &str) -> Result<(), String> {
+        self.storage.create_profile(name)
+    }
+
+    fn rename_profile(&mut self, old_name: &str, new_name: &str) -> Result<(), String> {
+        self.storage.rename_profile(old_name, new_name)
+    }
+
+    fn delete_profile(&mut self, name: &str) -> Result<(), String> {
+        self.storage.delete_profile(name)?;
+        self.init_profile();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::save_storage::{FileSaveStorage, MemSaveStorage};
+
+    fn entries(pairs: &[(&str, &str)]) -> SaveEntries {
+        pairs.iter().map(|(key, val)| (key.to_string(), val.to_string())).collect()
+    }
+
+    fn record(entries: &SaveEntries, lev_idx: u16) -> LevelRecord {
+        LevelRecord::deserialize_json(&entries[&format!("Level{lev_idx}_record")]).unwrap()
+    }
+
+    #[test]
+    fn migrate_nothing_stored() {
+        assert_eq!(migrate_save_entries(SaveEntries::new()), SaveEntries::new());
+    }
+
+    #[test]
+    fn migrate_current_version_unchanged() {
+        let current = entries(&[("version", SAVE_VERSION), ("Level1_record", r#"{"unlocked":true}"#)]);
+        assert_eq!(migrate_save_entries(current.clone()), current);
+    }
+
+    #[test]
+    fn migrate_unrecognised_version_unchanged() {
+        let future = entries(&[("version", "9.9.9"), ("Level1_something", "new")]);
+        assert_eq!(migrate_save_entries(future.clone()), future);
+    }
+
+    #[test]
+    fn migrate_from_1_6_1() {
+        let migrated = migrate_save_entries(entries(&[("Level1", "unlocked"), ("Level2", "unlocked")]));
+        assert_eq!(migrated.keys().collect::<Vec<_>>(), ["Level1_record", "Level2_record", "version"]);
+        assert_eq!(migrated["version"], SAVE_VERSION);
+        assert!(record(&migrated, 2).unlocked);
+        assert_eq!(record(&migrated, 2).attempts, 0);
+    }
+
+    #[test]
+    fn migrate_from_1_6_2() {
+        let migrated = migrate_save_entries(entries(&[
+            ("version", "1.6.2"),
+            ("Level1_unlocked", "unlocked"),
+            ("Level1_solutions", ""),
+        ]));
+        assert_eq!(migrated.keys().collect::<Vec<_>>(), ["Level1_record", "version"]);
+        assert_eq!(record(&migrated, 1), LevelRecord { unlocked: true, ..LevelRecord::default() });
+    }
+
+    #[test]
+    fn migrate_from_1_6_3() {
+        let outcomes = concat!(
+            "\n2025-12-20 10:00:00 UTC (1.6.3): Die: [F,F]",
+            "\n2025-12-20 10:01:00 UTC (1.6.3): Won: [F,F,R,F,F,F]..",
+            "\n2025-12-21 09:00:00 UTC (1.6.3): Won: [F,loop[F,R]]",
+        );
+        let migrated = migrate_save_entries(entries(&[
+            ("version", "1.6.3"),
+            ("Level1_unlocked", "unlocked"),
+            ("Level2_unlocked", "unlocked"),
+            ("Level1_outcomes", outcomes),
+        ]));
+        assert_eq!(migrated.keys().collect::<Vec<_>>(), ["Level1_record", "Level2_record", "version"]);
+
+        let lev1 = record(&migrated, 1);
+        assert!(lev1.unlocked);
+        assert_eq!(lev1.attempts, 5);
+        assert_eq!(lev1.outcomes.len(), 3);
+        assert_eq!(lev1.outcomes[1].solution, "[F,F,R,F,F,F]");
+        assert_eq!(lev1.outcomes[1].repeats, 2);
+        assert_eq!(lev1.best_solution.as_deref(), Some("[F,loop[F,R]]"));
+        assert_eq!(lev1.first_played(), Some("2025-12-20 10:00:00 UTC"));
+        assert_eq!(lev1.last_played(), Some("2025-12-21 09:00:00 UTC"));
+
+        assert_eq!(record(&migrated, 2), LevelRecord { unlocked: true, ..LevelRecord::default() });
+    }
+
+    fn new_save_game(num_levels: u16, stored: SaveEntries) -> GenericProgSaveGame {
+        GenericProgSaveGame::new(Progression::linear(num_levels), Box::new(MemSaveStorage::from(stored)))
+    }
+
+    #[test]
+    fn new_save_game_unlocks_first_level() {
+        let save_game = new_save_game(3, SaveEntries::new());
+        assert_eq!(save_game.get_unlocked_levels(), [1].into());
+        assert_eq!(save_game.storage.get(VERSION_KEY).as_deref(), Some(SAVE_VERSION));
+    }
+
+    #[test]
+    fn unlock_levels() {
+        let mut save_game = new_save_game(3, SaveEntries::new());
+        save_game.unlock_level(3);
+        assert_eq!(save_game.get_unlocked_levels(), [1, 3].into());
+        save_game.unlock_level(3);
+        save_game.unlock_level(2);
+        assert_eq!(save_game.get_unlocked_levels(), [1, 2, 3].into());
+        // Levels past the end of the levset aren't reported.
+        save_game.unlock_level(4);
+        assert_eq!(save_game.get_unlocked_levels(), [1, 2, 3].into());
+    }
+
+    #[test]
+    fn unlocked_levels_loaded_from_old_save() {
+        let save_game = new_save_game(3, entries(&[("Level2", "unlocked"), ("Level3", "unlocked")]));
+        assert_eq!(save_game.get_unlocked_levels(), [1, 2, 3].into());
+        assert_eq!(save_game.storage.get(VERSION_KEY).as_deref(), Some(SAVE_VERSION));
+    }
+
+    #[test]
+    fn migration_leaves_other_keys_alone() {
+        let save_game = new_save_game(3, entries(&[
+            ("profiles", r#"["Player 1","Ann"]"#),
+            ("Profile:Player 1/Level2", "unlocked"),
+            ("Profile:Ann/Level3", "unlocked"),
+            ("other_game_key", "kept"),
+        ]));
+        assert_eq!(save_game.get_unlocked_levels(), [1, 2].into());
+        let root = save_game.storage.root_entries();
+        assert_eq!(root.get("Profile:Ann/Level3").map(String::as_str), Some("unlocked"));
+        assert_eq!(root.get("other_game_key").map(String::as_str), Some("kept"));
+        assert!(!root.contains_key("Profile:Player 1/Level2"));
+    }
+
+    #[test]
+    fn store_outcomes() {
+        let mut save_game = new_save_game(3, SaveEntries::new());
+        save_game.store_outcome(2, OutcomeToStore::new("Die".to_string(), "[F]".to_string()));
+        save_game.store_outcome(2, OutcomeToStore::new("Won".to_string(), "[F,F]".to_string()));
+
+        let record = save_game.level_record(2);
+        assert_eq!(record.attempts, 2);
+        assert_eq!(record.outcomes.iter().map(|outcome| outcome.outcome.as_str()).collect::<Vec<_>>(), ["Die", "Won"]);
+        assert_eq!(record.best_solution.as_deref(), Some("[F,F]"));
+        assert_eq!(record.first_played(), Some(record.outcomes[0].datetime.as_str()));
+        // Storing an outcome doesn't unlock the level itself.
+        assert!(!record.unlocked);
+        assert_eq!(save_game.level_record(1).attempts, 0);
+    }
+
+    #[test]
+    fn store_progs() {
+        let mut save_game = new_save_game(3, SaveEntries::new());
+        assert_eq!(save_game.get_current_prog(1), None);
+
+        save_game.store_current_prog(1, "[F]");
+        save_game.store_outcome(1, OutcomeToStore::new("Won".to_string(), "[F,F]".to_string()));
+        assert_eq!(save_game.get_current_prog(1).as_deref(), Some("[F,F]"));
+        save_game.store_current_prog(1, "[F,F,R,F,F]");
+        assert_eq!(save_game.get_current_prog(1).as_deref(), Some("[F,F,R,F,F]"));
+        assert_eq!(save_game.get_best_prog(1).as_deref(), Some("[F,F]"));
+        assert_eq!(save_game.level_record(1).attempts, 1);
+
+        assert_eq!(save_game.get_current_prog(2), None);
+        assert_eq!(save_game.get_best_prog(2), None);
+    }
+
+    #[test]
+    fn profiles_keep_separate_progress() {
+        let mut save_game = new_save_game(3, entries(&[("Level2", "unlocked")]));
+        // Progress from before profiles belongs to the first profile.
+        assert_eq!(save_game.profiles(), ["Player 1"]);
+        assert_eq!(save_game.current_profile().as_deref(), Some("Player 1"));
+        assert_eq!(save_game.get_unlocked_levels(), [1, 2].into());
+
+        save_game.create_profile("  Ann ").unwrap();
+        assert_eq!(save_game.profiles(), ["Player 1", "Ann"]);
+        assert_eq!(save_game.current_profile().as_deref(), Some("Player 1"));
+        save_game.select_profile("Ann").unwrap();
+        assert_eq!(save_game.get_unlocked_levels(), [1].into());
+        save_game.unlock_level(3);
+        save_game.store_current_prog(3, "[F]");
+
+        save_game.select_profile("Player 1").unwrap();
+        assert_eq!(save_game.get_unlocked_levels(), [1, 2].into());
+        assert_eq!(save_game.get_current_prog(3), None);
+
+        save_game.rename_profile("Ann", "Bea").unwrap();
+        assert_eq!(save_game.profiles(), ["Player 1", "Bea"]);
+        assert!(save_game.select_profile("Ann").is_err());
+        save_game.select_profile("Bea").unwrap();
+        assert_eq!(save_game.get_unlocked_levels(), [1, 3].into());
+        assert_eq!(save_game.get_current_prog(3).as_deref(), Some("[F]"));
+
+        // Deleting current profile switches to another.
+        save_game.delete_profile("Bea").unwrap();
+        assert_eq!(save_game.profiles(), ["Player 1"]);
+        assert_eq!(save_game.current_profile().as_deref(), Some("Player 1"));
+        assert_eq!(save_game.get_unlocked_levels(), [1, 2].into());
+        assert!(save_game.delete_profile("Player 1").is_err());
+
+        // Recreated profile starts empty.
+        save_game.create_profile("Bea").unwrap();
+        save_game.select_profile("Bea").unwrap();
+        assert_eq!(save_game.get_unlocked_levels(), [1].into());
+    }
+
+    #[test]
+    fn profile_names_validated() {
+        let mut save_game = new_save_game(3, SaveEntries::new());
+        assert!(save_game.create_profile("").is_err());
+        assert!(save_game.create_profile("   ").is_err());
+        assert!(save_game.create_profile("Player 1").is_err());
+        assert!(save_game.create_profile("a/b").is_err());
+        assert!(save_game.create_profile("A name much too long to fit").is_err());
+        assert!(save_game.rename_profile("Player 1", "").is_err());
+        assert!(save_game.rename_profile("Nobody", "Ann").is_err());
+        assert_eq!(save_game.profiles(), ["Player 1"]);
+    }
+
+    #[test]
+    fn export_and_import_progress() {
+        let mut save_game = new_save_game(3, SaveEntries::new());
+        save_game.unlock_level(2);
+        save_game.store_current_prog(2, "[F,R]");
+        let blob = save_game.export_progress().unwrap();
+
+        let mut other = new_save_game(3, entries(&[("Level3", "unlocked")]));
+        other.import_progress(&blob).unwrap();
+        assert_eq!(other.get_unlocked_levels(), [1, 2].into());
+        assert_eq!(other.get_current_prog(2).as_deref(), Some("[F,R]"));
+
+        // Invalid code leaves progress unchanged.
+        assert!(other.import_progress("PROGSAVE1:0:xyz").is_err());
+        assert_eq!(other.get_unlocked_levels(), [1, 2].into());
+    }
+
+    #[test]
+    fn progression_unlocks_levels() {
+        use crate::progression::UnlockRule::*;
+        let won = |solution: &str| OutcomeToStore::new("Won".to_string(), solution.to_string());
+        let progression = Progression::default()
+            .with_chapter("Basics")
+            .with_level(1, Start)
+            .with_level(2, AfterAny(vec![1]))
+            .with_bonus_level(3, AfterAny(vec![1]))
+            .with_chapter("Loops")
+            .with_level(4, ChapterSolved { chapter: 0, count: 2 });
+        let mut save_game = GenericProgSaveGame::new(progression, Box::new(MemSaveStorage::default()));
+        assert_eq!(save_game.get_unlocked_levels(), [1].into());
+
+        save_game.store_outcome(1, OutcomeToStore::new("Die".to_string(), "[F]".to_string()));
+        assert_eq!(save_game.get_unlocked_levels(), [1].into());
+        save_game.store_outcome(1, won("[F,F]"));
+        assert_eq!(save_game.get_unlocked_levels(), [1, 2, 3].into());
+        // Bonus level doesn't count towards chapter.
+        save_game.store_outcome(3, won("[F]"));
+        assert_eq!(save_game.get_unlocked_levels(), [1, 2, 3].into());
+        save_game.store_outcome(2, won("[F]"));
+        assert_eq!(save_game.get_unlocked_levels(), [1, 2, 3, 4].into());
+    }
+
+    #[test]
+    fn save_id_keys_record() {
+        let progression = |save_id: &str| Progression::linear(2).with_chapter("Imported").with_level(3, crate::progression::UnlockRule::Start).with_save_id(save_id);
+        let mut save_game = GenericProgSaveGame::new(progression("world-a"), Box::new(MemSaveStorage::default()));
+        save_game.store_current_prog(3, "[F]");
+        assert!(save_game.storage.get("Level_world-a_record").is_some());
+        assert!(save_game.storage.get("Level3_record").is_none());
+
+        // Another level in the same place starts afresh, and the first keeps its progress.
+        let storage = Box::new(MemSaveStorage::from(save_game.storage.root_entries()));
+        let other = GenericProgSaveGame::new(progression("world-b"), storage);
+        assert_eq!(other.get_current_prog(3), None);
+        assert_eq!(other.get_unlocked_levels(), [1, 3].into());
+        assert_eq!(save_game.get_current_prog(3).as_deref(), Some("[F]"));
+    }
+
+    #[test]
+    fn file_storage_round_trip() {
+        let path = std::env::temp_dir().join(format!("tile_engine_test_{}", std::process::id())).join("save.json");
+        let _ = std::fs::remove_file(&path);
+
+        let mut storage = FileSaveStorage::open(path.clone());
+        assert_eq!(storage.entries(), SaveEntries::new());
+        storage.set("version", SAVE_VERSION);
+        storage.set("Level1_record", r#"{"unlocked":true}"#);
+        storage.set("Level2_record", "{}");
+        storage.remove("Level2_record");
+
+        let reopened = FileSaveStorage::open(path.clone());
+        assert_eq!(reopened.entries(), entries(&[("Level1_record", r#"{"unlocked":true}"#), ("version", SAVE_VERSION)]));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn only_save_keys_replaced() {
+        use crate::save_storage::is_save_key;
+        for key in ["version", "profiles", "current_profile", "Profile:Ann/Level1_record", "Level3_unlocked"] {
+            assert!(is_save_key(key), "{key}");
+        }
+        for key in ["other_game_save", "Profiles", "theme"] {
+            assert!(!is_save_key(key), "{key}");
+        }
+
+        let mut storage = MemSaveStorage::from(entries(&[("version", SAVE_VERSION), ("Level1_record", "{}"), ("Level2_record", "{}")]));
+        storage.replace_entries(&entries(&[("version", SAVE_VERSION), ("Level1_record", r#"{"unlocked":true}"#)]));
+        assert_eq!(storage.entries(), entries(&[("Level1_record", r#"{"unlocked":true}"#), ("version", SAVE_VERSION)]));
+    }
+
+    #[test]
+    fn record_stats() {
+        let mut record = LevelRecord::default();
+        assert_eq!(record.stats().win_ratio(), None);
+        assert_eq!(record.stats().secs_to_first_solve, None);
+
+        let outcome = |outcome: &str, solution: &str| OutcomeToStore::new(outcome.to_string(), solution.to_string());
+        record.add_outcome("2025-12-20 10:00:00 UTC", SAVE_VERSION, &outcome("Die", "[F]"));
+        record.add_outcome("2025-12-20 10:00:30 UTC", SAVE_VERSION, &outcome("Die", "[F]"));
+        record.add_outcome("2025-12-20 10:01:00 UTC", SAVE_VERSION, &outcome("Esc", "[F,F,R]"));
+        record.add_outcome("2025-12-20 10:02:05 UTC", SAVE_VERSION, &outcome("Won", "[F,F,R,F]"));
+        record.add_outcome("2025-12-21 09:00:00 UTC", SAVE_VERSION, &outcome("Won", "[x2[F]]"));
+
+        let stats = record.stats();
+        assert_eq!(stats.attempts, 5);
+        assert_eq!((stats.wins, stats.fails), (2, 2));
+        assert_eq!(stats.win_ratio(), Some(0.5));
+        assert_eq!(stats.secs_to_first_solve, Some(125));
+        assert_eq!(stats.solution_lens, [
+            ("Die".to_string(), 1),
+            ("Esc".to_string(), 3),
+            ("Won".to_string(), 4),
+            ("Won".to_string(), 2),
+        ]);
+    }
+
+    #[test]
+    fn record_add_outcome() {
+        let mut record = LevelRecord::default();
+        let won = OutcomeToStore::new("Won".to_string(), "[F,F,F]".to_string());
+        record.add_outcome("day 1", SAVE_VERSION, &won);
+        record.add_outcome("day 2", SAVE_VERSION, &won);
+        record.add_outcome("day 3", SAVE_VERSION, &OutcomeToStore::new("Die".to_string(), "[F]".to_string()));
+        record.add_outcome("day 4", SAVE_VERSION, &OutcomeToStore::new("Won".to_string(), "[F,F,F,F]".to_string()));
 
-        let key = &self._level_outcomes_key(lev_idx);
-        let prev_val = self.storage().get(key).unwrap_or_default();
-        self.storage().set(key, &(prev_val + &additional_txt));
+        assert_eq!(record.attempts, 4);
+        assert_eq!(record.outcomes.iter().map(|outcome| outcome.repeats).collect::<Vec<_>>(), [1, 0, 0]);
+        assert_eq!(record.best_solution.as_deref(), Some("[F,F,F]"));
 
-        self.most_recent = Some(outcome);
+        // Round trips through json.
+        assert_eq!(LevelRecord::deserialize_json(&record.serialize_json()).unwrap(), record);
     }
 }
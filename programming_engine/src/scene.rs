@@ -4,8 +4,16 @@
 
 mod scene_base;
 pub mod arena;
+pub mod arena_event;
+pub mod arena_search;
 pub mod coding;
+pub mod coding_edit;
+pub mod prog_cursor;
+pub mod prog_rules;
 pub mod splash;
+pub mod profile_picker;
+pub mod stats;
+pub mod win_condition;
 pub mod coding_arena;
 
 pub use scene_base::*;
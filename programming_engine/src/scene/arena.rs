@@ -14,9 +14,11 @@ use std::ops::IndexMut;
 use culpa::try_fn;
 
 use super::scene_base::{BaseScene, SceneConclusion, SceneContinuation};
+use super::arena_event::ArenaEvent;
+use super::win_condition::WinCondition;
 use crate::simple_custom_props;
 use crate::for_gamedata;
-use for_gamedata::BaseMovementLogic;
+use for_gamedata::{BaseCustomProps, BaseMovementLogic};
 
 use crate::map_coords::*;
 
@@ -27,6 +29,18 @@ pub struct RosterIndex {
     ros_idx: u16,
 }
 
+/// What happens to movs which try to move past the edge of the map.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum EdgePolicy {
+    /// Edge blocks movement like a wall.
+    #[default]
+    Solid,
+    /// Moving past the edge is impassable and has Effect::Kill.
+    Deadly,
+    /// Moving past one edge comes back in at the opposite edge.
+    Wrap,
+}
+
 /// Grid together with Ros. Those are two separate classes so they can more easily be borrowed separately.
 #[derive(Clone, Debug)]
 pub struct Arena<MovementLogic: for_gamedata::BaseMovementLogic> {
@@ -35,6 +49,13 @@ pub struct Arena<MovementLogic: for_gamedata::BaseMovementLogic> {
     // Used to represent map as ascii for init and debugging. Not comprehensive.
     map_key: std::collections::HashMap<char, Vec<FreeObj<MovementLogic::CustomProps>>>,
     ready_for_next_level: Option<SceneConclusion>,
+    // What happened during the most recent advance. Cleared at start of each advance.
+    events: Vec<ArenaEvent>,
+    edge_policy: EdgePolicy,
+    // Checked after each hero move. Arena concludes successfully once met.
+    win_condition: WinCondition,
+    // Each square hero has been in, from where it started, for WinCondition::VisitInOrder.
+    hero_trail: Vec<MapCoord>,
 }
 
 impl<MovementLogic : for_gamedata::BaseMovementLogic> BaseScene for Arena<MovementLogic>
@@ -55,29 +76,42 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
         // TODO: Decide order of char, enemy. Before or after not quite right. Or need
         // to handle char moving onto enemy.
         // TODO: Consider: Maybe display char moving out of sync with enemy.
-        let hero = Roster::hero();
+        self.events.clear();
 
-        // Before movement, reset "prev". Will be overwritten if movement happens.
-        // Should be moved into obj_move*() fn.
-        self[hero].refs.prev_pos = self[hero].refs.pos;
-        self[hero].logical_props.prev_dir = self[hero].logical_props.dir;
+        self.advance_mov(Roster::hero(), cmd)?;
 
-        MovementLogic::move_mov(self, hero, cmd)?;
-        log::trace!("After mov, hero facing changed {} -> {}", self[hero].logical_props.prev_dir, self[hero].logical_props.dir);
+        // Conclude scene successfully once hero achieves what level asks.
+        if self.win_condition.is_met(self) {
+            let (obj, pos) = (self.hero(), self[self.hero()].pos());
+            self.push_event(ArenaEvent::ReachedGoal { obj, pos });
+            return SceneContinuation::Break(SceneConclusion::Succeed);
+        }
 
         for mov in self.roster.all_movs() {
-            // Before movement, reset "prev". Will be overwritten if movement happens.
-            // Going through tmp is necessary to avoid two dynamic borrows at the same time..
-            // NOTE: If map is RefCell needs to be done in two steps else runtime panic.
-            // NOTE: And obj_at() is also incompatible with RefCell.
-            self[mov].refs.prev_pos = self[mov].refs.pos;
-            self[mov].logical_props.prev_dir = self[mov].logical_props.prev_dir;
-
-            MovementLogic::move_mov(self, mov, cmd)?;
+            self.advance_mov(mov, cmd)?;
         }
         SceneContinuation::Continue(())
     }
 
+    fn advance_mov(&mut self, mov: RosterIndex, cmd: crate::ui::InputCmd) -> SceneContinuation  {
+        // Before movement, reset "prev". Will be overwritten if movement happens.
+        // Going through tmp is necessary to avoid two dynamic borrows at the same time..
+        // NOTE: If map is RefCell needs to be done in two steps else runtime panic.
+        // NOTE: And obj_at() is also incompatible with RefCell.
+        self[mov].refs.prev_pos = self[mov].refs.pos;
+        self[mov].logical_props.prev_dir = self[mov].logical_props.dir;
+
+        let continuation = MovementLogic::move_mov(self, mov, cmd);
+
+        // Movement logic changes facing directly, so record any rotation here.
+        let (prev_dir, dir) = (self[mov].logical_props.prev_dir, self[mov].logical_props.dir);
+        if prev_dir != dir {
+            self.push_event(ArenaEvent::Rotated { obj: mov, from: prev_dir, to: dir });
+        }
+
+        continuation
+    }
+
     /////////////////
     /// Initialisers
     pub fn empty(w: u16, h: u16) -> Self {
@@ -86,6 +120,10 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
             roster: Roster::new(),
             map_key: std::collections::HashMap::new(),
             ready_for_next_level: None,
+            events: vec![],
+            edge_policy: EdgePolicy::default(),
+            win_condition: WinCondition::default(),
+            hero_trail: vec![],
         }
     }
 
@@ -94,10 +132,20 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
         map_key: HashMap<char, Vec<FreeObj<MovementLogic::CustomProps>>>,
     ) -> Self
         where StrLike: Into<String> + Clone
+    {
+        Self::from_rows_and_key(ascii_map, map_key)
+    }
+
+    /// As from_map_and_key, for maps whose height isn't known at compile time, e.g. imported ones.
+    pub fn from_rows_and_key<StrLike>(
+        ascii_map: &[StrLike],
+        map_key: HashMap<char, Vec<FreeObj<MovementLogic::CustomProps>>>,
+    ) -> Self
+        where StrLike: Into<String> + Clone
     {
         let mut map = Self {
             map_key: map_key.clone(),
-            ..Self::empty(StrLike::into(ascii_map[0].clone()).len() as u16, HEIGHT as u16)
+            ..Self::empty(StrLike::into(ascii_map[0].clone()).len() as u16, ascii_map.len() as u16)
         };
 
         for (y, line) in ascii_map.iter().enumerate() {
@@ -108,9 +156,22 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
             }
         }
 
+        // Initial objs are part of the level, not spawned during play.
+        map.events.clear();
+
         map
     }
 
+    /// Set what happens at map edges. Defaults to EdgePolicy::Solid.
+    pub fn with_edge_policy(self, edge_policy: EdgePolicy) -> Self {
+        Self { edge_policy, ..self }
+    }
+
+    /// Set what hero must do to win. Defaults to WinCondition::ReachGoal.
+    pub fn with_win_condition(self, win_condition: WinCondition) -> Self {
+        Self { win_condition, ..self }
+    }
+
     //////////////////////////////////////////////
     /// Exposed upward to front end of game engine
 
@@ -122,11 +183,75 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
         self.map.h()
     }
 
+    pub fn edge_policy(&self) -> EdgePolicy {
+        self.edge_policy
+    }
+
+    pub fn win_condition(&self) -> &WinCondition {
+        &self.win_condition
+    }
+
+    /// Squares hero has been in, in order, starting with where it was spawned.
+    pub fn hero_trail(&self) -> &[MapCoord] {
+        &self.hero_trail
+    }
+
+    /// Number of objs with Effect::Collect still in the map.
+    pub fn num_collectibles(&self) -> usize {
+        self.map_locs().map(|(_, _, loc)|
+            loc.into_iter().filter(|obj| obj.logical_props.effect == simple_custom_props::Effect::Collect).count()
+        ).sum()
+    }
+
+    /// Whether square has an obj with Effect::Mark.
+    pub fn is_marked(&self, pos: MapCoord) -> bool {
+        self.any_has_effect(pos, simple_custom_props::Effect::Mark)
+    }
+
+    /// Every square with a mark, in map_locs order.
+    pub fn marked_squares(&self) -> Vec<MapCoord> {
+        self.map_locs()
+            .filter(|(_, _, loc)| loc.any_effect(simple_custom_props::Effect::Mark))
+            .map(|(x, y, _)| MapCoord::from_xy(x, y))
+            .collect()
+    }
+
+    /// Coords of a square after wrapping past the map edge, if the map wraps.
+    ///
+    /// Other policies leave coords unchanged, so they may be outside the map.
+    pub fn wrap_pos(&self, pos: MapCoord) -> MapCoord {
+        match self.edge_policy {
+            EdgePolicy::Wrap => MapCoord::from_xy(
+                pos.x.rem_euclid(self.map_w() as i16),
+                pos.y.rem_euclid(self.map_h() as i16),
+            ),
+            EdgePolicy::Solid | EdgePolicy::Deadly => pos,
+        }
+    }
+
+    /// Square at pos, after wrapping, or None if it's past the map edge.
+    pub fn get(&self, pos: MapCoord) -> Option<&Loc<MovementLogic::CustomProps>> {
+        let pos = self.wrap_pos(pos);
+        self.in_bounds(pos).then(|| &self.map[pos])
+    }
+
     // TODO: Any better way to expose this for iterating?
     pub fn map_locs(&self) -> LocIterator<MovementLogic> {
         self.map.locs()
     }
 
+    /// Events which happened during the most recent advance.
+    pub fn events(&self) -> &[ArenaEvent] {
+        &self.events
+    }
+
+    /// Record event during advance. Used by engine, and by MovementLogic for
+    /// events the engine can't detect itself, e.g. being blocked.
+    pub fn push_event(&mut self, event: ArenaEvent) {
+        log::debug!("Arena event: {event:?}");
+        self.events.push(event);
+    }
+
     //////////////////////////////////////////////////////////////////////////////////
     /// Obj spawn and move fns.
     ///
@@ -153,13 +278,69 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
             logical_props: template_obj.logical_props,
             visual_props: template_obj.visual_props,
         };
+        self.push_event(ArenaEvent::Spawned { pos, name: obj.logical_props.name.clone() });
         self.map[pos].objs.push(obj);
+        if new_roster_idx == Roster::hero() {
+            self.hero_trail = vec![pos];
+        }
+    }
+
+    /// Spawn obj which doesn't move itself below any movs in the square, e.g. a mark a mov leaves.
+    pub fn spawn_obj_under_movs_at(&mut self, pos: MapCoord, template_obj: FreeObj<MovementLogic::CustomProps>) {
+        assert!(!template_obj.logical_props.custom_props.is_any_mov(), "Can only spawn objs under movs which aren't in roster");
+        let h = self.map[pos].objs.iter().position(|obj| obj.refs.curr_roster_idx != Roster::non_mov_handle()).unwrap_or(self.map[pos].len());
+        let obj = MapObj::<MovementLogic::CustomProps>{
+            refs: Refs { curr_roster_idx: Roster::non_mov_handle(), pos, prev_pos: pos },
+            logical_props: template_obj.logical_props,
+            visual_props: template_obj.visual_props,
+        };
+        self.push_event(ArenaEvent::Spawned { pos, name: obj.logical_props.name.clone() });
+        self.map[pos].objs.insert(h, obj);
+
+        // For each object above it in location, update its mapref in roster with changed height.
+        for other_h in h as u16 + 1..self.map[pos].len() as u16 {
+            let other_roster_idx = self.map[pos][other_h].refs.curr_roster_idx;
+            if other_roster_idx != Roster::non_mov_handle() {
+                self.roster[other_roster_idx].h = other_h;
+            }
+        }
+    }
+
+    /// Remove any marks from square. False if it had none.
+    pub fn unmark(&mut self, pos: MapCoord) -> bool {
+        let mut any = false;
+        while let Some(h) = self.map[pos].objs.iter().position(|obj| obj.logical_props.effect == simple_custom_props::Effect::Mark) {
+            self.despawn_obj_at(pos, h as u16);
+            any = true;
+        }
+        any
+    }
+
+    /// Remove obj which doesn't move itself, e.g. when a mov collects it.
+    ///
+    /// Movs can't be removed as that would invalidate roster indexes.
+    pub fn despawn_obj_at(&mut self, pos: MapCoord, h: u16) {
+        assert_eq!(self.map[pos][h].refs.curr_roster_idx, Roster::non_mov_handle(), "Can only despawn objs which aren't in roster");
+        let obj = self.map[pos].objs.remove(h as usize);
+
+        // For each other object in location, update its mapref in roster with changed height.
+        for other_h in h..self.map[pos].len() as u16 {
+            let other_roster_idx = self.map[pos][other_h].refs.curr_roster_idx;
+            if other_roster_idx != Roster::non_mov_handle() {
+                self.roster[other_roster_idx].h = other_h;
+            }
+        }
+
+        self.push_event(ArenaEvent::Despawned { pos, name: obj.logical_props.name });
     }
 
     /// Move obj to a new location.
     ///
     /// Update roster and backpos.curr_pos and backpos.prev_pos. Still untested for multiple movs.
+    ///
+    /// Target is wrapped past map edge if the map wraps.
     pub fn move_obj_to(&mut self, roster_idx: RosterIndex, target_pos: MapCoord) {
+        let target_pos = self.wrap_pos(target_pos);
         let orig_pos = self.roster[roster_idx].pos();
         let orig_h = self.roster[roster_idx].h;
 
@@ -190,6 +371,23 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
         self.roster[roster_idx].x = target_pos.x;
         self.roster[roster_idx].y = target_pos.y;
         self.roster[roster_idx].h = self.map[target_pos].len() as u16 -1;
+
+        self.push_event(ArenaEvent::Moved { obj: roster_idx, from: orig_pos, to: target_pos });
+
+        if roster_idx == Roster::hero() {
+            self.hero_trail.push(target_pos);
+            self.pick_up_collectibles(roster_idx);
+        }
+    }
+
+    /// Remove any objs with Effect::Collect from mov's square, as mov passes over them.
+    fn pick_up_collectibles(&mut self, roster_idx: RosterIndex) {
+        let pos = self[roster_idx].pos();
+        while let Some(h) = self.map[pos].objs.iter().position(|obj| obj.logical_props.effect == simple_custom_props::Effect::Collect) {
+            let name = self.map[pos][h as u16].logical_props.name.clone();
+            self.push_event(ArenaEvent::PickedUp { obj: roster_idx, pos, name });
+            self.despawn_obj_at(pos, h as u16);
+        }
     }
 
     ///////////////////////////////////////////////////
@@ -209,12 +407,17 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
     }
 
     // NB: Should remove engine dependency on simple_custom_props
+    // Past the map edge only has Effect::Kill if edge is deadly.
     pub fn any_has_effect(&self, pos: MapCoord, sought_effect: simple_custom_props::Effect) -> bool {
-        self.map[pos].any_effect(sought_effect)
+        match self.get(pos) {
+            Some(loc) => loc.any_effect(sought_effect),
+            None => self.edge_policy == EdgePolicy::Deadly && sought_effect == simple_custom_props::Effect::Kill,
+        }
     }
 
+    // Past the map edge is never passable.
     pub fn passable(&self, pos: MapCoord) -> bool {
-        self.map[pos].passable()
+        self.get(pos).is_some_and(|loc| loc.passable())
     }
 
     //////////////////////////////////////////////////////
@@ -577,6 +780,11 @@ pub struct MapObj<CustomProps: for_gamedata::BaseCustomProps> {
 }
 
 impl<CustomProps: for_gamedata::BaseCustomProps> MapObj<CustomProps> {
+    /// Handle for movs. Non-mov objs all share a placeholder handle.
+    pub fn roster_idx(&self) -> RosterIndex {
+        self.refs.curr_roster_idx
+    }
+
     pub fn pos(&self) -> MapCoord {
         self.refs.pos
     }
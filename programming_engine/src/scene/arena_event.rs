@@ -0,0 +1,54 @@
+// Events describing what changed in an Arena during one tick.
+//
+// Arena collects these as it advances so that UiArena, tests, and anything
+// else interested (e.g. audio, trace recording) can see what happened without
+// inferring it from prev_pos/prev_dir or scraping debug logs.
+//
+// Both engines have a copy of this file, as they don't share a crate. Keep the copies the same.
+
+use crate::map_coords::*;
+use super::arena::RosterIndex;
+
+/// One thing which happened to an obj in the Arena during the most recent tick.
+///
+/// Movs are identified by RosterIndex. Objs which don't move themselves are
+/// identified by name and position instead.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArenaEvent {
+    /// Mov moved from one square to another.
+    Moved { obj: RosterIndex, from: MapCoord, to: MapCoord },
+    /// Mov changed facing direction.
+    Rotated { obj: RosterIndex, from: Facing, to: Facing },
+    /// Mov tried to move but the target square was impassable.
+    Blocked { obj: RosterIndex, pos: MapCoord, target: MapCoord },
+    /// Mov was moved by another mov.
+    Pushed { obj: RosterIndex, by: RosterIndex, from: MapCoord, to: MapCoord },
+    /// Mov collected an obj, which was removed from the map.
+    PickedUp { obj: RosterIndex, pos: MapCoord, name: String },
+    /// Mov was killed.
+    Died { obj: RosterIndex, pos: MapCoord },
+    /// Hero met the arena's WinCondition, e.g. reached a square with Effect::Win.
+    ReachedGoal { obj: RosterIndex, pos: MapCoord },
+    /// New obj added to map after the arena was created.
+    Spawned { pos: MapCoord, name: String },
+    /// Obj removed from map.
+    Despawned { pos: MapCoord, name: String },
+}
+
+impl ArenaEvent {
+    /// Mov which the event happened to, if any.
+    pub fn obj(&self) -> Option<RosterIndex> {
+        use ArenaEvent::*;
+        match self {
+            Moved { obj, .. } |
+            Rotated { obj, .. } |
+            Blocked { obj, .. } |
+            Pushed { obj, .. } |
+            PickedUp { obj, .. } |
+            Died { obj, .. } |
+            ReachedGoal { obj, .. } => Some(*obj),
+            Spawned { .. } |
+            Despawned { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,112 @@
+// Queries over the Arena map: line of sight, shortest paths and reachability.
+//
+// Used by movement logic for AI decisions, and by tests to check levels are
+// solvable at all. Passability is pluggable so callers can decide whether e.g.
+// other movs block the way.
+//
+// Both engines have a copy of this file, as they don't share a crate. Keep the copies the same.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::for_gamedata::BaseMovementLogic;
+use crate::map_coords::*;
+use crate::simple_custom_props::Pass;
+
+use super::arena::Arena;
+
+impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
+    /// Whether pos is within the map.
+    pub fn in_bounds(&self, pos: MapCoord) -> bool {
+        (0..self.map_w() as i16).contains(&pos.x) && (0..self.map_h() as i16).contains(&pos.y)
+    }
+
+    /// Whether movs can see through a square. Only solid objs block sight, and
+    /// past the map edge is never transparent.
+    pub fn transparent(&self, pos: MapCoord) -> bool {
+        self.get(pos).is_some_and(|loc| loc.into_iter().all(|obj| obj.logical_props.pass != Pass::Solid))
+    }
+
+    /// Whether a straight line from one square to another passes only through
+    /// transparent squares. The squares at each end don't block sight.
+    pub fn line_of_sight(&self, from: MapCoord, to: MapCoord) -> bool {
+        squares_between(from, to).into_iter().all(|pos| self.transparent(pos))
+    }
+
+    /// Shortest orthogonal path from one square to another, or None if there isn't one.
+    /// Steps past the edge of a wrapping map come back in at the opposite edge.
+    ///
+    /// Returned path excludes `from` and ends with `to`. Neither end is tested with
+    /// `passable`, so it can be used to find a path from one mov to another.
+    pub fn shortest_path(&self, from: MapCoord, to: MapCoord, passable: impl Fn(MapCoord) -> bool) -> Option<Vec<MapCoord>> {
+        // Breadth first search, recording where each square was first reached from.
+        let mut came_from = HashMap::from([(from, from)]);
+        let mut frontier = VecDeque::from([from]);
+        while let Some(pos) = frontier.pop_front() {
+            if pos == to {
+                let mut path = vec![];
+                let mut step = to;
+                while step != from {
+                    path.push(step);
+                    step = came_from[&step];
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.orthogonal_neighbours(pos) {
+                if !came_from.contains_key(&next) && (next == to || passable(next)) {
+                    came_from.insert(next, pos);
+                    frontier.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// All squares reachable by orthogonal steps from a starting square, including itself.
+    ///
+    /// The starting square isn't tested with `passable`.
+    pub fn reachable_from(&self, from: MapCoord, passable: impl Fn(MapCoord) -> bool) -> HashSet<MapCoord> {
+        let mut reached = HashSet::from([from]);
+        let mut frontier = vec![from];
+        while let Some(pos) = frontier.pop() {
+            for next in self.orthogonal_neighbours(pos) {
+                if !reached.contains(&next) && passable(next) {
+                    reached.insert(next);
+                    frontier.push(next);
+                }
+            }
+        }
+        reached
+    }
+
+    fn orthogonal_neighbours(&self, pos: MapCoord) -> Vec<MapCoord> {
+        [(0, -1), (1, 0), (0, 1), (-1, 0)].into_iter()
+            .map(|(dx, dy)| self.wrap_pos(pos + CoordDelta::from_xy(dx, dy)))
+            .filter(|next| self.in_bounds(*next))
+            .collect()
+    }
+}
+
+/// Squares strictly between two coords along a straight (Bresenham) line.
+fn squares_between(from: MapCoord, to: MapCoord) -> Vec<MapCoord> {
+    let (dx, dy) = ((to.x - from.x).abs(), -(to.y - from.y).abs());
+    let (step_x, step_y) = ((to.x - from.x).signum(), (to.y - from.y).signum());
+    let mut err = dx + dy;
+    let mut pos = from;
+    let mut squares = vec![];
+    while pos != to {
+        let err2 = 2 * err;
+        if err2 >= dy {
+            err += dy;
+            pos.x += step_x;
+        }
+        if err2 <= dx {
+            err += dx;
+            pos.y += step_y;
+        }
+        if pos != to {
+            squares.push(pos);
+        }
+    }
+    squares
+}
@@ -1,14 +1,19 @@
+use super::coding_edit::EditHistory;
+use super::prog_rules::ProgRule;
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct ActionData {
     pub blocked: bool,
+    // Placed by level, and can't be moved or removed.
+    pub locked: bool,
 }
 
 impl ActionData {
     pub const fn default() -> Self {
-        ActionData {blocked: false}
+        ActionData {blocked: false, locked: false}
     }
     pub const fn blocked() -> Self {
-        ActionData {blocked: true}
+        ActionData {blocked: true, locked: false}
     }
 }
 
@@ -17,6 +22,10 @@ pub enum ActionOpcode {
     F,
     L,
     R,
+    // Put a mark on bot's square, or blocked if already marked.
+    Mark,
+    // Remove mark from bot's square, or blocked if not marked.
+    Unmark,
     No, // Only used during testing. Test treats it as always failing.
 }
 
@@ -28,6 +37,8 @@ impl ActionOpcode {
             F => "F",
             L => "L",
             R => "R",
+            Mark => "Mark",
+            Unmark => "Unmark",
             No => "No",
         }.to_string()
     }
@@ -47,6 +58,8 @@ pub enum ParentOpcode {
     LOOP,
     loop5,
     Else,
+    // Runs contents once if bot is on a mark, else skipped.
+    IfMark,
 }
 
 impl std::fmt::Display for ParentOpcode {
@@ -64,9 +77,15 @@ impl ParentOpcode {
             x2 => 1,
             loop5 => 5,
             Else => 999,
+            IfMark => 999,
         }
     }
 
+    // Whether repeat count depends on what bot senses, not just the prog.
+    pub fn senses_world(&self) -> bool {
+        matches!(self, ParentOpcode::IfMark)
+    }
+
     // Used for representing a single instruction graphically
     pub fn as_text(&self) -> String {
         use ParentOpcode::*;
@@ -76,6 +95,7 @@ impl ParentOpcode {
             x2 => "x2",
             loop5 => "loop5",
             Else => "Else",
+            IfMark => "IfMark",
         }.to_string()
     }
 }
@@ -97,34 +117,45 @@ impl std::fmt::Display for Opcode {
             Action(F) => std::fmt::Display::fmt("F", f),
             Action(L) => std::fmt::Display::fmt("L", f),
             Action(R) => std::fmt::Display::fmt("R", f),
+            Action(Mark) => std::fmt::Display::fmt("Mark", f),
+            Action(Unmark) => std::fmt::Display::fmt("Unmark", f),
             Action(No) => std::fmt::Display::fmt("No", f),
             Parent(group) => std::fmt::Display::fmt("group", f),
             Parent(LOOP) => std::fmt::Display::fmt("loop", f),
             Parent(x2) => std::fmt::Display::fmt("x2", f),
             Parent(loop5) => std::fmt::Display::fmt("loop5", f),
             Parent(Else) => std::fmt::Display::fmt("Else", f),
+            Parent(IfMark) => std::fmt::Display::fmt("IfMark", f),
         }
     }
 }
 
 impl From<&str> for Opcode {
     fn from(txt: &str) -> Self {
-        match txt {
-            "F" => Opcode::Action(ActionOpcode::F),
-            "L" => Opcode::Action(ActionOpcode::L),
-            "R" => Opcode::Action(ActionOpcode::R),
-            "No" => Opcode::Action(ActionOpcode::No),
-            "group" => Opcode::Parent(ParentOpcode::group),
-            "loop" => Opcode::Parent(ParentOpcode::LOOP),
-            "x2" => Opcode::Parent(ParentOpcode::x2),
-            "loop5" => Opcode::Parent(ParentOpcode::loop5),
-            "Else" => Opcode::Parent(ParentOpcode::Else),
-            _ => panic!("Unrecognised txt for instr: {}", txt)
-        }
+        Self::from_text(txt).unwrap_or_else(|| panic!("Unrecognised txt for instr: {}", txt))
     }
 }
 
 impl Opcode {
+    // Accepts both the "Friendly" representation and as_text (which differ for LOOP).
+    pub fn from_text(txt: &str) -> Option<Self> {
+        match txt {
+            "F" => Some(Opcode::Action(ActionOpcode::F)),
+            "L" => Some(Opcode::Action(ActionOpcode::L)),
+            "R" => Some(Opcode::Action(ActionOpcode::R)),
+            "Mark" => Some(Opcode::Action(ActionOpcode::Mark)),
+            "Unmark" => Some(Opcode::Action(ActionOpcode::Unmark)),
+            "No" => Some(Opcode::Action(ActionOpcode::No)),
+            "group" => Some(Opcode::Parent(ParentOpcode::group)),
+            "loop" | "LOOP" => Some(Opcode::Parent(ParentOpcode::LOOP)),
+            "x2" => Some(Opcode::Parent(ParentOpcode::x2)),
+            "loop5" => Some(Opcode::Parent(ParentOpcode::loop5)),
+            "Else" => Some(Opcode::Parent(ParentOpcode::Else)),
+            "IfMark" => Some(Opcode::Parent(ParentOpcode::IfMark)),
+            _ => None,
+        }
+    }
+
     pub fn as_text(&self) -> String {
         use Opcode::*;
         match self {
@@ -156,6 +187,13 @@ impl Instr {
         }
     }
 
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Instr::Action(op, _) => Opcode::Action(*op),
+            Instr::Parent(op, _) => Opcode::Parent(*op),
+        }
+    }
+
     pub fn has_opcode(&self, op: Opcode) -> bool {
         match self {
             Instr::Action(opcode_a, _) => matches!(&op, Opcode::Action(opcode_b) if opcode_a == opcode_b ),
@@ -220,7 +258,7 @@ impl Instr {
 
     // TODO: Move to fn of ControlFlowOp not Op.
     // More naturally part of opcode.
-    pub fn repeat_count(&self, subprog: &Subprog) -> usize {
+    pub fn repeat_count(&self, subprog: &Subprog, senses: Senses) -> usize {
         // assert!(std::ptr::eq(self, *subprog.instrs.get(idx).as_ref().unwrap()));
         use Instr::*;
         use ParentOpcode::*;
@@ -237,6 +275,7 @@ impl Instr {
                 log::debug!("Calculating Else repeat count as 0 in {subprog}");
                 0
             },
+            Parent(IfMark, _) => senses.on_mark as usize,
         }
     }
 
@@ -247,6 +286,32 @@ impl Instr {
         }
     }
 
+    /// Placed by the level, and can't be moved or removed. Instrs can still be added inside a
+    /// locked parent instr.
+    pub fn is_locked(&self) -> bool {
+        match self {
+            Instr::Action(_, data) => data.locked,
+            Instr::Parent(_, subprog) => subprog.locked,
+        }
+    }
+
+    /// Same instr, locked.
+    pub fn locked(mut self) -> Self {
+        match &mut self {
+            Instr::Action(_, data) => data.locked = true,
+            Instr::Parent(_, subprog) => subprog.locked = true,
+        }
+        self
+    }
+
+    /// Locked, or has a locked instr inside it, so can't be moved or removed.
+    pub fn contains_locked(&self) -> bool {
+        match self {
+            Instr::Action(_, data) => data.locked,
+            Instr::Parent(_, subprog) => subprog.locked || subprog.instrs.iter().any(Instr::contains_locked),
+        }
+    }
+
     pub fn h_len(&self) -> usize {
         match &self {
             Instr::Parent(_, subprog) => 1 + subprog.h_len(),
@@ -356,7 +421,9 @@ pub struct Subprog {
     // When used for iteration, counts number of times current execution of parent instr has executed this subprog.
     pub counter: usize,
     // Vector of one or more instrs to execute. Some parent ops have a specific number of nested instrs.
-    pub instrs: Vec<Instr>
+    pub instrs: Vec<Instr>,
+    // Whether parent instr with this subprog is locked. See Instr::is_locked.
+    pub locked: bool,
 }
 
 impl From<Vec<Instr>> for Subprog {
@@ -408,6 +475,29 @@ fn find_unmatched_closing_bracket(txt: &str) -> Option<usize> {
 
 impl Subprog {
     pub fn from_text(txt: &str) -> Self {
+        Self::try_from_text(txt).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Parse nested program text like "F,group[R,F]", or the bracketed form written
+    /// by Display like "[F,group[R,F]]". Err if the text isn't a valid program.
+    ///
+    /// Locked instrs have "#" after them, like "F#,group#[R]".
+    pub fn try_from_text(txt: &str) -> Result<Self, String> {
+        let txt = txt.trim();
+        match txt.strip_prefix('[') {
+            Some(inner) if find_unmatched_closing_bracket(inner).map(|idx| idx + 1) == Some(inner.len()) =>
+                Self::parse_instrs(&inner[..inner.len() - 1]),
+            _ => Self::parse_instrs(txt),
+        }
+    }
+
+    /// Program text as typed by players, like "F,group[R,F]". Parses with try_from_text.
+    pub fn as_instrs_text(&self) -> String {
+        let txt = self.to_string();
+        txt[1..txt.len() - 1].to_string()
+    }
+
+    fn parse_instrs(txt: &str) -> Result<Self, String> {
         let mut ret = Self::default();
         let mut remaining = txt;
         while remaining.len() > 0 {
@@ -424,31 +514,73 @@ impl Subprog {
                 )
             };
 
-            ret.instrs.push(segment.trim().into());
-
-            match ret.instrs.last_mut().unwrap() {
+            let (segment, locked) = match segment.trim().strip_suffix('#') {
+                Some(unlocked_segment) => (unlocked_segment.trim(), true),
+                None => (segment.trim(), false),
+            };
+            let op = Opcode::from_text(segment).ok_or_else(|| format!("Unrecognised txt for instr: '{segment}'"))?;
+            let instr = if locked {Instr::from_opcode(op).locked()} else {Instr::from_opcode(op)};
+            match instr {
                 instr @ Instr::Action(_, _) => {
-                    assert_eq!(sep, ",", "Expected ',' after {instr}");
+                    if sep != "," {
+                        return Err(format!("Expected ',' after {instr}"));
+                    }
+                    ret.instrs.push(instr);
                     remaining = trailing;
                 },
-                ref mut instr @ Instr::Parent(..) => {
-                    assert_eq!(sep, "[", "Expected '[' after {instr}");
-                    let idy = find_unmatched_closing_bracket(trailing).unwrap();
+                mut instr @ Instr::Parent(..) => {
+                    if sep != "[" {
+                        return Err(format!("Expected '[' after {instr}"));
+                    }
+                    let idy = find_unmatched_closing_bracket(trailing).ok_or_else(|| format!("Expected ']' in {trailing}"))?;
                     let (subprog_txt, rest_of_string) = (&trailing[0..idy], &trailing[idy+1..]);
-                    *instr.as_parent_subprog_mut() = Self::from_text(subprog_txt);
+                    instr.as_parent_subprog_mut().instrs = Self::parse_instrs(subprog_txt)?.instrs;
+                    ret.instrs.push(instr);
 
                     let rest_of_string = rest_of_string.trim();
                     if rest_of_string.is_empty() {
                         remaining = rest_of_string;
+                    } else if let Some(rest_of_string) = rest_of_string.strip_prefix(',') {
+                        remaining = rest_of_string;
                     } else {
-                        assert!(rest_of_string.starts_with(','), "Expected ',' after ']' in {trailing} at {idy}");
-                        remaining = &rest_of_string[1..];
-
+                        return Err(format!("Expected ',' after ']' in {trailing} at {idy}"));
                     }
                 } ,
             }
         }
-        ret
+        Ok(ret)
+    }
+
+    /// Count of each opcode the program takes from the supply, including in nested subprogs.
+    /// Locked instrs are placed by the level so aren't counted.
+    pub fn supply_counts(&self) -> Vec<(Opcode, u16)> {
+        let mut counts: Vec<(Opcode, u16)> = vec![];
+        for instr in &self.instrs {
+            let nested = match instr {
+                Instr::Parent(_, subprog) => subprog.supply_counts(),
+                Instr::Action(..) => vec![],
+            };
+            let own = (!instr.is_locked()).then_some((instr.opcode(), 1));
+            for (op, count) in own.into_iter().chain(nested) {
+                match counts.iter_mut().find(|(counted_op, _)| *counted_op == op) {
+                    Some((_, total)) => *total += count,
+                    None => counts.push((op, count)),
+                }
+            }
+        }
+        counts
+    }
+
+    /// Locked instrs, and the parent instrs they're in, without any other instrs. Edits
+    /// mustn't change this.
+    pub fn locked_outline(&self) -> Subprog {
+        let instrs = self.instrs.iter().filter_map(|instr| match instr {
+            Instr::Parent(op, subprog) if instr.contains_locked() =>
+                Some(Instr::Parent(*op, Subprog { locked: subprog.locked, ..subprog.locked_outline() })),
+            Instr::Action(op, data) if data.locked => Some(Instr::Action(*op, ActionData::default()).locked()),
+            _ => None,
+        }).collect::<Vec<_>>();
+        Subprog::from(instrs)
     }
 }
 
@@ -465,6 +597,9 @@ impl std::fmt::Display for Subprog {
         for (idx, instr) in self.instrs.iter().enumerate() {
             if idx >0 {write!(f, ",")?}
             write!(f, "{}", instr.as_text())?;
+            if instr.is_locked() {
+                write!(f, "#")?;
+            }
             if let Instr::Parent(_, subprog) = &instr {
                 write!(f, "{}", subprog)?;
             }
@@ -529,7 +664,7 @@ impl std::ops::IndexMut<i16> for Subprog {
 
 impl Subprog {
     pub const fn default() -> Self {
-        Subprog {counter: 0, curr_ip: 0, prev_ip: 0, instrs: vec![]}
+        Subprog {counter: 0, curr_ip: 0, prev_ip: 0, instrs: vec![], locked: false}
     }
 
     // Number of instructions wide if laid out vertically. i.e. nesting depth.
@@ -577,11 +712,28 @@ impl Subprog {
         self.curr_action_instr().unwrap()
     }
 
-    fn advance_ip(&mut self) {
+    fn advance_ip(&mut self, senses: Senses) {
         self.curr_ip += 1;
         // Skip over any repeat-0 instr.
-        if matches!(self.curr_instr(), Some(instr @ Instr::Parent(..)) if instr.repeat_count(self) == 0) {
-            self.advance_ip();
+        if matches!(self.curr_instr(), Some(instr @ Instr::Parent(..)) if instr.repeat_count(self, senses) == 0) {
+            self.advance_ip(senses);
+        }
+    }
+
+    /// Skip instrs whose condition on the world isn't met, e.g. IfMark when bot isn't on a
+    /// mark, including first instrs of parent instrs being entered.
+    ///
+    /// Else is decided as the prog advances instead, from the instr before it.
+    pub fn skip_unmet_conditions(&mut self, senses: Senses) {
+        while let Some(instr @ Instr::Parent(op, _)) = self.curr_instr() {
+            let unmet = op.senses_world() && instr.repeat_count(self, senses) == 0;
+            if !unmet {
+                break;
+            }
+            self.curr_ip += 1;
+        }
+        if let Some(Instr::Parent(_, subprog)) = self.curr_instr_mut() {
+            subprog.skip_unmet_conditions(senses);
         }
     }
 
@@ -595,16 +747,16 @@ impl Subprog {
         self.counter += 1;
     }
 
-    fn advance_current_subprog(&mut self, parent_op: &Instr) {
-        let repeat_count = parent_op.repeat_count(self);
+    fn advance_current_subprog(&mut self, parent_op: &Instr, senses: Senses) {
+        let repeat_count = parent_op.repeat_count(self, senses);
         let subprog = self.instrs.get_mut(self.curr_ip).unwrap().as_parent_subprog_mut();
-        subprog.advance_next_instr();
+        subprog.advance_next_instr_sensing(senses);
         if subprog.finished() {
             if subprog.counter + 1 < repeat_count {
                 subprog.iterate();
             } else {
                 subprog.reset();
-                self.advance_ip();
+                self.advance_ip(senses);
             }
         }
     }
@@ -616,6 +768,12 @@ impl Subprog {
     //
     // Returns Some(), or None if program wrapped round.
     pub fn advance_next_instr(&mut self) {
+        self.advance_next_instr_sensing(Senses::default());
+    }
+
+    // Advances control flow state, deciding instrs which test the world by what bot senses
+    // from where it is now.
+    pub fn advance_next_instr_sensing(&mut self, senses: Senses) {
         log::debug!("------");
         self.prev_ip = self.curr_ip;
         if self.finished() {
@@ -625,9 +783,10 @@ impl Subprog {
 
         let op = &self.instrs.get_mut(self.curr_ip).unwrap().clone();
         match op {
-            Instr::Action(..) => self.advance_ip(),
-            Instr::Parent(..) => self.advance_current_subprog(op),
+            Instr::Action(..) => self.advance_ip(senses),
+            Instr::Parent(..) => self.advance_current_subprog(op, senses),
         }
+        self.skip_unmet_conditions(senses);
         assert!(self.curr_action_instr().is_none() || matches!(self.curr_action_instr(), Some(Instr::Action(..))));
         log::debug!("Advanced prog to {:?}.", self); // to #{}. Next: #{}.", self, self.prev_ip, self.next_ip);
     }
@@ -635,10 +794,24 @@ impl Subprog {
 
 pub use Subprog as Prog;
 
+/// What bot senses about the world around it, for instrs which test it, e.g. IfMark.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Senses {
+    /// Bot's square has a mark.
+    pub on_mark: bool,
+}
+
 #[derive(Clone, Debug)]
 pub struct Coding {
     pub supply: Vec<Bin>,
     pub prog: Subprog,
+    // Prog the level starts with, e.g. a broken prog to fix. Its locked instrs stay in
+    // every prog, and resetting goes back to it.
+    pub start_prog: Subprog,
+    // Limits on prog's shape, which it must keep to before it can run.
+    pub rules: Vec<ProgRule>,
+    // Edits made through Coding::edit, for undo and redo.
+    pub history: EditHistory,
 }
 
 impl Coding {
@@ -648,7 +821,61 @@ impl Coding {
             Bin::new(*op, *count)
             ).collect(),
             prog: Subprog::default(),
+            start_prog: Subprog::default(),
+            rules: vec![],
+            history: EditHistory::default(),
+        }
+    }
+
+    /// Start level with prog, instead of an empty one. Instrs in it which aren't locked
+    /// are taken from the supply bins.
+    pub fn with_start_prog(mut self, prog: Subprog) -> Self {
+        self.start_prog = prog.clone();
+        if let Err(err) = self.restore_prog(prog) {
+            panic!("Level's start prog doesn't fit its supply: {err}");
         }
+        self
+    }
+
+    /// Return every instr in the prog to its supply bin, leaving the start prog.
+    pub fn reset_prog(&mut self) {
+        for bin in &mut self.supply {
+            bin.curr_count = bin.orig_count;
+        }
+        for (op, count) in self.start_prog.supply_counts() {
+            if let Some(bin) = self.supply.iter_mut().find(|bin| bin.op == op) {
+                bin.curr_count = bin.curr_count.saturating_sub(count);
+            }
+        }
+        self.prog = self.start_prog.clone();
+    }
+
+    /// Replace the prog with a previously saved one, taking its instrs from the supply bins.
+    ///
+    /// Err if the supply doesn't have enough of each instr (e.g. the level has changed
+    /// since the prog was saved), or the prog doesn't keep the level's locked instrs, in
+    /// which case the prog is left as the start prog.
+    pub fn restore_prog(&mut self, prog: Subprog) -> Result<(), String> {
+        self.reset_prog();
+        if prog.locked_outline() != self.start_prog.locked_outline() {
+            return Err("Prog doesn't keep the level's locked instrs in place".to_string());
+        }
+        let counts = prog.supply_counts();
+        for (op, count) in &counts {
+            let available = self.supply.iter().find(|bin| bin.op == *op).map_or(0, |bin| bin.orig_count);
+            if *count > available {
+                return Err(format!("Prog uses {count} {op} but only {available} available"));
+            }
+        }
+        for bin in &mut self.supply {
+            bin.curr_count = bin.orig_count;
+        }
+        for (op, count) in counts {
+            let bin = self.supply.iter_mut().find(|bin| bin.op == op).unwrap();
+            bin.curr_count -= count;
+        }
+        self.prog = prog;
+        Ok(())
     }
 }
 
@@ -659,6 +886,8 @@ pub mod action_ops {
     pub const F: ActionOpcode = ActionOpcode::F;
     pub const L: ActionOpcode = ActionOpcode::L;
     pub const R: ActionOpcode = ActionOpcode::R;
+    pub const Mark: ActionOpcode = ActionOpcode::Mark;
+    pub const Unmark: ActionOpcode = ActionOpcode::Unmark;
     pub const No: ActionOpcode = ActionOpcode::No;
 }
 
@@ -669,6 +898,8 @@ pub mod supply_ops {
     pub const F: Opcode = Opcode::Action(ActionOpcode::F);
     pub const L: Opcode = Opcode::Action(ActionOpcode::L);
     pub const R: Opcode = Opcode::Action(ActionOpcode::R);
+    pub const Mark: Opcode = Opcode::Action(ActionOpcode::Mark);
+    pub const Unmark: Opcode = Opcode::Action(ActionOpcode::Unmark);
     pub const No: Opcode = Opcode::Action(ActionOpcode::No);
 
     pub const x2: Opcode = Opcode::Parent(ParentOpcode::x2);
@@ -676,6 +907,7 @@ pub mod supply_ops {
     pub const loop5: Opcode = Opcode::Parent(ParentOpcode::loop5);
     pub const LOOP: Opcode = Opcode::Parent(ParentOpcode::LOOP);
     pub const Else: Opcode = Opcode::Parent(ParentOpcode::Else);
+    pub const IfMark: Opcode = Opcode::Parent(ParentOpcode::IfMark);
 }
 
 pub mod prog_ops {
@@ -686,17 +918,20 @@ pub mod prog_ops {
     pub const F: Instr = Instr::Action(ActionOpcode::F, ActionData::default());
     pub const L: Instr = Instr::Action(ActionOpcode::L, ActionData::default());
     pub const R: Instr = Instr::Action(ActionOpcode::R, ActionData::default());
+    pub const Mark: Instr = Instr::Action(ActionOpcode::Mark, ActionData::default());
+    pub const Unmark: Instr = Instr::Action(ActionOpcode::Unmark, ActionData::default());
     pub const No: Instr = Instr::Action(ActionOpcode::No, ActionData::blocked());
 
     // TODO: Introduce fn if we first subsume Subprog into ParentOp
     // pub fn x2(ops: Vec<Op>) -> Op = Op::Parent(ParentOp::x2);
 
     // TODO: make Subprog::default a const function to avoid duplication.
-    pub const default_subprog: Subprog = Subprog {counter: 0, curr_ip: 0, prev_ip: 0, instrs: vec![]};
+    pub const default_subprog: Subprog = Subprog {counter: 0, curr_ip: 0, prev_ip: 0, instrs: vec![], locked: false};
     pub const x2: Instr = Instr::Parent(ParentOpcode::x2, default_subprog);
     pub const group: Instr = Instr::Parent(ParentOpcode::group, default_subprog);
     pub const loop5: Instr = Instr::Parent(ParentOpcode::loop5, default_subprog);
     pub const Else: Instr = Instr::Parent(ParentOpcode::Else, default_subprog);
+    pub const IfMark: Instr = Instr::Parent(ParentOpcode::IfMark, default_subprog);
 }
 
 pub mod prog_fn_ops {
@@ -707,6 +942,8 @@ pub mod prog_fn_ops {
     pub const F: Instr = Instr::Action(ActionOpcode::F, ActionData::default());
     pub const L: Instr = Instr::Action(ActionOpcode::L, ActionData::default());
     pub const R: Instr = Instr::Action(ActionOpcode::R, ActionData::default());
+    pub const Mark: Instr = Instr::Action(ActionOpcode::Mark, ActionData::default());
+    pub const Unmark: Instr = Instr::Action(ActionOpcode::Unmark, ActionData::default());
     pub const No: Instr = Instr::Action(ActionOpcode::No, ActionData::blocked());
 
     pub fn group(ops: &[Instr]) -> Instr { Instr::Parent(ParentOpcode::group, Subprog::from(ops)) }
@@ -714,6 +951,7 @@ pub mod prog_fn_ops {
     pub fn LOOP(ops: &[Instr]) -> Instr { Instr::Parent(ParentOpcode::LOOP, Subprog::from(ops)) }
     pub fn loop5(ops: &[Instr]) -> Instr { Instr::Parent(ParentOpcode::loop5, Subprog::from(ops)) }
     pub fn Else(ops: &[Instr]) -> Instr { Instr::Parent(ParentOpcode::Else, Subprog::from(ops)) }
+    pub fn IfMark(ops: &[Instr]) -> Instr { Instr::Parent(ParentOpcode::IfMark, Subprog::from(ops)) }
 }
 
 #[cfg(test)]
@@ -776,6 +1014,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_prog_errors() {
+        use prog_fn_ops::*;
+        assert_eq!(Prog::try_from_text("[F,LOOP[R,F]]"), Ok(Prog::from(vec![F, LOOP(&[R, F])])));
+        assert_eq!(Prog::try_from_text("[]"), Ok(Prog::default()));
+        assert!(Prog::try_from_text("F,Jump").is_err());
+        assert!(Prog::try_from_text("F[R]").is_err());
+        assert!(Prog::try_from_text("group,F").is_err());
+        assert!(Prog::try_from_text("group[F").is_err());
+        assert!(Prog::try_from_text("group[F]R").is_err());
+    }
+
+    #[test]
+    fn parse_locked_prog() {
+        use prog_fn_ops::*;
+        let prog = Prog::from_text("F#, group#[R, x2 # [F]], L");
+        assert_eq!(prog, Prog::from(vec![F.locked(), group(&[R, x2(&[F]).locked()]).locked(), L]));
+        assert_eq!(prog.to_string(), "[F#,group#[R,x2#[F]],L]");
+        assert_eq!(Prog::from_text(&prog.to_string()), prog);
+
+        assert_eq!(prog.supply_counts(), [(supply_ops::R, 1), (supply_ops::F, 1), (supply_ops::L, 1)]);
+        assert_eq!(prog.locked_outline(), Prog::from(vec![F.locked(), group(&[x2(&[]).locked()]).locked()]));
+        assert!(Prog::try_from_text("F#R").is_err());
+    }
+
+    #[test]
+    fn restore_and_reset_prog() {
+        use prog_fn_ops::*;
+        let mut coding = Coding::from_vec(&[(supply_ops::F, 3), (supply_ops::R, 1), (supply_ops::x2, 1)]);
+        let prog = Prog::from(vec![F, x2(&[F, R])]);
+        assert_eq!(prog.supply_counts(), [(supply_ops::F, 2), (supply_ops::x2, 1), (supply_ops::R, 1)]);
+
+        coding.restore_prog(prog.clone()).unwrap();
+        assert_eq!(coding.prog, prog);
+        assert_eq!(coding.supply.iter().map(|bin| bin.curr_count).collect::<Vec<_>>(), [1, 0, 0]);
+
+        // Restoring again starts from a full supply, not the current one.
+        coding.restore_prog(prog.clone()).unwrap();
+        assert_eq!(coding.supply.iter().map(|bin| bin.curr_count).collect::<Vec<_>>(), [1, 0, 0]);
+
+        // Not enough instrs in supply.
+        assert!(coding.restore_prog(Prog::from(vec![R, R])).is_err());
+        assert_eq!(coding.prog, Prog::default());
+        assert!(coding.restore_prog(Prog::from(vec![L])).is_err());
+
+        coding.restore_prog(prog).unwrap();
+        coding.reset_prog();
+        assert_eq!(coding.prog, Prog::default());
+        assert_eq!(coding.supply.iter().map(|bin| bin.curr_count).collect::<Vec<_>>(), [3, 1, 1]);
+    }
+
     fn run_prog_and_test(mut prog: Prog, expected_ops: &[ActionOpcode]) {
         for (idx, expected_op) in expected_ops.iter().enumerate() {
             assert!(!prog.finished());
@@ -875,4 +1164,22 @@ mod tests {
             run_prog_and_test(prog, &[F, No, L]);
         }
     }
+
+    #[test]
+    fn test_if_mark() {
+        initialise_logging_for_tests();
+        use prog_fn_ops::*;
+        // Unmark only squares with a mark, including when IfMark is first in the loop.
+        let mut prog = Prog::from(vec![LOOP(&[IfMark(&[Unmark]), F])]);
+        let on_mark = |on_mark| Senses { on_mark };
+        prog.skip_unmet_conditions(on_mark(false));
+        assert_eq!(prog.unwrap_curr_op().as_action_op(), action_ops::F);
+        prog.advance_next_instr_sensing(on_mark(true));
+        assert_eq!(prog.unwrap_curr_op().as_action_op(), action_ops::Unmark);
+        prog.advance_next_instr_sensing(on_mark(false));
+        assert_eq!(prog.unwrap_curr_op().as_action_op(), action_ops::F);
+        prog.advance_next_instr_sensing(on_mark(false));
+        assert_eq!(prog.unwrap_curr_op().as_action_op(), action_ops::F);
+        assert_eq!(Prog::from_text("LOOP[IfMark[Unmark], Mark]"), Prog::from(vec![LOOP(&[IfMark(&[Unmark]), Mark])]));
+    }
 }
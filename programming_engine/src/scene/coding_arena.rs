@@ -12,12 +12,23 @@ pub enum CodingRunningPhase {
 #[derive(Clone, Debug)]
 pub struct CodingArena<MovementLogic : for_gamedata::BaseMovementLogic> {
     pub init_arena: Arena<MovementLogic>,
+    // Further maps prog is run on after init_arena, e.g. with bot starting somewhere else.
+    // Level is only won if prog solves every map.
+    pub more_arenas: Vec<Arena<MovementLogic>>,
+    // Map running, or last run: 0 for init_arena, then each of more_arenas.
+    pub curr_test: usize,
+    // Whether prog solved each map in latest run, or None if run didn't get to it.
+    pub test_results: Vec<Option<bool>>,
     // Maybe move into Running state, not core data?
     pub curr_arena: Option<Arena<MovementLogic>>,
     pub coding: Coding,
     pub phase: CodingRunningPhase,
     ready_for_next_level: Option<SceneConclusion>,
     outcome_to_store: Option<OutcomeToStore>,
+    // Player asked to see stats and history, which engine shows in place of this scene.
+    stats_requested: bool,
+    // Playing back a stored solution. Read-only, and not recorded as an attempt.
+    replay: bool,
 }
 
 impl<MovementLogic : for_gamedata::BaseMovementLogic> BaseScene for CodingArena<MovementLogic>
@@ -40,10 +51,16 @@ impl<MovementLogic : for_gamedata::BaseMovementLogic> BaseScene for CodingArena<
                         if conclusion == Some(for_gamedata::SceneConclusion::Fail) {
                             // TODO: Call died() instead when bot dies for other reasons.
                             log::debug!("Ran off end of program.");
+                            self.test_results[self.curr_test] = Some(false);
                             self.finish();
                         } else if conclusion == Some(for_gamedata::SceneConclusion::Succeed) {
                             log::debug!("Bot found target!");
-                            self.won();
+                            self.test_results[self.curr_test] = Some(true);
+                            if self.curr_test + 1 < self.num_tests() {
+                                self.start_test(self.curr_test + 1);
+                            } else {
+                                self.won();
+                            }
                         } else if conclusion == None {
                             log::trace!("Bot advanced normally. Continue executing program.");
                         }
@@ -82,14 +99,56 @@ impl<MovementLogic: for_gamedata::BaseMovementLogic> CodingArena<MovementLogic>
     ) -> Self {
         Self {
             init_arena: arena,
+            more_arenas: vec![],
+            curr_test: 0,
+            test_results: vec![None],
             curr_arena: None,
             coding: code,
             phase: CodingRunningPhase::Coding,
             ready_for_next_level: None,
             outcome_to_store: None,
+            stats_requested: false,
+            replay: false,
         }
     }
 
+    /// Also test prog on another map, after init_arena and any others.
+    pub fn with_test_arena(mut self, arena: Arena<MovementLogic>) -> Self {
+        self.more_arenas.push(arena);
+        self.test_results.push(None);
+        self
+    }
+
+    /// Number of maps prog is tested on.
+    pub fn num_tests(&self) -> usize {
+        1 + self.more_arenas.len()
+    }
+
+    pub fn test_arena(&self, test_idx: usize) -> &Arena<MovementLogic> {
+        match test_idx {
+            0 => &self.init_arena,
+            _ => &self.more_arenas[test_idx - 1],
+        }
+    }
+
+    /// Play back prog on this level, e.g. a solution from save game history.
+    ///
+    /// Starts running straight away. Prog can't be edited, the outcome isn't stored,
+    /// and the scene concludes (always with Continue) when the player leaves the replay.
+    pub fn into_replay(mut self, prog: Subprog) -> Result<Self, String> {
+        self.coding.restore_prog(prog)?;
+        if let Some(violation) = self.coding.rule_violations().into_iter().next() {
+            return Err(violation);
+        }
+        self.replay = true;
+        self.start_execution();
+        Ok(self)
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.replay
+    }
+
     pub fn is_running(&self) -> bool {
         self.phase == CodingRunningPhase::Running
     }
@@ -100,10 +159,21 @@ impl<MovementLogic: for_gamedata::BaseMovementLogic> CodingArena<MovementLogic>
 
     fn start_execution(&mut self) {
         assert!(self.phase == CodingRunningPhase::Coding);
+        // UI shows player why.
+        if let Some(violation) = self.coding.rule_violations().first() {
+            log::info!("Not running prog which breaks level's rules: {violation}");
+            return;
+        }
         self.transition(CodingRunningPhase::Running);
+        self.test_results = vec![None; self.num_tests()];
+        self.start_test(0);
+    }
 
+    fn start_test(&mut self, test_idx: usize) {
+        log::debug!("Running prog on map {} of {}", test_idx + 1, self.num_tests());
+        self.curr_test = test_idx;
         // Init interactive arena
-        self.curr_arena = Some(self.init_arena.clone());
+        self.curr_arena = Some(self.test_arena(test_idx).clone());
         // Run game-specific logic to copy prog into bot.
         MovementLogic::harmonise(self);
     }
@@ -122,7 +192,7 @@ impl<MovementLogic: for_gamedata::BaseMovementLogic> CodingArena<MovementLogic>
 
     fn continue_to_next_level(&mut self) {
         assert!(self.phase == CodingRunningPhase::Won);
-        self.ready_for_next_level = Some(SceneConclusion::Succeed);
+        self.ready_for_next_level = Some(if self.replay {SceneConclusion::Continue} else {SceneConclusion::Succeed});
     }
 
     fn cancel_execution(&mut self) {
@@ -133,6 +203,11 @@ impl<MovementLogic: for_gamedata::BaseMovementLogic> CodingArena<MovementLogic>
 
     fn continue_coding(&mut self) {
         assert!(self.phase != CodingRunningPhase::Coding);
+        if self.replay {
+            // Nothing to edit. Leave replay instead.
+            self.ready_for_next_level = Some(SceneConclusion::Continue);
+            return;
+        }
         self.transition(CodingRunningPhase::Coding);
 
         // De-init interactive arena
@@ -158,6 +233,9 @@ impl<MovementLogic: for_gamedata::BaseMovementLogic> CodingArena<MovementLogic>
     }
 
     fn record_outcome(&mut self, outcome: &str) {
+        if self.replay {
+            return;
+        }
         log::debug!("Coding Arena: Recording outcome to store: {outcome}");
         assert!(self.outcome_to_store.is_none());
         self.outcome_to_store = Some(OutcomeToStore::new(outcome.to_string(), self.coding.prog.to_string()));
@@ -166,4 +244,13 @@ impl<MovementLogic: for_gamedata::BaseMovementLogic> CodingArena<MovementLogic>
     pub fn consume_outcome_to_store(&mut self) -> Option<OutcomeToStore> {
         self.outcome_to_store.take()
     }
+
+    pub fn request_stats(&mut self) {
+        assert!(self.phase == CodingRunningPhase::Coding);
+        self.stats_requested = true;
+    }
+
+    pub fn consume_stats_request(&mut self) -> bool {
+        std::mem::take(&mut self.stats_requested)
+    }
 }
@@ -0,0 +1,428 @@
+// Edits to the prog being coded, as commands which can be undone and redone.
+//
+// The UI drags instrs around freely while the mouse is down, then puts back
+// whatever it took out and makes the edit the drag amounts to, so every change
+// goes through Coding::edit and keeps the supply bin counts consistent.
+
+use super::coding::{Bin, Coding, Instr, Opcode, Subprog};
+
+/// Where an instr is in a prog: its index in the prog, or for an instr nested in
+/// parent instrs, the index of each parent in turn and then its index in the subprog.
+pub type InstrPath = Vec<usize>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditCmd {
+    /// Take an instr from a supply bin and put it in the prog.
+    Insert { bin_idx: usize, to: InstrPath },
+    /// Take an instr, and any instrs inside it, out of the prog and back to the supply.
+    Remove { from: InstrPath },
+    /// Take an instr, and any instrs inside it, out of the prog and put it somewhere
+    /// else. `to` is where it goes in the prog once it has been taken out.
+    Move { from: InstrPath, to: InstrPath },
+    /// Take a parent instr from a supply bin, and put `count` instrs starting at `first`
+    /// inside it, in their place.
+    Wrap { bin_idx: usize, first: InstrPath, count: usize },
+    /// Return every instr to the supply, and take instrs for a whole new prog from it.
+    Replace { prog: Subprog },
+    /// Return every instr to the supply, leaving the level's start prog.
+    Reset,
+}
+
+// What's changed by edits. Bins' ops and original counts never change.
+#[derive(Clone, Debug, PartialEq)]
+struct CodingSnapshot {
+    prog: Subprog,
+    supply_counts: Vec<u16>,
+}
+
+/// Coding as it was before each edit, for undoing, and edits undone, for redoing.
+#[derive(Clone, Debug, Default)]
+pub struct EditHistory {
+    undo: Vec<(EditCmd, CodingSnapshot)>,
+    redo: Vec<EditCmd>,
+}
+
+impl Subprog {
+    // Subprog which instrs at path are in, and how many instrs it has room for.
+    fn parent_of_mut(&mut self, path: &[usize]) -> Result<(&mut Subprog, usize), String> {
+        let Some((_, parent_idxs)) = path.split_last() else {
+            return Err("Empty instr path".to_string());
+        };
+        let mut subprog = self;
+        let mut max_len = usize::MAX;
+        for &idx in parent_idxs {
+            match subprog.instrs.get_mut(idx) {
+                Some(Instr::Parent(op, inner)) => {
+                    max_len = op.r_connect_max();
+                    subprog = inner;
+                },
+                _ => return Err(format!("No parent instr at {path:?}")),
+            }
+        }
+        Ok((subprog, max_len))
+    }
+
+    /// Put instr at path, moving any instrs at or after it along.
+    pub fn insert_at(&mut self, path: &[usize], instr: Instr) -> Result<(), String> {
+        let (subprog, max_len) = self.parent_of_mut(path)?;
+        let idx = *path.last().unwrap();
+        if idx > subprog.instrs.len() {
+            return Err(format!("Can't insert past end of subprog at {path:?}"));
+        }
+        if subprog.instrs.len() >= max_len {
+            return Err(format!("No room for another instr at {path:?}"));
+        }
+        subprog.instrs.insert(idx, instr);
+        Ok(())
+    }
+
+    /// Take out instr at path, including any instrs inside it.
+    pub fn remove_at(&mut self, path: &[usize]) -> Result<Instr, String> {
+        let (subprog, _) = self.parent_of_mut(path)?;
+        let idx = *path.last().unwrap();
+        if idx >= subprog.instrs.len() {
+            return Err(format!("No instr at {path:?}"));
+        }
+        Ok(subprog.instrs.remove(idx))
+    }
+
+    /// Put count instrs starting at path inside parent instr, in their place.
+    pub fn wrap_at(&mut self, path: &[usize], count: usize, parent: Instr) -> Result<(), String> {
+        let Instr::Parent(op, _) = parent else {
+            return Err(format!("Can't put instrs inside {parent}"));
+        };
+        if count == 0 || count > op.r_connect_max() {
+            return Err(format!("{op} can't hold {count} instrs"));
+        }
+        let (subprog, _) = self.parent_of_mut(path)?;
+        let idx = *path.last().unwrap();
+        if idx + count > subprog.instrs.len() {
+            return Err(format!("Not {count} instrs to wrap at {path:?}"));
+        }
+        let wrapped = subprog.instrs.drain(idx..idx + count).collect::<Vec<_>>();
+        subprog.instrs.insert(idx, Instr::Parent(op, Subprog::from(wrapped)));
+        Ok(())
+    }
+
+    /// Err if any parent instr has more instrs inside it than it can hold.
+    pub fn check_fits(&self) -> Result<(), String> {
+        for instr in &self.instrs {
+            if let Instr::Parent(op, subprog) = instr {
+                if subprog.instrs.len() > op.r_connect_max() {
+                    return Err(format!("{op} can only hold {} instrs", op.r_connect_max()));
+                }
+                subprog.check_fits()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Coding {
+    fn snapshot(&self) -> CodingSnapshot {
+        CodingSnapshot {
+            prog: self.prog.clone(),
+            supply_counts: self.supply.iter().map(|bin| bin.curr_count).collect(),
+        }
+    }
+
+    fn restore_snapshot(&mut self, snapshot: CodingSnapshot) {
+        self.prog = snapshot.prog;
+        for (bin, count) in self.supply.iter_mut().zip(snapshot.supply_counts) {
+            bin.curr_count = count;
+        }
+    }
+
+    /// Add instr, and any instrs inside it, back to their supply bins.
+    pub fn return_to_supply(&mut self, instr: Instr) {
+        for (op, count) in Subprog::from(vec![instr]).supply_counts() {
+            if let Some(bin) = self.bin_for_mut(op) {
+                bin.curr_count += count;
+            }
+        }
+    }
+
+    fn bin_for_mut(&mut self, op: Opcode) -> Option<&mut Bin> {
+        self.supply.iter_mut().find(|bin| bin.op == op)
+    }
+
+    // New instr from supply bin.
+    fn take_from_supply(&mut self, bin_idx: usize) -> Result<Instr, String> {
+        let bin = self.supply.get_mut(bin_idx).ok_or(format!("No supply bin {bin_idx}"))?;
+        if bin.curr_count == 0 {
+            return Err(format!("No {} left in supply", bin.op));
+        }
+        bin.curr_count -= 1;
+        Ok(Instr::from_opcode(bin.op))
+    }
+
+    // Instr taken out of prog, if it's allowed to be.
+    fn unlocked(instr: Instr) -> Result<Instr, String> {
+        if instr.contains_locked() {
+            return Err(format!("{instr} is locked in place"));
+        }
+        Ok(instr)
+    }
+
+    // May leave coding part-changed if Err.
+    fn apply(&mut self, cmd: &EditCmd) -> Result<(), String> {
+        match cmd {
+            EditCmd::Insert { bin_idx, to } => {
+                let instr = self.take_from_supply(*bin_idx)?;
+                self.prog.insert_at(to, instr)
+            },
+            EditCmd::Wrap { bin_idx, first, count } => {
+                let parent = self.take_from_supply(*bin_idx)?;
+                self.prog.wrap_at(first, *count, parent)
+            },
+            EditCmd::Remove { from } => {
+                let instr = Self::unlocked(self.prog.remove_at(from)?)?;
+                self.return_to_supply(instr);
+                Ok(())
+            },
+            EditCmd::Move { from, to } => {
+                let instr = Self::unlocked(self.prog.remove_at(from)?)?;
+                self.prog.insert_at(to, instr)
+            },
+            EditCmd::Replace { prog } => {
+                prog.check_fits()?;
+                self.restore_prog(prog.clone())
+            },
+            EditCmd::Reset => {
+                self.reset_prog();
+                Ok(())
+            },
+        }
+    }
+
+    /// Replace prog with one typed as text. Err with message for player if it doesn't
+    /// parse, doesn't fit together, or needs more instrs than the supply has.
+    pub fn edit_from_text(&mut self, txt: &str) -> Result<(), String> {
+        self.edit(EditCmd::Replace { prog: Subprog::try_from_text(txt)? })
+    }
+
+    /// Make edit, so that it can be undone. Err, leaving coding as it was, if edit isn't possible.
+    ///
+    /// Edits which don't change anything aren't recorded.
+    pub fn edit(&mut self, cmd: EditCmd) -> Result<(), String> {
+        let before = self.snapshot();
+        let result = self.apply(&cmd).and_then(|()| {
+            // E.g. wrapping a locked instr in a parent instr.
+            match self.prog.locked_outline() == self.start_prog.locked_outline() {
+                true => Ok(()),
+                false => Err("Locked instrs can't be moved".to_string()),
+            }
+        });
+        if let Err(msg) = result {
+            self.restore_snapshot(before);
+            return Err(msg);
+        }
+        if self.snapshot() != before {
+            log::debug!("Edited prog: {cmd:?}");
+            self.history.undo.push((cmd, before));
+            self.history.redo.clear();
+        }
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    /// Put prog and supply back as they were before last edit. False if nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some((cmd, before)) = self.history.undo.pop() else {
+            return false;
+        };
+        self.restore_snapshot(before);
+        self.history.redo.push(cmd);
+        true
+    }
+
+    /// Make last undone edit again. False if nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(cmd) = self.history.redo.pop() else {
+            return false;
+        };
+        let before = self.snapshot();
+        // Coding is as it was when edit was first made, so it still applies.
+        self.apply(&cmd).expect("Redone edit applies as it did originally");
+        self.history.undo.push((cmd, before));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::coding::{prog_fn_ops, supply_ops};
+
+    fn coding() -> Coding {
+        Coding::from_vec(&[(supply_ops::F, 3), (supply_ops::R, 2), (supply_ops::LOOP, 1), (supply_ops::x2, 1)])
+    }
+
+    fn counts(coding: &Coding) -> Vec<u16> {
+        coding.supply.iter().map(|bin| bin.curr_count).collect()
+    }
+
+    #[test]
+    fn insert_remove_and_move() {
+        use prog_fn_ops::*;
+        let mut coding = coding();
+        coding.edit(EditCmd::Insert { bin_idx: 0, to: vec![0] }).unwrap();
+        coding.edit(EditCmd::Insert { bin_idx: 2, to: vec![1] }).unwrap();
+        coding.edit(EditCmd::Insert { bin_idx: 0, to: vec![1, 0] }).unwrap();
+        coding.edit(EditCmd::Insert { bin_idx: 1, to: vec![1, 1] }).unwrap();
+        assert_eq!(coding.prog, Subprog::from(vec![F, LOOP(&[F, R])]));
+        assert_eq!(counts(&coding), [1, 1, 0, 1]);
+
+        // Move R out of the loop to the start.
+        coding.edit(EditCmd::Move { from: vec![1, 1], to: vec![0] }).unwrap();
+        assert_eq!(coding.prog, Subprog::from(vec![R, F, LOOP(&[F])]));
+        assert_eq!(counts(&coding), [1, 1, 0, 1]);
+
+        // Removing the loop returns what's inside it too.
+        coding.edit(EditCmd::Remove { from: vec![2] }).unwrap();
+        assert_eq!(coding.prog, Subprog::from(vec![R, F]));
+        assert_eq!(counts(&coding), [2, 1, 1, 1]);
+
+        coding.edit(EditCmd::Reset).unwrap();
+        assert_eq!(coding.prog, Subprog::default());
+        assert_eq!(counts(&coding), [3, 2, 1, 1]);
+    }
+
+    #[test]
+    fn replace_from_text() {
+        use prog_fn_ops::*;
+        let mut coding = coding();
+        coding.edit(EditCmd::Insert { bin_idx: 1, to: vec![0] }).unwrap();
+
+        coding.edit_from_text("LOOP[F, x2[R]], F").unwrap();
+        assert_eq!(coding.prog, Subprog::from(vec![LOOP(&[F, x2(&[R])]), F]));
+        assert_eq!(counts(&coding), [1, 1, 0, 0]);
+        assert_eq!(coding.prog.as_instrs_text(), "LOOP[F,x2[R]],F");
+
+        // Bad text, too many instrs, or more than a parent holds, leave prog as it was.
+        assert!(coding.edit_from_text("LOOP[F").is_err());
+        assert!(coding.edit_from_text("Jump").is_err());
+        assert!(coding.edit_from_text("F,F,F,F").is_err());
+        assert!(coding.edit_from_text("x2[F,R]").is_err());
+        assert_eq!(coding.prog, Subprog::from(vec![LOOP(&[F, x2(&[R])]), F]));
+        assert_eq!(counts(&coding), [1, 1, 0, 0]);
+
+        coding.undo();
+        assert_eq!(coding.prog, Subprog::from(vec![R]));
+        assert_eq!(counts(&coding), [3, 1, 1, 1]);
+    }
+
+    #[test]
+    fn wrap() {
+        use prog_fn_ops::*;
+        let mut coding = coding();
+        coding.restore_prog(Subprog::from(vec![R, F, F, R])).unwrap();
+
+        // x2 only holds one instr.
+        assert!(coding.edit(EditCmd::Wrap { bin_idx: 3, first: vec![1], count: 2 }).is_err());
+        // Can't wrap in an action.
+        assert!(coding.edit(EditCmd::Wrap { bin_idx: 0, first: vec![1], count: 1 }).is_err());
+        assert_eq!(counts(&coding), [1, 0, 1, 1]);
+
+        coding.edit(EditCmd::Wrap { bin_idx: 2, first: vec![1], count: 2 }).unwrap();
+        assert_eq!(coding.prog, Subprog::from(vec![R, LOOP(&[F, F]), R]));
+        coding.edit(EditCmd::Wrap { bin_idx: 3, first: vec![1, 1], count: 1 }).unwrap();
+        assert_eq!(coding.prog, Subprog::from(vec![R, LOOP(&[F, x2(&[F])]), R]));
+        assert_eq!(counts(&coding), [1, 0, 0, 0]);
+
+        coding.undo();
+        coding.undo();
+        assert_eq!(coding.prog, Subprog::from(vec![R, F, F, R]));
+        assert_eq!(counts(&coding), [1, 0, 1, 1]);
+    }
+
+    #[test]
+    fn impossible_edits_change_nothing() {
+        use prog_fn_ops::*;
+        let mut coding = coding();
+        coding.restore_prog(Subprog::from(vec![F, x2(&[R])])).unwrap();
+        let before = coding.snapshot();
+
+        // x2 only holds one instr.
+        assert!(coding.edit(EditCmd::Insert { bin_idx: 0, to: vec![1, 0] }).is_err());
+        assert!(coding.edit(EditCmd::Move { from: vec![0], to: vec![0, 1] }).is_err());
+        // No such places.
+        assert!(coding.edit(EditCmd::Insert { bin_idx: 0, to: vec![5] }).is_err());
+        assert!(coding.edit(EditCmd::Insert { bin_idx: 9, to: vec![0] }).is_err());
+        assert!(coding.edit(EditCmd::Remove { from: vec![0, 0] }).is_err());
+        // Supply bin empty.
+        assert!(coding.edit(EditCmd::Insert { bin_idx: 3, to: vec![0] }).is_err());
+
+        assert_eq!(coding.snapshot(), before);
+        assert!(!coding.can_undo());
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        use prog_fn_ops::*;
+        let mut coding = coding();
+        assert!(!coding.undo());
+
+        coding.edit(EditCmd::Insert { bin_idx: 2, to: vec![0] }).unwrap();
+        coding.edit(EditCmd::Insert { bin_idx: 0, to: vec![0, 0] }).unwrap();
+        coding.edit(EditCmd::Insert { bin_idx: 1, to: vec![0, 1] }).unwrap();
+        let built = coding.snapshot();
+        // Moving to same place changes nothing, so isn't recorded.
+        coding.edit(EditCmd::Move { from: vec![0], to: vec![0] }).unwrap();
+
+        coding.edit(EditCmd::Reset).unwrap();
+        assert!(coding.undo());
+        assert_eq!(coding.snapshot(), built);
+        assert!(coding.undo());
+        assert_eq!(coding.prog, Subprog::from(vec![LOOP(&[F])]));
+        assert_eq!(counts(&coding), [2, 2, 0, 1]);
+
+        assert!(coding.redo());
+        assert!(coding.redo());
+        assert_eq!(coding.prog, Subprog::default());
+        assert!(!coding.redo());
+
+        // Undo all the way back, and a new edit forgets what was undone.
+        while coding.undo() {}
+        assert_eq!(coding.prog, Subprog::default());
+        assert_eq!(counts(&coding), [3, 2, 1, 1]);
+        coding.edit(EditCmd::Insert { bin_idx: 1, to: vec![0] }).unwrap();
+        assert!(!coding.can_redo());
+        assert_eq!(coding.prog, Subprog::from(vec![R]));
+    }
+
+    #[test]
+    fn locked_instrs() {
+        use prog_fn_ops::*;
+        // Fill in the blank in a locked loop, after a broken instr which can be changed.
+        let start = Subprog::from_text("R, LOOP#[F#]");
+        let mut coding = coding().with_start_prog(start.clone());
+        assert_eq!(counts(&coding), [3, 1, 1, 1]);
+
+        coding.edit(EditCmd::Insert { bin_idx: 1, to: vec![1, 1] }).unwrap();
+        coding.edit(EditCmd::Move { from: vec![0], to: vec![0, 0] }).unwrap();
+        assert_eq!(coding.prog.to_string(), "[LOOP#[R,F#,R]]");
+        assert_eq!(counts(&coding), [3, 0, 1, 1]);
+
+        // Locked instrs, and instrs around them, stay where they are.
+        assert!(coding.edit(EditCmd::Remove { from: vec![0] }).is_err());
+        assert!(coding.edit(EditCmd::Move { from: vec![0, 1], to: vec![0, 0] }).is_err());
+        assert!(coding.edit(EditCmd::Wrap { bin_idx: 3, first: vec![0, 1], count: 1 }).is_err());
+        assert!(coding.edit_from_text("LOOP[R, F#, R]").is_err());
+        assert!(coding.edit_from_text("F#, LOOP#[F#]").is_err());
+        coding.edit(EditCmd::Wrap { bin_idx: 3, first: vec![0, 2], count: 1 }).unwrap();
+        assert_eq!(coding.prog, Subprog::from(vec![LOOP(&[R, F.locked(), x2(&[R])]).locked()]));
+
+        // Reset goes back to level's start.
+        coding.edit(EditCmd::Reset).unwrap();
+        assert_eq!(coding.prog, start);
+        assert_eq!(counts(&coding), [3, 1, 1, 1]);
+    }
+}
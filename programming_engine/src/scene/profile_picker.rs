@@ -0,0 +1,48 @@
+use super::*;
+
+/// Change being made to the profile list, while waiting for the player to type or confirm it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProfileEdit {
+    New { name: String },
+    Rename { old_name: String, new_name: String },
+    ConfirmDelete { name: String },
+}
+
+/// Choose which player's progress to use, before the first level.
+///
+/// The profiles themselves are kept by the save game. The ui creates, renames
+/// and deletes them through BaseSaveGame, and this keeps track of the choice
+/// and any edit in progress.
+#[derive(Clone, Debug)]
+pub struct ProfilePicker {
+    /// Index of highlighted profile in save game's list of profiles.
+    pub selected_idx: usize,
+    pub edit: Option<ProfileEdit>,
+    /// Feedback for player, e.g. why a name wasn't accepted.
+    pub message: Option<String>,
+    ready_for_next_level: Option<SceneConclusion>,
+}
+
+impl ProfilePicker {
+    pub fn new(selected_idx: usize) -> Self {
+        Self {
+            selected_idx,
+            edit: None,
+            message: None,
+            ready_for_next_level: None,
+        }
+    }
+}
+
+impl BaseScene for ProfilePicker {
+    // Continue once the chosen profile has been selected in the save game.
+    fn advance(&mut self, cmd: InputCmd) {
+        if cmd == InputCmd::Continue {
+            self.ready_for_next_level = Some(SceneConclusion::Continue);
+        }
+    }
+
+    fn ready_for_next_level(&self) -> Option<SceneConclusion> {
+        self.ready_for_next_level
+    }
+}
@@ -0,0 +1,251 @@
+// Where the player is editing the prog by keyboard.
+//
+// Moves through the instr tree, and works out which edits keys make. The UI
+// makes the edits with Coding::edit and moves the cursor after them.
+
+use std::ops::Range;
+
+use super::coding::{Coding, Instr, Subprog};
+use super::coding_edit::{EditCmd, InstrPath};
+
+/// Position before an instr, or at the end of a subprog, with an optional selection
+/// of instrs from there in the same subprog.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgCursor {
+    pub path: InstrPath,
+    // Index in same subprog of the other end of the selection, if any.
+    pub anchor: Option<usize>,
+}
+
+impl ProgCursor {
+    /// At end of prog, for adding instrs.
+    pub fn at_end(prog: &Subprog) -> Self {
+        Self { path: vec![prog.instrs.len()], anchor: None }
+    }
+
+    fn idx(&self) -> usize {
+        *self.path.last().unwrap()
+    }
+
+    fn set_idx(&mut self, idx: usize) {
+        *self.path.last_mut().unwrap() = idx;
+    }
+
+    // Subprog cursor is in, if path is still valid.
+    fn subprog<'a>(&self, prog: &'a Subprog) -> Option<&'a Subprog> {
+        let mut subprog = prog;
+        for &idx in &self.path[..self.path.len() - 1] {
+            match subprog.instrs.get(idx) {
+                Some(Instr::Parent(_, inner)) => subprog = inner,
+                _ => return None,
+            }
+        }
+        Some(subprog)
+    }
+
+    fn subprog_len(&self, prog: &Subprog) -> usize {
+        self.subprog(prog).map_or(0, |subprog| subprog.instrs.len())
+    }
+
+    /// Instr at cursor, if not at end of a subprog.
+    pub fn instr<'a>(&self, prog: &'a Subprog) -> Option<&'a Instr> {
+        self.subprog(prog)?.instrs.get(self.idx())
+    }
+
+    /// Move to nearest valid position, e.g. after prog was edited some other way.
+    pub fn clamp_to(&mut self, prog: &Subprog) {
+        while self.path.len() > 1 && self.subprog(prog).is_none() {
+            self.path.pop();
+            self.anchor = None;
+        }
+        let len = self.subprog_len(prog);
+        self.set_idx(self.idx().min(len));
+        self.anchor = self.anchor.filter(|anchor| *anchor < len);
+    }
+
+    /// Instrs selected, as range of indices in cursor's subprog. Includes instr at each end.
+    pub fn selection(&self, prog: &Subprog) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        let last = self.subprog_len(prog).checked_sub(1)?;
+        let (start, end) = (anchor.min(self.idx()), anchor.max(self.idx()).min(last));
+        Some(start..end + 1)
+    }
+
+    /// Up or down within subprog. Extending selection if `select`, else clearing it.
+    pub fn step(&mut self, prog: &Subprog, down: bool, select: bool) {
+        if select && self.anchor.is_none() {
+            self.anchor = Some(self.idx());
+        } else if !select {
+            self.anchor = None;
+        }
+        let idx = if down {(self.idx() + 1).min(self.subprog_len(prog))} else {self.idx().saturating_sub(1)};
+        self.set_idx(idx);
+    }
+
+    /// Into first instr inside parent instr at cursor. False if not on a parent instr.
+    pub fn step_in(&mut self, prog: &Subprog) -> bool {
+        if !matches!(self.instr(prog), Some(Instr::Parent(..))) {
+            return false;
+        }
+        self.path.push(0);
+        self.anchor = None;
+        true
+    }
+
+    /// Out to parent instr of cursor's subprog. False if at top level.
+    pub fn step_out(&mut self) -> bool {
+        if self.path.len() <= 1 {
+            return false;
+        }
+        self.path.pop();
+        self.anchor = None;
+        true
+    }
+
+    /// Edit to make for choosing supply bin: put its instr around selection if there is one,
+    /// else put it at cursor.
+    pub fn edit_for_bin(&self, coding: &Coding, bin_idx: usize) -> EditCmd {
+        match self.selection(&coding.prog) {
+            Some(range) => EditCmd::Wrap { bin_idx, first: [&self.path[..self.path.len() - 1], &[range.start]].concat(), count: range.len() },
+            None => EditCmd::Insert { bin_idx, to: self.path.clone() },
+        }
+    }
+
+    /// Edit to make for deleting: remove instr at cursor, if any.
+    pub fn edit_for_delete(&self, prog: &Subprog) -> Option<EditCmd> {
+        self.instr(prog).map(|_| EditCmd::Remove { from: self.path.clone() })
+    }
+
+    /// Move after edit made by edit_for_bin. After an inserted instr, or inside it if it's a
+    /// parent so its instrs can be added next. Onto a wrapped selection.
+    pub fn after_edit(&mut self, prog: &Subprog, cmd: &EditCmd) {
+        match cmd {
+            EditCmd::Insert { .. } if matches!(self.instr(prog), Some(Instr::Parent(..))) => {
+                self.path.push(0);
+            },
+            EditCmd::Insert { .. } => self.set_idx(self.idx() + 1),
+            EditCmd::Wrap { first, .. } => {
+                self.path = first.clone();
+                self.anchor = None;
+            },
+            _ => (),
+        }
+        self.clamp_to(prog);
+    }
+
+    /// Column and row in prog as drawn, as used for coords of instrs by UI. Each instr is
+    /// below previous instrs in its subprog, including all their nested instrs.
+    pub fn grid_pos(prog: &Subprog, path: &[usize]) -> (usize, usize) {
+        let mut row = 0;
+        let mut subprog = Some(prog);
+        for &idx in path {
+            let Some(curr) = subprog else {
+                break;
+            };
+            row += curr.instrs.iter().take(idx).map(|instr| instr.v_len()).sum::<usize>();
+            subprog = match curr.instrs.get(idx) {
+                Some(Instr::Parent(_, inner)) => Some(inner),
+                _ => None,
+            };
+        }
+        (path.len() - 1, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::coding::{prog_fn_ops::*, supply_ops};
+
+    fn coding() -> Coding {
+        let mut coding = Coding::from_vec(&[(supply_ops::F, 4), (supply_ops::R, 2), (supply_ops::LOOP, 1), (supply_ops::x2, 1)]);
+        coding.restore_prog(Subprog::from(vec![F, LOOP(&[R, F]), R])).unwrap();
+        coding
+    }
+
+    #[test]
+    fn move_through_tree() {
+        let prog = coding().prog;
+        let mut cursor = ProgCursor::at_end(&prog);
+        assert_eq!(cursor.path, [3]);
+        cursor.step(&prog, true, false);
+        assert_eq!(cursor.path, [3]);
+
+        cursor.step(&prog, false, false);
+        cursor.step(&prog, false, false);
+        assert_eq!(cursor.instr(&prog), Some(&LOOP(&[R, F])));
+        assert!(cursor.step_in(&prog));
+        assert_eq!(cursor.path, [1, 0]);
+        // Action instrs have nothing inside.
+        assert!(!cursor.step_in(&prog));
+        cursor.step(&prog, true, false);
+        cursor.step(&prog, true, false);
+        assert_eq!(cursor.path, [1, 2]);
+        assert_eq!(ProgCursor::grid_pos(&prog, &cursor.path), (1, 3));
+        assert!(cursor.step_out());
+        assert_eq!(cursor.path, [1]);
+        assert!(!cursor.step_out());
+
+        // Last R is below loop and the instrs in it.
+        assert_eq!(ProgCursor::grid_pos(&prog, &[2]), (0, 3));
+
+        // Stays valid when prog changes under it.
+        cursor.path = vec![1, 2];
+        cursor.clamp_to(&Subprog::from(vec![F, LOOP(&[R])]));
+        assert_eq!(cursor.path, [1, 1]);
+        cursor.clamp_to(&Subprog::from(vec![F]));
+        assert_eq!(cursor.path, [1]);
+    }
+
+    #[test]
+    fn edits_at_cursor() {
+        let mut coding = coding();
+        let mut cursor = ProgCursor { path: vec![1], anchor: None };
+
+        // Inserting a parent moves into it, ready to add its instrs.
+        let cmd = cursor.edit_for_bin(&coding, 3);
+        assert_eq!(cmd, EditCmd::Insert { bin_idx: 3, to: vec![1] });
+        coding.edit(cmd.clone()).unwrap();
+        cursor.after_edit(&coding.prog, &cmd);
+        assert_eq!(cursor.path, [1, 0]);
+        let cmd = cursor.edit_for_bin(&coding, 0);
+        coding.edit(cmd.clone()).unwrap();
+        cursor.after_edit(&coding.prog, &cmd);
+        assert_eq!(coding.prog, Subprog::from(vec![F, x2(&[F]), LOOP(&[R, F]), R]));
+        assert_eq!(cursor.path, [1, 1]);
+
+        let mut cursor = ProgCursor { path: vec![0], anchor: None };
+        coding.edit(cursor.edit_for_delete(&coding.prog).unwrap()).unwrap();
+        assert_eq!(coding.prog, Subprog::from(vec![x2(&[F]), LOOP(&[R, F]), R]));
+        cursor.path = vec![3];
+        assert_eq!(cursor.edit_for_delete(&coding.prog), None);
+    }
+
+    #[test]
+    fn wrap_selection() {
+        let mut coding = coding();
+        coding.edit(EditCmd::Remove { from: vec![1] }).unwrap();
+        coding.edit(EditCmd::Insert { bin_idx: 0, to: vec![1] }).unwrap();
+        assert_eq!(coding.prog, Subprog::from(vec![F, F, R]));
+
+        // Select from last instr up to first.
+        let mut cursor = ProgCursor { path: vec![2], anchor: None };
+        cursor.step(&coding.prog, false, true);
+        cursor.step(&coding.prog, false, true);
+        assert_eq!(cursor.selection(&coding.prog), Some(0..3));
+        cursor.step(&coding.prog, true, true);
+        assert_eq!(cursor.selection(&coding.prog), Some(1..3));
+
+        let cmd = cursor.edit_for_bin(&coding, 2);
+        assert_eq!(cmd, EditCmd::Wrap { bin_idx: 2, first: vec![1], count: 2 });
+        coding.edit(cmd.clone()).unwrap();
+        cursor.after_edit(&coding.prog, &cmd);
+        assert_eq!(coding.prog, Subprog::from(vec![F, LOOP(&[F, R])]));
+        assert_eq!(cursor, ProgCursor { path: vec![1], anchor: None });
+
+        // Moving without selecting clears selection.
+        cursor.step(&coding.prog, true, true);
+        cursor.step(&coding.prog, true, false);
+        assert_eq!(cursor.selection(&coding.prog), None);
+    }
+}
@@ -0,0 +1,107 @@
+// Limits on a prog's shape which a level can declare, beyond what its supply bins allow.
+//
+// Progs which break a rule can still be edited, so the UI shows what's wrong as
+// the player goes, but can't be run.
+
+use super::coding::{Coding, Instr, Opcode, ParentOpcode, Subprog};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProgRule {
+    /// At most this many instrs in total, including nested ones.
+    MaxInstrs(usize),
+    /// Instrs nested at most this deep, as drawn. 1 allows no parent instrs.
+    MaxDepth(usize),
+    /// Op mustn't be directly inside parent op, e.g. no LOOP in a LOOP.
+    NotInside { op: Opcode, parent: ParentOpcode },
+    /// Op must be used somewhere in prog.
+    MustUse(Opcode),
+}
+
+impl ProgRule {
+    /// Why prog breaks rule, for player, if it does.
+    pub fn violation(&self, prog: &Subprog) -> Option<String> {
+        match self {
+            Self::MaxInstrs(max) => {
+                let count = num_instrs(prog);
+                (count > *max).then(|| format!("Use at most {max} instrs, not {count}."))
+            },
+            Self::MaxDepth(max) => (prog.h_len() > *max).then(|| format!("Nest instrs at most {max} deep.")),
+            Self::NotInside { op, parent } => has_inside(prog, *op, *parent).then(|| format!("Don't put {} inside {parent}.", op.as_text())),
+            Self::MustUse(op) => (!uses(prog, *op)).then(|| format!("Use {} somewhere.", op.as_text())),
+        }
+    }
+}
+
+fn num_instrs(prog: &Subprog) -> usize {
+    prog.instrs.iter().map(|instr| match instr {
+        Instr::Parent(_, subprog) => 1 + num_instrs(subprog),
+        Instr::Action(..) => 1,
+    }).sum()
+}
+
+fn uses(prog: &Subprog, op: Opcode) -> bool {
+    prog.instrs.iter().any(|instr| instr.has_opcode(op) || matches!(instr, Instr::Parent(_, subprog) if uses(subprog, op)))
+}
+
+// Whether op is directly inside a parent instr with parent op, anywhere in prog.
+fn has_inside(prog: &Subprog, op: Opcode, parent: ParentOpcode) -> bool {
+    prog.instrs.iter().any(|instr| match instr {
+        Instr::Parent(parent_op, subprog) =>
+            *parent_op == parent && subprog.instrs.iter().any(|inner| inner.has_opcode(op)) || has_inside(subprog, op, parent),
+        Instr::Action(..) => false,
+    })
+}
+
+impl Coding {
+    /// Also limit prog by rule.
+    pub fn with_rule(mut self, rule: ProgRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Why prog can't be run, for each rule it breaks.
+    pub fn rule_violations(&self) -> Vec<String> {
+        self.rules.iter().filter_map(|rule| rule.violation(&self.prog)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::coding::supply_ops;
+
+    fn violations(rule: ProgRule, prog: &str) -> Option<String> {
+        rule.violation(&Subprog::from_text(prog))
+    }
+
+    #[test]
+    fn rules() {
+        use ProgRule::*;
+        assert_eq!(violations(MaxInstrs(3), "F, LOOP[R]"), None);
+        assert_eq!(violations(MaxInstrs(3), "F, LOOP[R, x2[F]]"), Some("Use at most 3 instrs, not 5.".to_string()));
+
+        assert_eq!(violations(MaxDepth(1), "F, R"), None);
+        assert_eq!(violations(MaxDepth(2), "F, LOOP[R]"), None);
+        assert!(violations(MaxDepth(2), "LOOP[x2[F]]").is_some());
+
+        let no_loop_in_loop = NotInside { op: supply_ops::LOOP, parent: ParentOpcode::LOOP };
+        assert_eq!(violations(no_loop_in_loop.clone(), "LOOP[F], LOOP[x2[LOOP[R]]]"), None);
+        assert_eq!(violations(no_loop_in_loop, "x2[LOOP[F, LOOP[R]]]"), Some("Don't put LOOP inside LOOP.".to_string()));
+
+        assert_eq!(violations(MustUse(supply_ops::Else), "LOOP[F, Else[R]]"), None);
+        assert_eq!(violations(MustUse(supply_ops::Else), "LOOP[F, R]"), Some("Use Else somewhere.".to_string()));
+    }
+
+    #[test]
+    fn coding_with_rules() {
+        use supply_ops::*;
+        let mut coding = Coding::from_vec(&[(F, 4), (LOOP, 1)])
+            .with_rule(ProgRule::MaxInstrs(2))
+            .with_rule(ProgRule::MustUse(LOOP));
+        assert_eq!(coding.rule_violations(), ["Use LOOP somewhere."]);
+        coding.edit_from_text("F, F, F").unwrap();
+        assert_eq!(coding.rule_violations(), ["Use at most 2 instrs, not 3.", "Use LOOP somewhere."]);
+        coding.edit_from_text("LOOP[F]").unwrap();
+        assert!(coding.rule_violations().is_empty());
+    }
+}
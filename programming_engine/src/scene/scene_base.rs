@@ -1,8 +1,15 @@
 use std::ops::ControlFlow;
 
 pub use super::arena::Arena;
+pub use super::arena_event::*;
 pub use super::coding::*;
+pub use super::coding_edit::*;
+pub use super::prog_cursor::*;
+pub use super::prog_rules::*;
 pub use super::splash::*;
+pub use super::profile_picker::*;
+pub use super::stats::*;
+pub use super::win_condition::*;
 pub use super::coding_arena::*;
 pub use super::super::ui::InputCmd;
 pub use crate::for_gamedata::OutcomeToStore;
@@ -17,6 +24,17 @@ pub enum SceneConclusion {
     Fail,
 }
 
+// Same questions as pushing_engine's SceneConclusion answers, so LevelSet is the same in both engines.
+impl SceneConclusion {
+    pub fn solved(&self) -> bool {
+        *self == SceneConclusion::Succeed
+    }
+
+    pub fn failed(&self) -> bool {
+        *self == SceneConclusion::Fail
+    }
+}
+
 // After each tick, either Continue, or restart/start another level based on Conclusion.
 // TODO: Move into Arena, only used there.
 pub type SceneContinuation = ControlFlow<SceneConclusion, ()>;
@@ -35,6 +53,8 @@ pub trait BaseScene {
 #[derive(Clone, Debug)]
 pub enum Scene<MovementLogic: for_gamedata::BaseMovementLogic> {
     Splash(Splash),
+    ProfilePicker(ProfilePicker),
+    Stats(Stats),
     CodingArena(CodingArena<MovementLogic>),
     // Could be defined but not used separately:
     // Arena(Arena<MovementLogic>),
@@ -55,6 +75,8 @@ impl<MovementLogic: for_gamedata::BaseMovementLogic> Scene<MovementLogic> {
         // NB: Use the crate that makes it easy to inherit behaviour between enum variants.
         match self {
             Self::Splash(scene) => scene.advance(cmd),
+            Self::ProfilePicker(scene) => scene.advance(cmd),
+            Self::Stats(scene) => scene.advance(cmd),
             Self::CodingArena(scene) => scene.advance(cmd),
         }
     }
@@ -62,20 +84,60 @@ impl<MovementLogic: for_gamedata::BaseMovementLogic> Scene<MovementLogic> {
     pub fn ready_for_next_level(&self) -> Option<SceneConclusion> {
         match self {
             Self::Splash(scene) => scene.ready_for_next_level(),
+            Self::ProfilePicker(scene) => scene.ready_for_next_level(),
+            Self::Stats(scene) => scene.ready_for_next_level(),
             Self::CodingArena(scene) => scene.ready_for_next_level(),
         }
     }
 
     pub fn consume_outcome_to_store(&mut self) -> Option<OutcomeToStore> {
         match self {
-            Self::Splash(_splash) => None,
+            Self::Splash(_) | Self::ProfilePicker(_) | Self::Stats(_) => None,
             Self::CodingArena(scene) => scene.consume_outcome_to_store(),
         }
     }
 
+    // Whether player asked to see stats, since last asked.
+    pub fn consume_stats_request(&mut self) -> bool {
+        match self {
+            Self::CodingArena(scene) => scene.consume_stats_request(),
+            _ => false,
+        }
+    }
+
+    // Program player asked to replay, and its level, since last asked.
+    pub fn consume_replay_request(&mut self) -> Option<(u16, String)> {
+        match self {
+            Self::Stats(scene) => scene.consume_replay_request(),
+            _ => None,
+        }
+    }
+
+    // Whether scene is replaying a stored solution.
+    pub fn is_replay(&self) -> bool {
+        matches!(self, Self::CodingArena(scene) if scene.is_replay())
+    }
+
+    // Coding being edited, in scenes which have one. Not replays, which can't be edited.
+    pub fn coding(&self) -> Option<&Coding> {
+        match self {
+            Self::Splash(_) | Self::ProfilePicker(_) | Self::Stats(_) => None,
+            Self::CodingArena(scene) if scene.is_replay() => None,
+            Self::CodingArena(scene) => Some(&scene.coding),
+        }
+    }
+
+    pub fn coding_mut(&mut self) -> Option<&mut Coding> {
+        match self {
+            Self::Splash(_) | Self::ProfilePicker(_) | Self::Stats(_) => None,
+            Self::CodingArena(scene) if scene.is_replay() => None,
+            Self::CodingArena(scene) => Some(&mut scene.coding),
+        }
+    }
+
     pub fn as_arena(&self) -> &Arena<MovementLogic> {
         match self {
-            Self::Splash(_splash) => panic!(),
+            Self::Splash(_) | Self::ProfilePicker(_) | Self::Stats(_) => panic!(),
             Self::CodingArena(scene) => &scene.init_arena,
         }
     }
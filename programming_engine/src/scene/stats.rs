@@ -0,0 +1,61 @@
+use super::*;
+
+/// Stats and history of attempts at a level, from the save game.
+///
+/// Shown in place of a level, then either returns to it, or goes to a level
+/// with a program chosen from its history. Programs from the history can also
+/// be replayed, returning here afterwards.
+#[derive(Clone, Debug)]
+pub struct Stats {
+    /// Level being shown. Can change to show other levels.
+    pub lev_idx: u16,
+    /// Index of highlighted attempt in level's outcome history. Past the end
+    /// means the latest attempt.
+    pub selected_idx: usize,
+    /// Program to load into editor on leaving, if one was chosen.
+    pub chosen_prog: Option<String>,
+    /// Feedback for player, e.g. why a program couldn't be replayed.
+    pub message: Option<String>,
+    replay_prog: Option<String>,
+    ready_for_next_level: Option<SceneConclusion>,
+}
+
+impl Stats {
+    pub fn new(lev_idx: u16) -> Self {
+        Self {
+            lev_idx,
+            selected_idx: usize::MAX,
+            chosen_prog: None,
+            message: None,
+            replay_prog: None,
+            ready_for_next_level: None,
+        }
+    }
+
+    pub fn choose_prog(&mut self, prog: String) {
+        self.chosen_prog = Some(prog);
+        self.advance(InputCmd::Continue);
+    }
+
+    // Ask engine to replay prog on the level being shown, then come back here.
+    pub fn request_replay(&mut self, prog: String) {
+        self.message = None;
+        self.replay_prog = Some(prog);
+    }
+
+    pub fn consume_replay_request(&mut self) -> Option<(u16, String)> {
+        self.replay_prog.take().map(|prog| (self.lev_idx, prog))
+    }
+}
+
+impl BaseScene for Stats {
+    fn advance(&mut self, cmd: InputCmd) {
+        if matches!(cmd, InputCmd::Continue | InputCmd::Cancel) {
+            self.ready_for_next_level = Some(SceneConclusion::Continue);
+        }
+    }
+
+    fn ready_for_next_level(&self) -> Option<SceneConclusion> {
+        self.ready_for_next_level
+    }
+}
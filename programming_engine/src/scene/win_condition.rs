@@ -0,0 +1,91 @@
+// What the hero must achieve for an Arena to conclude successfully.
+//
+// Arena checks its WinCondition after each hero move, so games don't need to
+// detect winning in their MovementLogic. Conditions compose, e.g.
+// All(vec![CollectAll, ReachGoal]) to collect every gem and then reach the exit.
+
+use crate::map_coords::{Facing, MapCoord};
+use crate::simple_custom_props::Effect;
+use crate::for_gamedata::BaseMovementLogic;
+use super::arena::Arena;
+
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum WinCondition {
+    /// Hero is on a square with Effect::Win.
+    #[default]
+    ReachGoal,
+    /// No objs with Effect::Collect are left in the map.
+    CollectAll,
+    /// Hero has been to each of these squares, in this order, since the start.
+    VisitInOrder(Vec<MapCoord>),
+    /// Exactly these squares have a mark, e.g. a pattern the hero must paint.
+    MarkedExactly(Vec<MapCoord>),
+    /// Hero is facing this way.
+    Facing(Facing),
+    /// Every condition holds at once.
+    All(Vec<WinCondition>),
+    /// At least one condition holds.
+    Any(Vec<WinCondition>),
+}
+
+impl WinCondition {
+    pub fn is_met<MovementLogic: BaseMovementLogic>(&self, arena: &Arena<MovementLogic>) -> bool {
+        match self {
+            Self::ReachGoal => arena.any_has_effect(arena[arena.hero()].pos(), Effect::Win),
+            Self::CollectAll => arena.num_collectibles() == 0,
+            Self::VisitInOrder(waypoints) => {
+                let mut trail = arena.hero_trail().iter();
+                waypoints.iter().all(|waypoint| trail.any(|pos| pos == waypoint))
+            },
+            Self::MarkedExactly(targets) => {
+                let marked = arena.marked_squares();
+                marked.len() == targets.len() && targets.iter().all(|pos| marked.contains(pos))
+            },
+            Self::Facing(dir) => arena[arena.hero()].logical_props.dir == *dir,
+            Self::All(conds) => conds.iter().all(|cond| cond.is_met(arena)),
+            Self::Any(conds) => conds.iter().any(|cond| cond.is_met(arena)),
+        }
+    }
+
+    /// Squares to visit, in order, for UI to mark.
+    pub fn waypoints(&self) -> Vec<MapCoord> {
+        match self {
+            Self::VisitInOrder(waypoints) => waypoints.clone(),
+            Self::All(conds) | Self::Any(conds) => conds.iter().flat_map(|cond| cond.waypoints()).collect(),
+            Self::ReachGoal | Self::CollectAll | Self::MarkedExactly(_) | Self::Facing(_) => vec![],
+        }
+    }
+
+    /// Squares which should end up marked, for UI to outline.
+    pub fn target_marks(&self) -> Vec<MapCoord> {
+        match self {
+            Self::MarkedExactly(targets) => targets.clone(),
+            Self::All(conds) | Self::Any(conds) => conds.iter().flat_map(|cond| cond.target_marks()).collect(),
+            Self::ReachGoal | Self::CollectAll | Self::VisitInOrder(_) | Self::Facing(_) => vec![],
+        }
+    }
+
+    /// MarkedExactly the squares drawn as '+' in an ascii pattern the size of the map.
+    pub fn marked_pattern(ascii_pattern: &[&str]) -> Self {
+        Self::MarkedExactly(ascii_pattern.iter().enumerate().flat_map(|(y, row)|
+            row.chars().enumerate().filter(|(_, ch)| *ch == '+').map(move |(x, _)| MapCoord::from_xy(x as i16, y as i16))
+        ).collect())
+    }
+}
+
+/// Described for the player, e.g. "collect everything, and reach the exit".
+impl std::fmt::Display for WinCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let join = |conds: &[WinCondition], sep: &str| conds.iter().map(|cond| cond.to_string()).collect::<Vec<_>>().join(sep);
+        match self {
+            Self::ReachGoal => write!(f, "reach the exit"),
+            Self::CollectAll => write!(f, "collect everything"),
+            Self::VisitInOrder(waypoints) => write!(f, "visit the {} marked squares in order", waypoints.len()),
+            Self::MarkedExactly(targets) if targets.is_empty() => write!(f, "remove every mark"),
+            Self::MarkedExactly(targets) => write!(f, "mark just the {} outlined squares", targets.len()),
+            Self::Facing(dir) => write!(f, "face {dir}"),
+            Self::All(conds) => write!(f, "{}", join(conds, ", and ")),
+            Self::Any(conds) => write!(f, "{}", join(conds, ", or ")),
+        }
+    }
+}
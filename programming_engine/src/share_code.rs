@@ -0,0 +1,77 @@
+// Share a program for a level as a short code, and read it back.
+//
+// Players paste codes to each other, or into links, so the code only uses
+// URL-safe chars: "PROG1." then level number, checksum, and URL-safe base64
+// of the program text, separated by dots.
+
+use crate::save_export::{base64_decode, base64_encode, checksum};
+use crate::scene::Subprog;
+
+const SHARE_PREFIX: &str = "PROG1";
+
+/// Code for prog on level, to paste into another game.
+pub fn prog_share_code(lev_idx: u16, prog: &Subprog) -> String {
+    let prog_txt = prog.as_instrs_text();
+    let payload = base64_encode(prog_txt.as_bytes()).replace('+', "-").replace('/', "_").replace('=', "");
+    format!("{SHARE_PREFIX}.{lev_idx}.{:08x}.{payload}", share_checksum(lev_idx, &prog_txt))
+}
+
+/// True if txt looks meant as a share code, rather than e.g. program text.
+pub fn is_share_code(txt: &str) -> bool {
+    txt.trim().starts_with(SHARE_PREFIX)
+}
+
+/// Level and prog from a share code.
+///
+/// Err with a message for the player if the code is garbled or was edited. The
+/// prog isn't checked against the level's supply.
+pub fn parse_share_code(code: &str) -> Result<(u16, Subprog), String> {
+    let garbled = || "Program code is garbled. Was all of it copied?".to_string();
+    let fields: Vec<&str> = code.trim().split('.').collect();
+    let [SHARE_PREFIX, lev_txt, checksum_txt, payload] = fields[..] else {
+        return Err("That doesn't look like a program code.".to_string());
+    };
+    let lev_idx = lev_txt.parse::<u16>().map_err(|_| garbled())?;
+    let prog_bytes = base64_decode(&payload.replace('-', "+").replace('_', "/")).ok_or_else(garbled)?;
+    let prog_txt = String::from_utf8(prog_bytes).map_err(|_| garbled())?;
+    if u32::from_str_radix(checksum_txt, 16).ok() != Some(share_checksum(lev_idx, &prog_txt)) {
+        return Err("Program code is garbled or has been edited.".to_string());
+    }
+    let prog = Subprog::try_from_text(&prog_txt)?;
+    Ok((lev_idx, prog))
+}
+
+// Short, as only needs to catch accidental damage to a short code.
+fn share_checksum(lev_idx: u16, prog_txt: &str) -> u32 {
+    checksum(format!("{lev_idx}:{prog_txt}").as_bytes()) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::prog_fn_ops::*;
+
+    #[test]
+    fn share_code_round_trip() {
+        let prog = Subprog::from(vec![F, LOOP(&[R, x2(&[F])]), L]);
+        let code = prog_share_code(12, &prog);
+        assert!(code.starts_with("PROG1.12."));
+        assert!(code.chars().all(|ch| ch.is_ascii_alphanumeric() || "-_.".contains(ch)));
+        assert!(is_share_code(&code));
+        assert_eq!(parse_share_code(&format!(" {code}\n")), Ok((12, prog)));
+
+        assert_eq!(parse_share_code(&prog_share_code(1, &Subprog::default())), Ok((1, Subprog::default())));
+    }
+
+    #[test]
+    fn share_code_rejects_invalid() {
+        let code = prog_share_code(3, &Subprog::from(vec![F, R]));
+        assert!(!is_share_code("F,R"));
+        assert!(parse_share_code("F,R").is_err());
+        assert!(parse_share_code(&code[..code.len() - 2]).is_err());
+        assert_eq!(
+            parse_share_code(&code.replacen("PROG1.3.", "PROG1.4.", 1)),
+            Err("Program code is garbled or has been edited.".to_string()),
+        );
+    }
+}
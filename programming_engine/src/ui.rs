@@ -8,6 +8,8 @@ mod ui_helpers;
 mod ui_arena;
 mod ui_coding_arena;
 mod ui_splash;
+mod ui_profile_picker;
+mod ui_stats;
 mod ui_lev_chooser;
 
 use ui_helpers::*;
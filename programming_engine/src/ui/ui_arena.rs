@@ -1,9 +1,9 @@
 use macroquad::prelude::*;
 
-use assrt::rsst;
-use crate::scene::arena::MapObj;
-use crate::scene::Arena;
+use crate::scene::arena::{MapObj, RosterIndex};
+use crate::scene::{Arena, ArenaEvent};
 use crate::game_data::BaseMovementLogic;
+use crate::map_coords::{Facing, MapCoord};
 
 use super::*;
 
@@ -20,9 +20,14 @@ pub struct UiArena<'a> {
     // Size of each tile
     sq_w: f32,
     sq_h: f32,
+    // Map dimensions in tiles. Used to tell moves which wrapped past the edge.
+    map_w: u16,
+    map_h: u16,
     texture_cache: &'a mut TextureCache,
     slide_frac: f32,
     anim_frac: f32,
+    // Events from most recent tick, used to animate moves, turns and bumps.
+    events: &'a [ArenaEvent],
 }
 
 impl<'a> UiArena<'a> {
@@ -50,9 +55,12 @@ impl<'a> UiArena<'a> {
             game_y,
             sq_w: sq_sz,
             sq_h: sq_sz,
+            map_w: w,
+            map_h: h,
             texture_cache,
             slide_frac: anim.slide_frac,
             anim_frac: anim.anim_frac,
+            events: state.events(),
         };
 
         render_lev.draw_backdrop();
@@ -72,6 +80,35 @@ impl<'a> UiArena<'a> {
                     self.draw_ent(x - ox, y - oy, ent).await;
                 }
             }
+            // Over floor, under anything on it.
+            if h == 0 {
+                self.draw_waypoints(&state.win_condition().waypoints(), (ox, oy));
+            }
+        }
+        // Over marks, so still visible once marked.
+        self.draw_target_marks(&state.win_condition().target_marks(), (ox, oy));
+    }
+
+    // Outline each square hero must mark.
+    fn draw_target_marks(&self, targets: &[MapCoord], (ox, oy): (i16, i16)) {
+        let inset = self.sq_w * 0.1;
+        for pos in targets {
+            let px = self.game_x + self.sq_w * (pos.x - ox) as f32;
+            let py = self.game_y + self.sq_h * (pos.y - oy) as f32;
+            draw_rectangle_lines(px + inset, py + inset, self.sq_w - inset * 2., self.sq_h - inset * 2., 3., PURPLE);
+        }
+    }
+
+    // Number each square hero must visit, in order.
+    fn draw_waypoints(&self, waypoints: &[MapCoord], (ox, oy): (i16, i16)) {
+        for (idx, pos) in waypoints.iter().enumerate() {
+            let px = self.game_x + self.sq_w * (pos.x - ox) as f32;
+            let py = self.game_y + self.sq_h * (pos.y - oy) as f32;
+            draw_circle(px + self.sq_w / 2., py + self.sq_h / 2., self.sq_w * 0.35, SKYBLUE);
+            let txt = (idx + 1).to_string();
+            let font_sz = self.sq_h * 0.6;
+            let txt_dims = measure_text(&txt, None, font_sz as u16, 1.);
+            draw_text(&txt, px + (self.sq_w - txt_dims.width) / 2., py + (self.sq_h + txt_dims.height) / 2., font_sz, DARKBLUE);
         }
     }
 
@@ -87,6 +124,60 @@ impl<'a> UiArena<'a> {
         Color {a: col.a * alpha, ..col}
     }
 
+    // Offset in tiles to nudge a mov towards a square it was blocked from entering, and back.
+    fn blocked_bump(&self, roster_idx: RosterIndex) -> (f32, f32) {
+        let bump_frac = 0.15 * (self.slide_frac * std::f32::consts::PI).sin();
+        self.events.iter().find_map(|event| match event {
+            ArenaEvent::Blocked { obj, pos, target } if *obj == roster_idx => Some((
+                (target.x - pos.x) as f32 * bump_frac,
+                (target.y - pos.y) as f32 * bump_frac,
+            )),
+            _ => None,
+        }).unwrap_or((0., 0.))
+    }
+
+    // Square mov slid from during the most recent tick, or its current square if it didn't move.
+    fn slid_from(&self, roster_idx: RosterIndex, pos: MapCoord) -> MapCoord {
+        self.events.iter().find_map(|event| match event {
+            ArenaEvent::Moved { obj, from, .. } if *obj == roster_idx => Some(*from),
+            _ => None,
+        }).unwrap_or(pos)
+    }
+
+    // Facing mov turned from during the most recent tick, or its current facing if it didn't turn.
+    fn turned_from(&self, roster_idx: RosterIndex, dir: Facing) -> Facing {
+        self.events.iter().find_map(|event| match event {
+            ArenaEvent::Rotated { obj, from, .. } if *obj == roster_idx => Some(*from),
+            _ => None,
+        }).unwrap_or(dir)
+    }
+
+    // Convert a move in tiles to the shortest equivalent if the map wraps, e.g. moving from
+    // x=w-1 to x=0 is a move of +1 not -(w-1). Otherwise the obj would slide across the map.
+    fn wrapped_delta(delta: i16, len: u16) -> i16 {
+        let len = len as i16;
+        if delta > len / 2 {
+            delta - len
+        } else if delta < -len / 2 {
+            delta + len
+        } else {
+            delta
+        }
+    }
+
+    // Only draw within the arena until unclipped, e.g. so an obj wrapping past the
+    // edge doesn't slide over whatever is drawn beside the map.
+    fn clip_to_arena(&self, clip: bool) {
+        let dpi = screen_dpi_scale();
+        let clip_rect = clip.then_some((
+            (self.game_x * dpi) as i32,
+            (self.game_y * dpi) as i32,
+            (self.sq_w * self.map_w as f32 * dpi) as i32,
+            (self.sq_h * self.map_h as f32 * dpi) as i32,
+        ));
+        unsafe { get_internal_gl() }.quad_gl.scissor(clip_rect);
+    }
+
     // Draw ent's texture/colour to the screen at specified tile coords.
     // Works out pixel coords given pixel size of arena area in RenderLev.
     pub async fn draw_ent<CustomProps: crate::for_gamedata::BaseCustomProps>(
@@ -100,7 +191,7 @@ impl<'a> UiArena<'a> {
         let visual_props = &obj.visual_props;
         let logical_props = &obj.logical_props;
         let pos = obj.pos();
-        let prev_pos = obj.prev_pos();
+        let prev_pos = self.slid_from(obj.roster_idx(), pos);
 
         let base_px = self.game_x + self.sq_w * vx as f32;
         let base_py = self.game_y + self.sq_h * vy as f32;
@@ -108,8 +199,16 @@ impl<'a> UiArena<'a> {
         // Used to draw tile smaller than real size. Not used at the moment.
         let pc_size = 1.;
 
-        let dx = pos.x - prev_pos.x;
-        let dy = pos.y - prev_pos.y;
+        let dx = Self::wrapped_delta(pos.x - prev_pos.x, self.map_w);
+        let dy = Self::wrapped_delta(pos.y - prev_pos.y, self.map_h);
+
+        // Obj which wrapped past the edge slides in at the new edge, and a copy slides
+        // out past the old edge. Tile offsets at which to draw each copy.
+        let wrap_copies = if (dx, dy) == (pos.x - prev_pos.x, pos.y - prev_pos.y) {
+            vec![(0, 0)]
+        } else {
+            vec![(0, 0), (dx - (pos.x - prev_pos.x), dy - (pos.y - prev_pos.y))]
+        };
 
         // Switch to using fixed frame throughout from here?
         let slide_in_frame_units = Some(3);
@@ -119,56 +218,68 @@ impl<'a> UiArena<'a> {
             self.slide_frac
         };
 
-        let px = base_px + self.sq_w * (1.-pc_size) / 2. - (dx as f32 * (1.-slide_fr_frac) * self.sq_w);
-        let py = base_py + self.sq_h * (1.-pc_size) / 2. - (dy as f32 * (1.-slide_fr_frac) * self.sq_h);
-        let w = self.sq_w * pc_size;
-        let h = self.sq_h * pc_size;
-
-        if !logical_props.custom_props.is_any_mov() {rsst!(prev_pos == pos)}
+        let (bump_x, bump_y) = self.blocked_bump(obj.roster_idx());
 
         let alpha = 1.;
 
-        if let Some(col) = visual_props.fill {
-            draw_rectangle(px, py, w, h, Self::alpha_col(col, alpha));
+        let wrapped = wrap_copies.len() > 1;
+        if wrapped {
+            self.clip_to_arena(true);
         }
 
-        if let Some(col) = visual_props.border {
-            draw_rectangle_lines(px, py, w, h, 2., Self::alpha_col(col, alpha));
-        }
+        for (copy_vx, copy_vy) in wrap_copies {
+            let px = base_px + self.sq_w * (1.-pc_size) / 2. - (dx as f32 * (1.-slide_fr_frac) * self.sq_w) + (bump_x + copy_vx as f32) * self.sq_w;
+            let py = base_py + self.sq_h * (1.-pc_size) / 2. - (dy as f32 * (1.-slide_fr_frac) * self.sq_h) + (bump_y + copy_vy as f32) * self.sq_h;
+            let w = self.sq_w * pc_size;
+            let h = self.sq_h * pc_size;
+
+            if let Some(col) = visual_props.fill {
+                draw_rectangle(px, py, w, h, Self::alpha_col(col, alpha));
+            }
+
+            if let Some(col) = visual_props.border {
+                draw_rectangle_lines(px, py, w, h, 2., Self::alpha_col(col, alpha));
+            }
+
+            if visual_props.tex_paths.len() > 0 {
+                // TODO: Simplify calc? Prevent anim_frac being 100? Or being 0?
+                let tex_frame_idx = (visual_props.tex_paths.len()-1).min((self.anim_frac * visual_props.tex_paths.len() as f32) as usize);
+                let tex_path = &visual_props.tex_paths[tex_frame_idx];
 
-        if visual_props.tex_paths.len() > 0 {
-            // TODO: Simplify calc? Prevent anim_frac being 100? Or being 0?
-            let tex_frame_idx = (visual_props.tex_paths.len()-1).min((self.anim_frac * visual_props.tex_paths.len() as f32) as usize);
-            let tex_path = &visual_props.tex_paths[tex_frame_idx];
-
-            let tex_data: &Texture2D = if let Some(tex_data) = self.texture_cache.get(tex_path) {
-                tex_data
-            } else {
-                self.texture_cache.insert(tex_path.clone(), load_texture_unwrap(tex_path).await);
-                self.texture_cache.get(tex_path).unwrap()
-            };
-
-            // log::debug!("{:?}, {:?}", logical_props.prev_dir, logical_props.dir);
-            let prev_rotation = logical_props.prev_dir.as_angle();
-            let curr_rotation = logical_props.dir.as_angle();
-            let rotation = prev_rotation + (curr_rotation-prev_rotation)*self.slide_frac;
-            draw_texture_ex(
-                &tex_data,
-                px - w * (visual_props.tex_scale-1.0) / 2.,
-                py - h * (visual_props.tex_scale-1.0) / 2.,
-                WHITE,
-                DrawTextureParams {
-                    dest_size: Some(vec2(w * visual_props.tex_scale, h * visual_props.tex_scale)),
-                    rotation,
-                    ..Default::default()
-                    // TODO: alpha
-                },
-            );
+                let tex_data: &Texture2D = if let Some(tex_data) = self.texture_cache.get(tex_path) {
+                    tex_data
+                } else {
+                    self.texture_cache.insert(tex_path.clone(), load_texture_unwrap(tex_path).await);
+                    self.texture_cache.get(tex_path).unwrap()
+                };
+
+                let prev_rotation = self.turned_from(obj.roster_idx(), logical_props.dir).as_angle();
+                let curr_rotation = logical_props.dir.as_angle();
+                // Turn whichever way round is shorter, e.g. N to W is a quarter turn anticlockwise.
+                let turn = (curr_rotation - prev_rotation + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+                let rotation = prev_rotation + turn*self.slide_frac;
+                draw_texture_ex(
+                    &tex_data,
+                    px - w * (visual_props.tex_scale-1.0) / 2.,
+                    py - h * (visual_props.tex_scale-1.0) / 2.,
+                    WHITE,
+                    DrawTextureParams {
+                        dest_size: Some(vec2(w * visual_props.tex_scale, h * visual_props.tex_scale)),
+                        rotation,
+                        ..Default::default()
+                        // TODO: alpha
+                    },
+                );
+            }
+
+            if let Some(text) = visual_props.text.clone() {
+                let text_col = Self::alpha_col(visual_props.text_col.unwrap_or(DARKGRAY), alpha);
+                draw_text(&text, (px + w*0.1).floor(), (py + h*0.6).floor(), 15., text_col);
+            }
         }
 
-        if let Some(text) = visual_props.text.clone() {
-            let text_col = Self::alpha_col(visual_props.text_col.unwrap_or(DARKGRAY), alpha);
-            draw_text(&text, (px + w*0.1).floor(), (py + h*0.6).floor(), 15., text_col);
+        if wrapped {
+            self.clip_to_arena(false);
         }
     }
 }
@@ -7,6 +7,8 @@ use crate::scene::*;
 
 use super::ui_helpers::*;
 use super::ui_splash::*;
+use super::ui_profile_picker::*;
+use super::ui_stats::*;
 use super::ui_coding_arena::*;
 
 pub type TextureCache = HashMap<String, Texture2D>;
@@ -33,6 +35,12 @@ impl Ui {
                 UiSplash::advance(scene_struct);
                 let _r = UiSplash::do_frame(scene_struct);
             }
+            Scene::ProfilePicker(scene_struct) => {
+                UiProfilePicker::do_frame(scene_struct, state);
+            }
+            Scene::Stats(scene_struct) => {
+                UiStats::do_frame(scene_struct, state);
+            }
             Scene::CodingArena(scene_struct) => {
                 self.ui_coding_arena.advance::<GameData>(scene_struct);
                 self.ui_coding_arena.do_frame(scene_struct, &mut self.texture_cache, state).await;
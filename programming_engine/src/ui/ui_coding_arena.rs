@@ -1,6 +1,8 @@
 use macroquad::prelude::*;
 
 use crate::game_data::{BaseMovementLogic, BaseGameData};
+use crate::savegame::BaseSaveGame;
+use crate::share_code::{is_share_code, parse_share_code, prog_share_code};
 
 use crate::ui::ui_helpers::{was_any_input, was_key_pressed};
 use crate::scene::*;
@@ -23,10 +25,25 @@ enum InstrRef {
 struct DragOrigin {
     instr: Instr,
     op_ref: InstrRef,
+    // Where instr was in prog, if dragged from prog.
+    from_path: InstrPath,
     orig_offset_x: f32,
     orig_offset_y: f32,
 }
 
+// Prog typed in text panel, and why it was last rejected, if it was.
+struct ProgTextEdit {
+    txt: String,
+    error: Option<String>,
+}
+
+// Where a drag ended up.
+enum DragEnd {
+    ToProg(InstrPath),
+    ToSupply,
+    Cancel,
+}
+
 #[derive(Copy, Clone, Default)]
 pub struct OpSize {
     pub w: f32,
@@ -62,6 +79,8 @@ struct FrameCoords {
     arena: PRect,
     supply: PRect,
     prog: PRect,
+    // Prog as text, below prog.
+    text: PRect,
     lev_chooser: PRect,
 
     supply_op: OpSize,
@@ -143,6 +162,16 @@ impl OpStyle {
         }
     }
 
+    /// Placed by level, and can't be picked up.
+    pub fn coding_locked() -> Self {
+        Self {
+            border_width: 3.,
+            border_col: DARKBLUE,
+            fill_col: LIGHTGRAY,
+            scale: 1.0,
+        }
+    }
+
     pub fn dragging() -> Self {
         Self {
             border_width: 2.,
@@ -212,6 +241,18 @@ pub struct UiCodingArena {
 
     dragging: Option<DragOrigin>,
 
+    // Drag dropped into prog this frame, and where, to make into an edit once done with prog.
+    dropped: Option<(DragOrigin, InstrPath)>,
+
+    // Where prog is being edited by keyboard, if it is.
+    cursor: Option<ProgCursor>,
+
+    // Prog being typed as text, if it is.
+    text_edit: Option<ProgTextEdit>,
+
+    // Which of level's maps is shown while coding.
+    shown_test: usize,
+
     lev_chooser: LevChooser,
 
     /// Smoothly from 0 to 1 transition from previous state to current state
@@ -220,6 +261,9 @@ pub struct UiCodingArena {
 
     /// Record input from user ready for use.
     ticker: Ticker,
+
+    /// Message for player and time it was shown, e.g. result of importing progress.
+    notice: Option<(String, f64)>,
 }
 
 impl UiCodingArena
@@ -237,10 +281,20 @@ impl UiCodingArena
 
             dragging: None,
 
+            dropped: None,
+
+            cursor: None,
+
+            text_edit: None,
+
+            shown_test: 0,
+
             lev_chooser: LevChooser::default(),
 
             anim: AnimState::default(),
             ticker: Ticker::new(),
+
+            notice: None,
         }
     }
 
@@ -323,7 +377,8 @@ impl UiCodingArena
         self.is_dead = coding_arena_phase == CodingRunningPhase::Died;
 
         let supply_n_w = 1;
-        let supply_n_h = flow_n.max(6);
+        // Four more than number of bins, for reset, stats, undo and redo buttons.
+        let supply_n_h = (flow_n + 4).max(6);
         let prog_n_w = prog_n_w.max(2);
         let prog_n_h = prog_n_h.max(6);
 
@@ -356,11 +411,19 @@ impl UiCodingArena
                 h: screen_height() - lev_chooser.h,
             };
 
+            let text_h = if self.is_coding {60.} else {0.};
             let prog = PRect {
                 x: supply.x + supply.w,
                 y: lev_chooser.h,
                 w: screen_width() - arena.w - supply.w,
-                h: screen_height() - lev_chooser.h,
+                h: screen_height() - lev_chooser.h - text_h,
+            };
+
+            let text = PRect {
+                x: prog.x,
+                y: prog.y + prog.h,
+                w: prog.w,
+                h: text_h,
             };
 
             let prog_instr = self.choose_op_sz(prog.w, prog.h, prog_n_w, prog_n_h);
@@ -371,6 +434,7 @@ impl UiCodingArena
                 arena,
                 supply,
                 prog,
+                text,
                 lev_chooser,
                 supply_op,
                 prog_instr,
@@ -412,6 +476,7 @@ impl UiCodingArena
                 arena,
                 supply,
                 prog,
+                text: PRect { x: prog.x, y: prog.y + prog.h, w: prog.w, h: 0. },
                 lev_chooser,
                 supply_op,
                 prog_instr,
@@ -428,10 +493,15 @@ impl UiCodingArena
         // That is more like what I had before but makes sense with those values...
         match coding_arena.phase {
             CodingRunningPhase::Coding => {
-                // Continue to execution on: space/enter, or clicking on map.
-                if matches!(was_key_pressed(), Some(Ok)) ||
-                    is_mouse_button_pressed(MouseButton::Left) && self.mouse_in_rect(self.fr_pos.arena) {
+                // Continue to execution on: space/enter unless choosing level, or clicking on map.
+                let on_tab = (0..coding_arena.num_tests()).any(|test_idx| self.mouse_in_rect(self.test_tab_rect(coding_arena, test_idx)));
+                if matches!(was_key_pressed(), Some(Ok)) && !self.lev_chooser.has_focus() && self.text_edit.is_none() ||
+                    is_mouse_button_pressed(MouseButton::Left) && self.mouse_in_rect(self.fr_pos.arena) && !on_tab {
                     coding_arena.advance(InputCmd::Continue);
+                    // Prog breaks level's rules, so didn't run.
+                    if let Some(violation) = coding_arena.coding.rule_violations().first() {
+                        self.show_notice(&format!("Can't run program yet. {violation}"));
+                    }
                     }
             },
             CodingRunningPhase::Died => {
@@ -493,24 +563,211 @@ impl UiCodingArena
 
         if self.is_coding {
             self.anim = AnimState { slide_frac: 1., anim_frac: 0. };
-            UiArena::render(&coding_arena.init_arena, texture_cache, self.fr_pos.arena, self.anim).await;
+            self.shown_test = self.shown_test.min(coding_arena.num_tests() - 1);
+            UiArena::render(coding_arena.test_arena(self.shown_test), texture_cache, self.fr_pos.arena, self.anim).await;
         } else {
+            // Back to coding shows the map last run, e.g. the one prog failed on.
+            self.shown_test = coding_arena.curr_test;
             UiArena::render(coding_arena.curr_arena.as_mut().unwrap(), texture_cache, self.fr_pos.arena, self.anim).await;
         }
+        self.draw_test_tabs(coding_arena);
+        if self.is_coding {
+            self.draw_win_condition(coding_arena.test_arena(self.shown_test));
+        } else {
+            self.draw_win_condition(coding_arena.curr_arena.as_ref().unwrap());
+        }
 
         self.draw_prog(GameData::MovementLogic::current_prog(coding_arena));
         if self.is_coding {
+            self.draw_cursor(&coding_arena.coding);
+            self.draw_rule_violations(&coding_arena.coding);
             self.draw_supply(&mut coding_arena.coding);
             self.draw_widget_outline(self.fr_pos.lev_chooser, self.border_cols());
-            self.lev_chooser.do_frame(game_state, self.fr_pos.lev_chooser);
+            self.lev_chooser.do_frame(game_state, self.fr_pos.lev_chooser, self.text_edit.is_none());
+            self.draw_text_panel(&coding_arena.coding);
             self.draw_dragging();
+        } else {
+            self.lev_chooser.drop_focus();
+            self.text_edit = None;
+        }
+        self.draw_notice();
+        if coding_arena.is_replay() {
+            self.draw_replay_banner();
         }
 
         self.interact_prog(GameData::MovementLogic::current_prog(coding_arena));
         if self.is_coding {
+            if let Some((drag, to)) = self.dropped.take() {
+                Self::finish_drag(&mut coding_arena.coding, drag, DragEnd::ToProg(to));
+            }
+            self.interact_test_tabs(coding_arena);
+            self.interact_text_panel(&mut coding_arena.coding);
             self.interact_supply(&mut coding_arena.coding);
+            self.interact_keyboard_editing(&mut coding_arena.coding);
             self.interact_dragging(&mut coding_arena.coding);
+            self.interact_save_transfer(game_state);
+            self.interact_prog_clipboard(&mut coding_arena.coding, game_state);
+            self.interact_stats_button(coding_arena);
+        }
+    }
+
+    // Export progress to clipboard on: Ctrl+E. Import from clipboard on: Ctrl+I.
+    fn interact_save_transfer<GameData: BaseGameData>(&mut self, game_state: &mut GameData) {
+        let ctrl = (is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl)) && self.text_edit.is_none();
+        if ctrl && is_key_pressed(KeyCode::E) {
+            match game_state.save_game().export_progress() {
+                Some(blob) => {
+                    miniquad::window::clipboard_set(&blob);
+                    self.show_notice("Save code copied to clipboard.");
+                },
+                None => self.show_notice("This game doesn't have saved progress."),
+            }
+        } else if ctrl && is_key_pressed(KeyCode::I) {
+            let blob = miniquad::window::clipboard_get().unwrap_or_default();
+            match game_state.save_game().import_progress(&blob) {
+                Ok(()) => {
+                    self.show_notice("Progress imported.");
+                    // Reload level to show imported prog. Stay on level if still unlocked.
+                    let unlocked = game_state.save_game().get_unlocked_levels();
+                    let curr_lev = game_state.get_current_level();
+                    let lev_idx = if unlocked.contains(&curr_lev) {curr_lev} else {unlocked.into_iter().max().unwrap_or(1)};
+                    game_state.goto_level(lev_idx);
+                },
+                Err(msg) => self.show_notice(&msg),
+            }
+        }
+    }
+
+    // Copy prog as text on: Ctrl+C. Copy code to share prog on: Ctrl+Shift+C. Paste either on: Ctrl+V.
+    fn interact_prog_clipboard<GameData: BaseGameData>(&mut self, coding: &mut Coding, game_state: &mut GameData) {
+        let ctrl = (is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl)) && self.text_edit.is_none();
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        if self.dragging.is_some() || !ctrl {
+            return;
         }
+        if is_key_pressed(KeyCode::C) {
+            if shift {
+                miniquad::window::clipboard_set(&prog_share_code(game_state.get_current_level(), &coding.prog));
+                self.show_notice("Program code copied to clipboard. Paste it with Ctrl+V to load the program.");
+            } else {
+                miniquad::window::clipboard_set(&coding.prog.as_instrs_text());
+                self.show_notice("Program copied to clipboard.");
+            }
+        } else if is_key_pressed(KeyCode::V) {
+            let txt = miniquad::window::clipboard_get().unwrap_or_default();
+            match Self::paste_prog(&txt, coding, game_state) {
+                Ok(msg) | Err(msg) => self.show_notice(&msg),
+            }
+        }
+    }
+
+    /// Replace prog with pasted program text or program code. A code for another level goes to
+    /// that level with the prog. Err if it doesn't fit the level's supply, leaving prog as it was.
+    fn paste_prog<GameData: BaseGameData>(txt: &str, coding: &mut Coding, game_state: &mut GameData) -> Result<String, String> {
+        if !is_share_code(txt) {
+            coding.edit_from_text(txt)?;
+            return Ok("Program pasted.".to_string());
+        }
+        let (lev_idx, prog) = parse_share_code(txt)?;
+        if lev_idx == game_state.get_current_level() {
+            coding.edit(EditCmd::Replace { prog })?;
+            return Ok("Program pasted.".to_string());
+        }
+        if !game_state.save_game().get_unlocked_levels().contains(&lev_idx) {
+            return Err(format!("That program is for level {lev_idx}, which isn't unlocked yet."));
+        }
+        // Check prog fits level before going there. Loaded there like a prog from the stats screen.
+        match game_state.load_level_scene(lev_idx) {
+            Some(Scene::CodingArena(mut coding_arena)) => coding_arena.coding.restore_prog(prog.clone())?,
+            _ => return Err(format!("That program is for level {lev_idx}, which doesn't have programs.")),
+        }
+        game_state.save_game().store_current_prog(lev_idx, &prog.to_string());
+        game_state.goto_level(lev_idx);
+        Ok(format!("Program pasted into level {lev_idx}."))
+    }
+
+    fn show_notice(&mut self, msg: &str) {
+        log::info!("{msg}");
+        self.notice = Some((msg.to_string(), get_time()));
+    }
+
+    /// Draw notice along bottom of arena for a few seconds after it's shown.
+    fn draw_notice(&mut self) {
+        let show_for = 4.;
+        if let Some((_, shown_at)) = &self.notice && get_time() > shown_at + show_for {
+            self.notice = None;
+        }
+        // Otherwise remind of keys while editing by keyboard.
+        let keys_hint = "Arrows: move. Shift: select. 1-9: add/wrap. Del: remove. Esc: done.";
+        let msg = match (&self.notice, &self.cursor) {
+            (Some((msg, _)), _) => msg.as_str(),
+            (None, Some(_)) if self.is_coding => keys_hint,
+            _ => return,
+        };
+        let font_sz = 24.;
+        let rect = PRect {x: self.fr_pos.arena.x, y: self.fr_pos.arena.h - 40., w: self.fr_pos.arena.w, h: 40.};
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color {a: 0.8, ..WHITE});
+        draw_text(msg, rect.x + 10., rect.y + 27., font_sz, self.font_col());
+    }
+
+    // Tab for each map of a level with more than one, below where replay banner goes.
+    fn test_tab_rect<MovementLogic: BaseMovementLogic>(&self, coding_arena: &CodingArena<MovementLogic>, test_idx: usize) -> PRect {
+        if coding_arena.num_tests() < 2 {
+            return PRect::default();
+        }
+        let (w, h, gap) = (90., 30., 8.);
+        PRect {x: self.fr_pos.arena.x + gap + test_idx as f32 * (w + gap), y: self.fr_pos.arena.y + 45., w, h}
+    }
+
+    /// Draw tab for each map prog is tested on, coloured by whether prog solved it in the latest
+    /// run. Outline map shown.
+    fn draw_test_tabs<MovementLogic: BaseMovementLogic>(&self, coding_arena: &CodingArena<MovementLogic>) {
+        if coding_arena.num_tests() < 2 {
+            return;
+        }
+        for (test_idx, result) in coding_arena.test_results.iter().enumerate() {
+            let rect = self.test_tab_rect(coding_arena, test_idx);
+            let (fill_col, result_txt) = match result {
+                Some(true) => (Color::new(0.6, 0.9, 0.6, 1.), "pass"),
+                Some(false) => (Color::new(0.95, 0.6, 0.6, 1.), "fail"),
+                None => (Color {a: 0.8, ..WHITE}, ""),
+            };
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, fill_col);
+            let (border_width, border_col) = if test_idx == self.shown_test {(4., DARKBLUE)} else {(1., DARKGRAY)};
+            draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, border_width, border_col);
+            draw_text(&format!("Map {} {result_txt}", test_idx + 1), rect.x + 8., rect.y + 21., 20., self.font_col());
+        }
+    }
+
+    // Show a map on: click on its tab.
+    fn interact_test_tabs<MovementLogic: BaseMovementLogic>(&mut self, coding_arena: &CodingArena<MovementLogic>) {
+        if self.dragging.is_some() || !is_mouse_button_pressed(MouseButton::Left) {
+            return;
+        }
+        if let Some(test_idx) = (0..coding_arena.num_tests()).find(|test_idx| self.mouse_in_rect(self.test_tab_rect(coding_arena, *test_idx))) {
+            self.shown_test = test_idx;
+        }
+    }
+
+    /// Draw what level asks for, unless it's just reaching the exit, above where notices go.
+    fn draw_win_condition<MovementLogic: BaseMovementLogic>(&self, arena: &Arena<MovementLogic>) {
+        if *arena.win_condition() == WinCondition::ReachGoal {
+            return;
+        }
+        let mut msg = format!("Goal: {}.", arena.win_condition());
+        if arena.num_collectibles() > 0 {
+            msg += &format!(" {} left to collect.", arena.num_collectibles());
+        }
+        let rect = PRect {x: self.fr_pos.arena.x, y: self.fr_pos.arena.h - 80., w: self.fr_pos.arena.w, h: 36.};
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color {a: 0.8, ..WHITE});
+        draw_text(&msg, rect.x + 10., rect.y + 25., 22., self.font_col());
+    }
+
+    /// Draw reminder along top of arena that this is a replay, and how to control it.
+    fn draw_replay_banner(&self) {
+        let rect = PRect {x: self.fr_pos.arena.x, y: self.fr_pos.arena.y, w: self.fr_pos.arena.w, h: 40.};
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color {a: 0.8, ..WHITE});
+        draw_text("Replay. Space or click map: change speed. Escape: back.", rect.x + 10., rect.y + 27., 24., self.font_col());
     }
 
     fn supply_rect(&self) -> PRect {
@@ -596,6 +853,9 @@ impl UiCodingArena
         for (idx, bin) in coding.supply.iter().enumerate() {
             self.draw_supply_op(idx, bin);
         }
+        self.draw_reset_button(coding);
+        self.draw_stats_button(coding);
+        self.draw_undo_redo_buttons(coding);
 
         self.draw_widget_outline(self.fr_pos.supply, self.border_cols());
     }
@@ -605,7 +865,7 @@ impl UiCodingArena
         let coords = self.supply_op_coords(idx);
         let active = false;
         let has_op = bin.curr_count > 0;
-        self.draw_op_rect(coords, self.calculate_op_style(coords, active, has_op, InstrRef::Supply {idx}, self.is_droppable_on_supply_bin(idx, bin.op)), &bin.op.as_text());
+        self.draw_op_rect(coords, self.calculate_op_style(coords, active, has_op, InstrRef::Supply {idx}, false, self.is_droppable_on_supply_bin(idx, bin.op)), &bin.op.as_text());
 
         // Draw count
         let count_txt = format!("{}/{}", bin.curr_count, bin.orig_count);
@@ -613,11 +873,36 @@ impl UiCodingArena
         draw_text(&count_txt, coords.x + 0.5*self.fr_pos.supply_op.w, coords.y+1.25*self.fr_pos.supply_op.h, supply_count_font_sz, self.font_col());
     }
 
+    /// Draw button for returning all instrs in prog to supply, below supply bins.
+    fn draw_reset_button(&self, coding: &Coding) {
+        let idx = coding.supply.len();
+        let coords = self.supply_op_coords(idx);
+        let has_prog = coding.prog != coding.start_prog;
+        self.draw_op_rect(coords, self.calculate_op_style(coords, false, has_prog, InstrRef::Supply {idx}, false, false), "Reset");
+    }
+
+    /// Draw button for showing stats and history of attempts, below reset button.
+    fn draw_stats_button(&self, coding: &Coding) {
+        let idx = coding.supply.len() + 1;
+        let coords = self.supply_op_coords(idx);
+        self.draw_op_rect(coords, self.calculate_op_style(coords, false, true, InstrRef::Supply {idx}, false, false), "Stats");
+    }
+
+    /// Draw buttons for undoing and redoing edits to prog, below stats button.
+    fn draw_undo_redo_buttons(&self, coding: &Coding) {
+        for (idx, label, available) in [(coding.supply.len() + 2, "Undo", coding.can_undo()), (coding.supply.len() + 3, "Redo", coding.can_redo())] {
+            let coords = self.supply_op_coords(idx);
+            self.draw_op_rect(coords, self.calculate_op_style(coords, false, available, InstrRef::Supply {idx}, false, false), label);
+        }
+    }
+
     /// Interact supply area and all supply bins
     fn interact_supply(&mut self, coding: &mut Coding) {
         for idx in 0..coding.supply.len() {
             self.interact_supply_op(coding, idx);
         }
+        self.interact_reset_button(coding);
+        self.interact_undo_redo_buttons(coding);
 
         if self.mouse_in_rect(self.supply_rect()) {
             if is_mouse_button_released(MouseButton::Left) {
@@ -639,6 +924,181 @@ impl UiCodingArena
         }
     }
 
+    // Reset prog on: click on reset button, or Shift+Delete.
+    fn interact_reset_button(&mut self, coding: &mut Coding) {
+        let coords = self.supply_op_coords(coding.supply.len());
+        let clicked = is_mouse_button_pressed(MouseButton::Left) && self.mouse_in_coords(coords);
+        // Needs Shift so a stray Delete doesn't wipe the prog. Delete removes one instr while editing by keyboard.
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        let key_pressed = shift && is_key_pressed(KeyCode::Delete) && self.cursor.is_none() && self.text_edit.is_none();
+        if self.is_coding && self.dragging.is_none() && (clicked || key_pressed) {
+            log::debug!("Resetting prog");
+            coding.edit(EditCmd::Reset).unwrap();
+        }
+    }
+
+    // Undo on: click on undo button, or Ctrl+Z. Redo on: click on redo button, or Ctrl+Y or Ctrl+Shift+Z.
+    fn interact_undo_redo_buttons(&mut self, coding: &mut Coding) {
+        if !self.is_coding || self.dragging.is_some() {
+            return;
+        }
+        let clicked = |idx| is_mouse_button_pressed(MouseButton::Left) && self.mouse_in_coords(self.supply_op_coords(idx));
+        let ctrl = (is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl)) && self.text_edit.is_none();
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        if clicked(coding.supply.len() + 2) || ctrl && !shift && is_key_pressed(KeyCode::Z) {
+            coding.undo();
+        } else if clicked(coding.supply.len() + 3) || ctrl && (is_key_pressed(KeyCode::Y) || shift && is_key_pressed(KeyCode::Z)) {
+            coding.redo();
+        }
+    }
+
+    // Show stats on: click on stats button, or H key.
+    fn interact_stats_button<MovementLogic: BaseMovementLogic>(&mut self, coding_arena: &mut CodingArena<MovementLogic>) {
+        let coords = self.supply_op_coords(coding_arena.coding.supply.len() + 1);
+        let clicked = is_mouse_button_pressed(MouseButton::Left) && self.mouse_in_coords(coords);
+        let key_pressed = is_key_pressed(KeyCode::H) && !self.lev_chooser.has_focus() && self.cursor.is_none() && self.text_edit.is_none();
+        if self.is_coding && self.dragging.is_none() && (clicked || key_pressed) {
+            coding_arena.request_stats();
+        }
+    }
+
+    // Edit prog by keyboard, starting on: arrow key. Move on: Up/Down within subprog, Right into
+    // parent instr, Left out of it. Select on: Shift+Up/Down. Add instr from supply bin, or put
+    // selection inside it, on: 1-9. Remove instr on: Delete/Backspace. Stop on: Escape.
+    fn interact_keyboard_editing(&mut self, coding: &mut Coding) {
+        if self.dragging.is_some() || self.lev_chooser.has_focus() || self.text_edit.is_some() {
+            return;
+        }
+        let Some(mut cursor) = self.cursor.take() else {
+            if [KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right].into_iter().any(is_key_pressed) {
+                self.cursor = Some(ProgCursor::at_end(&coding.prog));
+            }
+            return;
+        };
+        if is_key_pressed(KeyCode::Escape) {
+            return;
+        }
+
+        cursor.clamp_to(&coding.prog);
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        let cmd = if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Down) {
+            cursor.step(&coding.prog, is_key_pressed(KeyCode::Down), shift);
+            None
+        } else if is_key_pressed(KeyCode::Right) {
+            cursor.step_in(&coding.prog);
+            None
+        } else if is_key_pressed(KeyCode::Left) {
+            cursor.step_out();
+            None
+        } else if is_key_pressed(KeyCode::Delete) || is_key_pressed(KeyCode::Backspace) {
+            cursor.edit_for_delete(&coding.prog)
+        } else {
+            bin_key_pressed().filter(|bin_idx| *bin_idx < coding.supply.len())
+                .map(|bin_idx| cursor.edit_for_bin(coding, bin_idx))
+        };
+        if let Some(cmd) = cmd {
+            match coding.edit(cmd.clone()) {
+                Ok(()) => cursor.after_edit(&coding.prog, &cmd),
+                Err(msg) => self.show_notice(&msg),
+            }
+        }
+        self.cursor = Some(cursor);
+    }
+
+    // Type prog as text, starting on: click on text panel, or T. Paste on: Ctrl+V. Use it on: Enter.
+    // Stop on: Escape.
+    fn interact_text_panel(&mut self, coding: &mut Coding) {
+        if self.dragging.is_some() {
+            return;
+        }
+        let Some(text_edit) = &mut self.text_edit else {
+            let clicked = is_mouse_button_pressed(MouseButton::Left) && self.mouse_in_rect(self.fr_pos.text);
+            if clicked || is_key_pressed(KeyCode::T) && !self.lev_chooser.has_focus() {
+                // Start from prog as it is, without whatever was typed before, e.g. the T.
+                clear_input_queue();
+                self.text_edit = Some(ProgTextEdit { txt: coding.prog.as_instrs_text(), error: None });
+                self.cursor = None;
+                self.lev_chooser.drop_focus();
+            }
+            return;
+        };
+        if is_key_pressed(KeyCode::Escape) {
+            self.text_edit = None;
+        } else if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            match coding.edit_from_text(&text_edit.txt) {
+                Ok(()) => self.text_edit = None,
+                Err(msg) => text_edit.error = Some(msg),
+            }
+        } else if (is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl)) && is_key_pressed(KeyCode::V) {
+            text_edit.txt.push_str(miniquad::window::clipboard_get().unwrap_or_default().trim());
+        } else {
+            type_into(&mut text_edit.txt);
+        }
+    }
+
+    /// Draw prog as text below prog, or text being typed with why it was rejected, if it was.
+    fn draw_text_panel(&self, coding: &Coding) {
+        let rect = self.fr_pos.text;
+        let font_sz = 18.;
+        let line_h = 18.;
+        let fill = match &self.text_edit {
+            Some(_) => WHITE,
+            None if self.mouse_in_rect(rect) && self.dragging.is_none() => Color {a: 0.5, ..WHITE},
+            None => self.background_col(),
+        };
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, fill);
+        self.draw_widget_outline(rect, self.border_cols());
+
+        let (txt, txt_col, status, status_col) = match &self.text_edit {
+            Some(ProgTextEdit { txt, error: Some(error) }) => (format!("{txt}_"), self.font_col(), error.as_str(), RED),
+            Some(ProgTextEdit { txt, error: None }) => (format!("{txt}_"), self.font_col(), "Enter: use program. Esc: cancel.", GRAY),
+            None => (coding.prog.as_instrs_text(), GRAY, "T or click: type program. Ctrl+C/V: copy/paste.", GRAY),
+        };
+        // Show end of text, where typing happens.
+        let lines = wrap_text(&txt, rect.w - 10., font_sz);
+        for (row, line) in lines.iter().skip(lines.len().saturating_sub(2)).enumerate() {
+            draw_text(line, rect.x + 5., rect.y + (row + 1) as f32 * line_h, font_sz, txt_col);
+        }
+        draw_text(status, rect.x + 5., rect.y + 3. * line_h, font_sz, status_col);
+    }
+
+    /// Draw rules of level prog breaks, along bottom of prog.
+    fn draw_rule_violations(&self, coding: &Coding) {
+        let font_sz = 20.;
+        let line_h = 22.;
+        let rect = self.fr_pos.prog;
+        for (row, violation) in coding.rule_violations().iter().rev().enumerate() {
+            draw_text(violation, rect.x + 5., rect.y + rect.h - 8. - row as f32 * line_h, font_sz, RED);
+        }
+    }
+
+    /// Draw keyboard editing cursor as a bar where instrs are added, outlining instr after it,
+    /// and shading selection. Number supply bins by key which adds their instr.
+    fn draw_cursor(&self, coding: &Coding) {
+        let Some(cursor) = &self.cursor else {
+            return;
+        };
+        if let Some(range) = cursor.selection(&coding.prog) {
+            let parent_path = &cursor.path[..cursor.path.len() - 1];
+            for idx in range {
+                let (xidx, yidx) = ProgCursor::grid_pos(&coding.prog, &[parent_path, &[idx]].concat());
+                let c = self.prog_instr_coords(xidx, yidx);
+                draw_rectangle(c.x, c.y, c.w, c.h, Color {a: 0.3, ..ORANGE});
+            }
+        }
+        let (xidx, yidx) = ProgCursor::grid_pos(&coding.prog, &cursor.path);
+        let c = self.prog_instr_coords(xidx, yidx);
+        if cursor.instr(&coding.prog).is_some() {
+            draw_rectangle_lines(c.x - 3., c.y - 3., c.w + 6., c.h + 6., 2., ORANGE);
+        }
+        draw_line(c.x - 3., c.y - 4., c.x + c.w + 3., c.y - 4., 4., BLUE);
+
+        for idx in 0..coding.supply.len().min(9) {
+            let coords = self.supply_op_coords(idx);
+            draw_text(&format!("{}", idx + 1), coords.x + 3., coords.y + 14., 16., BLUE);
+        }
+    }
+
     fn draw_prog(&self, prog: &Subprog) {
         self.draw_widget_outline(self.fr_pos.prog, self.border_cols());
 
@@ -653,7 +1113,7 @@ impl UiCodingArena
     fn draw_placeholder_rect(&self, xidx: usize, yidx: usize) {
         let coords = self.prog_instr_coords(xidx, yidx);
         let txt = "...".to_string();
-        self.draw_op_rect(coords, self.calculate_op_style(coords, false, false, InstrRef::Prog {idx: 0}, self.is_droppable_onto_prog_instr(xidx, yidx)), &txt);
+        self.draw_op_rect(coords, self.calculate_op_style(coords, false, false, InstrRef::Prog {idx: 0}, false, self.is_droppable_onto_prog_instr(xidx, yidx)), &txt);
     }
 
     /// Draw subprog, either top-level prog, or inside a parent instr. At specified instr coords.
@@ -683,7 +1143,7 @@ impl UiCodingArena
         let coords = self.prog_instr_coords(xidx, yidx);
         let highlight_above = room_for_more && self.is_droppable_before_prog_instr(xidx, yidx);
 
-        self.draw_op_rect(coords, self.calculate_op_style(coords, active, true, InstrRef::Prog {idx: yidx}, highlight_above), &instr.as_text());
+        self.draw_op_rect(coords, self.calculate_op_style(coords, active, true, InstrRef::Prog {idx: yidx}, instr.is_locked(), highlight_above), &instr.as_text());
 
         if let Some(connector_yidx) = prev_yidx {
             self.draw_v_connector(self.prog_instr_coords(xidx, connector_yidx), coords, highlight_above);
@@ -706,10 +1166,10 @@ impl UiCodingArena
         if self.is_coding
         {
             // Specially treat START or first instr as accepting a drop anywhere?
-            self.interact_prog_instr(0, 0, prog, 0, true);
+            self.interact_prog_instr(0, 0, prog, 0, true, &[]);
 
             // Deal with all subsequent instr normally. Ie. Dropped onto top or bottom of instr for before or after.
-            self.interact_subprog(0, 0, prog, true);
+            self.interact_subprog(0, 0, prog, true, &[]);
         }
     }
 
@@ -719,11 +1179,13 @@ impl UiCodingArena
     ///
     /// If idx is equal to prog len, treats an instr-rect sized placeholder at that index. Currently only used
     /// when both are 0.
-    fn interact_subprog(&mut self, subprog_xidx: usize, subprog_yidx: usize, prog: &mut Prog, room_for_more: bool) {
+    ///
+    /// Path is of parent instrs of subprog, empty for top-level prog.
+    fn interact_subprog(&mut self, subprog_xidx: usize, subprog_yidx: usize, prog: &mut Prog, room_for_more: bool, path: &[usize]) {
         let mut prev_instr_yidx = None;
         let mut instr_yidx = subprog_yidx;
         for idx in 0..prog.instrs.len() {
-            self.interact_prog_instr(subprog_xidx, instr_yidx, prog, idx, room_for_more);
+            self.interact_prog_instr(subprog_xidx, instr_yidx, prog, idx, room_for_more, path);
             if idx >= prog.instrs.len() {
                 // TODO: More explicltly deal with prog changing while recursing.
                 // Either use calculations based on original. Or bail out when finding first pick-up.
@@ -733,22 +1195,25 @@ impl UiCodingArena
             instr_yidx += prog.instrs[idx].v_len();
         }
         if room_for_more && let Some(placeholder_yidx) = prev_instr_yidx && prog.instrs.last().as_ref().unwrap().v_connector() {
-            self.interact_placeholder_below(subprog_xidx, placeholder_yidx, prog, prog.instrs.len());
+            self.interact_placeholder_below(subprog_xidx, placeholder_yidx, prog.instrs.len(), path);
         }
     }
 
     /// Interact dragging/dropping with an instr in program. Including subprog.
-    fn interact_prog_instr(&mut self, xidx: usize, yidx: usize, prog: &mut Prog, idx: usize, room_for_more: bool)
+    fn interact_prog_instr(&mut self, xidx: usize, yidx: usize, prog: &mut Prog, idx: usize, room_for_more: bool, path: &[usize])
     {
         // TODO: Better guards for altered program.
         let coords = self.prog_instr_coords(xidx, yidx);
+        let instr_path = [path, &[idx]].concat();
         if self.is_pickable_from_prog_instr(xidx, yidx) && is_mouse_button_pressed(MouseButton::Left) {
-            if idx < prog.instrs.len() {
-                self.drag_prog_instr(prog, idx, mouse_position().0 - coords.x, mouse_position().1 - coords.y);
+            if prog.instrs.get(idx).is_some_and(Instr::contains_locked) {
+                self.show_notice("That instr is locked in place.");
+            } else if idx < prog.instrs.len() {
+                self.drag_prog_instr(prog, instr_path, mouse_position().0 - coords.x, mouse_position().1 - coords.y);
             }
         } else if room_for_more && self.is_droppable_before_prog_instr(xidx, yidx) && is_mouse_button_released(MouseButton::Left) {
             if idx <= prog.instrs.len() {
-                self.drop_to_prog(prog, idx);
+                self.drop_to_prog(instr_path);
             }
         } else {
             // Recurse to detect interaction in subprog
@@ -757,38 +1222,28 @@ impl UiCodingArena
                 if let Instr::Parent(instr, subprog) = instr {
                     let subprog_room_for_more = subprog.instrs.len() < instr.r_connect_max();
                     if subprog.instrs.len() > 0 {
-                        self.interact_subprog(xidx + 1, yidx, subprog, subprog_room_for_more);
+                        self.interact_subprog(xidx + 1, yidx, subprog, subprog_room_for_more, &instr_path);
                     } else {
-                        self.interact_prog_instr(xidx + 1, yidx, subprog, 0, subprog_room_for_more);
+                        self.interact_prog_instr(xidx + 1, yidx, subprog, 0, subprog_room_for_more, &instr_path);
                     }
                 }
             }
         }
     }
 
-    fn interact_placeholder_below(&mut self, xidx: usize, yidx: usize, prog: &mut Prog, idx: usize)
+    fn interact_placeholder_below(&mut self, xidx: usize, yidx: usize, idx: usize, path: &[usize])
     {
         if self.is_droppable_on_placeholder_below(xidx, yidx) && is_mouse_button_released(MouseButton::Left) {
-            self.drop_to_prog(prog, idx);
+            self.drop_to_prog([path, &[idx]].concat());
         }
     }
 
     fn interact_dragging(&mut self, coding: &mut Coding) {
         // If mouse is released anywhere else, cancel drag, return dragged op to its origin.
         // Use "!is_mouse_button_down" not "is_mouse_buttom_released" to ensure dragging is stopped.
-        if !is_mouse_button_down(MouseButton::Left) {
-            match &self.dragging {
-                Some(DragOrigin { instr:_instr, op_ref: InstrRef::Supply { idx }, ..}) => {
-                    log::debug!("INFO: Cancelling drag. Returning {:?} to supply idx {:?}", _instr, idx);
-                    self.drop_to_supply_bin(coding, *idx);
-                },
-                Some(DragOrigin { instr: _instr, op_ref: InstrRef::Prog { idx }, ..}) => {
-                    log::debug!("INFO: Cancelling drag. Returning {:?} to supply idx {:?}", _instr, idx);
-                    // TODO: !!
-                    self.drop_to_prog(&mut coding.prog, *idx);
-                },
-                None => (),
-            }
+        if !is_mouse_button_down(MouseButton::Left) && let Some(drag) = self.dragging.take() {
+            log::debug!("INFO: Cancelling drag. Returning {:?} to where it came from", drag.instr);
+            Self::finish_drag(coding, drag, DragEnd::Cancel);
         }
     }
 
@@ -808,19 +1263,21 @@ impl UiCodingArena
         }
     }
 
-    fn calculate_op_style(&self, coords: OpCoords, active: bool, has_op: bool, instr_ref: InstrRef, droppable: bool) -> OpStyle
+    fn calculate_op_style(&self, coords: OpCoords, active: bool, has_op: bool, instr_ref: InstrRef, locked: bool, droppable: bool) -> OpStyle
     {
         let drag_origin = matches!(self.dragging, Some(DragOrigin{op_ref: orig_op_ref, ..}) if orig_op_ref == instr_ref);
 
         let mut style;
         if self.is_coding {
-            style = if has_op {
+            style = if locked {
+                OpStyle::coding_locked()
+            } else if has_op {
                 OpStyle::coding()
             } else {
                 OpStyle::coding_placeholder(self.background_col())
             };
 
-            if matches!(self.dragging, None) && has_op && self.mouse_in_coords(coords) {
+            if matches!(self.dragging, None) && has_op && !locked && self.mouse_in_coords(coords) {
                 // Available to pick up
                 style = OpStyle::highlighted(style);
             }
@@ -944,6 +1401,7 @@ impl UiCodingArena
             Some(DragOrigin {
                 instr: Instr::from_opcode(bin.op),
                 op_ref: InstrRef::Supply { idx },
+                from_path: vec![],
                 orig_offset_x: orig_offset_x * self.fr_pos.prog_instr.w / self.fr_pos.supply_op.w,
                 orig_offset_y: orig_offset_y * self.fr_pos.prog_instr.w / self.fr_pos.supply_op.w
             })
@@ -952,62 +1410,88 @@ impl UiCodingArena
         }
     }
 
-    fn drag_prog_instr(&mut self, prog: &mut Prog, idx: usize, orig_offset_x: f32, orig_offset_y: f32) {
+    // Path is of instr in whole prog. Prog is the subprog it's in.
+    fn drag_prog_instr(&mut self, prog: &mut Prog, path: InstrPath, orig_offset_x: f32, orig_offset_y: f32) {
         // TODO: Test not already dragging?
+        let idx = *path.last().unwrap();
         let instr = prog.instrs.remove(idx);
         log::debug!("INFO: Dragging {:?} from prog", instr);
         self.dragging = Some(DragOrigin {
             instr,
             op_ref: InstrRef::Prog { idx },
+            from_path: path,
             orig_offset_x,
             orig_offset_y
         })
     }
 
     fn drop_to_supply_bin(&mut self, coding: &mut Coding, idx: usize) {
-        if let Some(DragOrigin {instr, ..}) = &self.dragging {
+        if let Some(DragOrigin {instr, ..}) = &self.dragging && instr.has_opcode(coding.supply[idx].op) {
             log::debug!("INFO: Dropping {:?} to supply bin", instr);
-            let bin = &mut coding.supply.get_mut(idx).unwrap();
-            if instr.has_opcode(bin.op) {
-                bin.curr_count += 1;
-                if let Instr::Parent(_, subprog) = &instr {
-                    for subnode in &mut subprog.instrs.clone() {
-                        let supply = &mut coding.supply;
-                        self.drop_node_to_supply(supply, subnode.clone());
-                    }
-                }
-                self.dragging = None;
-            }
+            Self::finish_drag(coding, self.dragging.take().unwrap(), DragEnd::ToSupply);
         }
     }
 
-    fn drop_node_to_supply(&mut self, supply: &mut Vec<Bin>, instr: Instr) {
-        log::debug!("INFO: Dropping {:?} to supply", instr);
-        for bin in &mut *supply {
-            if instr.has_opcode(bin.op) {
-                bin.curr_count += 1;
-                break;
-            }
+    fn drop_drag_to_supply(&mut self, coding: &mut Coding) {
+        if let Some(drag) = self.dragging.take() {
+            log::debug!("INFO: Dropping {:?} to supply", drag.instr);
+            Self::finish_drag(coding, drag, DragEnd::ToSupply);
         }
-        if let Instr::Parent(_, subprog) = &instr {
-            for instr in subprog.instrs.clone() {
-                self.drop_node_to_supply(supply, instr);
-            }
+    }
+
+    // Path is where instr goes in whole prog. Edit is made once done with prog this frame.
+    fn drop_to_prog(&mut self, path: InstrPath) {
+        if let Some(drag) = self.dragging.take() {
+            log::debug!("INFO: Dropping {:?} to prog", drag.instr);
+            self.dropped = Some((drag, path));
         }
     }
 
-    fn drop_drag_to_supply(&mut self, coding: &mut Coding) {
-        if let Some(DragOrigin {instr, ..}) = self.dragging.clone() {
-            self.drop_node_to_supply(&mut coding.supply, instr);
-            self.dragging = None;
+    /// Put back whatever the drag took out of the coding, then make the edit the drag amounts
+    /// to, so that it can be undone. From supply to prog inserts, within prog moves, and from
+    /// prog to supply removes. Anything else leaves the coding as it was before the drag.
+    fn finish_drag(coding: &mut Coding, drag: DragOrigin, end: DragEnd) {
+        let cmd = match (drag.op_ref, end) {
+            (InstrRef::Supply { idx }, end) => {
+                coding.supply[idx].curr_count += 1;
+                match end {
+                    DragEnd::ToProg(to) => Some(EditCmd::Insert { bin_idx: idx, to }),
+                    DragEnd::ToSupply | DragEnd::Cancel => None,
+                }
+            },
+            (InstrRef::Prog { .. }, end) => {
+                coding.prog.insert_at(&drag.from_path, drag.instr).expect("Dragged instr fits back where it came from");
+                match end {
+                    DragEnd::ToProg(to) => Some(EditCmd::Move { from: drag.from_path, to }),
+                    DragEnd::ToSupply => Some(EditCmd::Remove { from: drag.from_path }),
+                    DragEnd::Cancel => None,
+                }
+            },
+        };
+        if let Some(cmd) = cmd && let Err(msg) = coding.edit(cmd) {
+            log::warn!("Couldn't make edit from drag: {msg}");
         }
     }
+}
+
+// Index of supply bin chosen with number keys 1-9, if any.
+fn bin_key_pressed() -> Option<usize> {
+    use KeyCode::*;
+    [(Key1, Kp1), (Key2, Kp2), (Key3, Kp3), (Key4, Kp4), (Key5, Kp5), (Key6, Kp6), (Key7, Kp7), (Key8, Kp8), (Key9, Kp9)]
+        .into_iter()
+        .position(|(key, kp_key)| is_key_pressed(key) || is_key_pressed(kp_key))
+}
 
-    fn drop_to_prog(&mut self, prog: &mut Prog, idx: usize) {
-        if let Some(DragOrigin { instr, .. }) = &self.dragging {
-            log::debug!("INFO: Dropping {:?} to prog", instr);
-            prog.instrs.insert(idx, instr.clone());
-            self.dragging = None;
+// Split text into lines no wider than max_w, breaking between any chars.
+fn wrap_text(txt: &str, max_w: f32, font_sz: f32) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for ch in txt.chars() {
+        let line = lines.last_mut().unwrap();
+        line.push(ch);
+        if line.chars().count() > 1 && measure_text(line, None, font_sz as u16, 1.).width > max_w {
+            line.pop();
+            lines.push(ch.to_string());
         }
     }
+    lines
 }
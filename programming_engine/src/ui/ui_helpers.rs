@@ -48,6 +48,19 @@ pub fn was_key_pressed() -> Option<KeyType> {
     }
 }
 
+// Add chars typed since last frame to txt, and delete last char on Backspace.
+pub fn type_into(txt: &mut String) {
+    // Queue returns most recent char first.
+    let mut typed = vec![];
+    while let Some(ch) = get_char_pressed() {
+        typed.push(ch);
+    }
+    txt.extend(typed.into_iter().rev().filter(|ch| !ch.is_control()));
+    if is_key_pressed(KeyCode::Backspace) {
+        txt.pop();
+    }
+}
+
 pub fn was_any_input() -> bool {
     use KeyType::*;
     matches!(was_key_pressed(), Some(Ok | Normal | Escape)) || is_mouse_button_pressed(MouseButton::Left)
@@ -1,17 +1,44 @@
+use std::collections::HashSet;
+
 use macroquad::prelude::*;
 
 use crate::ui::PRect;
 use crate::game_data::{BaseGameData};
-use crate::savegame::BaseSaveGame;
+use crate::savegame::{BaseSaveGame, solution_len};
+use crate::progression::Progression;
+
+const H_STRIDE: f32 = 30.;
+const V_STRIDE: f32 = 30.;
+const CHAPTER_GAP: f32 = 12.;
+// Radius of level buttons.
+const R: f32 = 10.;
+const FONT_SZ: f32 = 16.;
 
 struct DragInfo {
     lev_idx: u16,
     mouse_down_time: f64,
 }
 
+// Where one level's button is drawn. Row counts from first row of all levels, not first row shown.
+#[derive(Clone, Copy)]
+struct LevButton {
+    lev_idx: u16,
+    chapter_idx: usize,
+    bonus: bool,
+    row: usize,
+    x: f32,
+    y: f32,
+}
+
 #[derive(Default)]
 pub struct LevChooser {
     drag_origin: Option<DragInfo>,
+    // Level picked out with arrow keys, while chooser has keyboard focus.
+    focus: Option<u16>,
+    // First row of levels shown, if there are more rows than fit.
+    first_row: usize,
+    // Current level when last scrolled to show it, to scroll again when it changes.
+    scrolled_to_level: u16,
 }
 
 #[derive(PartialEq)]
@@ -54,74 +81,317 @@ impl LevChooser {
         MouseOverCols {text: LIGHTGRAY, fill: DARKGRAY, border: BLACK, border_width}
     }
 
-    pub fn do_frame<GameData: BaseGameData>(&mut self, game_state: &mut GameData, coords: PRect) {
-            let n_levs = game_state.num_levels();
-            let buttons_per_row = 10;
+    /// Whether arrow keys and Enter are for choosing a level, rather than for the rest of the screen.
+    pub fn has_focus(&self) -> bool {
+        self.focus.is_some()
+    }
+
+    pub fn drop_focus(&mut self) {
+        self.focus = None;
+    }
+
+    // Lay out levels in progression order, wrapping at edge of area, with a gap between chapters.
+    // Leaves room at right for scroll arrows.
+    fn layout(progression: &Progression, coords: PRect, first_row: usize) -> Vec<LevButton> {
+        let init_x = coords.x + 25.;
+        let max_x = coords.x + coords.w - 25.;
+        let mut curr_x = init_x;
+        let mut row = 0;
+        let mut buttons: Vec<LevButton> = vec![];
+        for (chapter_idx, level) in progression.levels() {
+            if let Some(prev) = buttons.last() && prev.chapter_idx != chapter_idx {
+                curr_x += CHAPTER_GAP;
+            }
+            if curr_x > max_x {
+                curr_x = init_x;
+                row += 1;
+            }
+            let y = coords.y + 20. + (row as f32 - first_row as f32) * V_STRIDE;
+            buttons.push(LevButton { lev_idx: level.lev_idx, chapter_idx, bonus: level.bonus, row, x: curr_x, y });
+            curr_x += H_STRIDE;
+        }
+        buttons
+    }
+
+    fn rows_visible(coords: PRect) -> usize {
+        ((coords.h - 10.) / V_STRIDE).max(1.) as usize
+    }
+
+    fn scroll_arrows(coords: PRect) -> (PRect, PRect) {
+        let (w, h) = (14., 12.);
+        let x = coords.x + coords.w - w - 4.;
+        (PRect { x, y: coords.y + 6., w, h }, PRect { x, y: coords.y + coords.h - h - 6., w, h })
+    }
+
+    // Shaded band behind each row of each chapter's levels. Returns bands and their chapter.
+    fn chapter_bands(buttons: &[LevButton]) -> Vec<(usize, PRect)> {
+        let pad = 4.;
+        buttons.chunk_by(|a, b| a.chapter_idx == b.chapter_idx && a.row == b.row)
+            .map(|run| {
+                let (first, last) = (&run[0], &run[run.len() - 1]);
+                (first.chapter_idx, PRect { x: first.x - R - pad, y: first.y - R - pad, w: last.x - first.x + 2. * (R + pad), h: 2. * (R + pad) })
+            })
+            .collect()
+    }
+
+    // Line from level to level it unlocks. Links between neighbours are short connectors. Links which
+    // skip over levels in the same row go over the top so they don't cross other buttons.
+    fn draw_link(from: &LevButton, to: &LevButton, col: Color) {
+        if from.row == to.row && (to.x - from.x).abs() > H_STRIDE * 1.5 {
+            let top = from.y - R - 6.;
+            draw_line(from.x, from.y - R, from.x, top, 1., col);
+            draw_line(from.x, top, to.x, top, 1., col);
+            draw_line(to.x, top, to.x, to.y - R, 1., col);
+        } else {
+            draw_line(from.x, from.y, to.x, to.y, 1., col);
+        }
+    }
 
-            let approx_half_char_width = 4.;
-            let h_stride = 30.;
+    fn draw_scroll_arrow(rect: PRect, up: bool) {
+        let (tip_y, base_y) = if up {(rect.y, rect.y + rect.h)} else {(rect.y + rect.h, rect.y)};
+        let col = if rect.contains(mouse_position()) {ORANGE} else {DARKGRAY};
+        draw_triangle(vec2(rect.x + rect.w / 2., tip_y), vec2(rect.x, base_y), vec2(rect.x + rect.w, base_y), col);
+    }
+
+    // Take or give back keyboard focus on: Tab. Also give back on: Escape.
+    // Move focus on: arrow keys, PageUp/PageDown. Go to focused level on: Enter/Space.
+    fn interact_keys<GameData: BaseGameData>(&mut self, game_state: &mut GameData, buttons: &[LevButton], unlocked: &HashSet<u16>, rows_visible: usize) {
+        if is_key_pressed(KeyCode::Tab) {
+            self.focus = match self.focus {
+                Some(_) => None,
+                None => Some(game_state.get_current_level()),
+            };
+            return;
+        }
+        let Some(focus) = self.focus else {
+            return;
+        };
+        let Some(pos) = buttons.iter().position(|button| button.lev_idx == focus) else {
+            self.focus = None;
+            return;
+        };
+        let row_step = if is_key_pressed(KeyCode::Up) {
+            Some(-1)
+        } else if is_key_pressed(KeyCode::Down) {
+            Some(1)
+        } else if is_key_pressed(KeyCode::PageUp) {
+            Some(-(rows_visible as isize))
+        } else if is_key_pressed(KeyCode::PageDown) {
+            Some(rows_visible as isize)
+        } else {
+            None
+        };
 
-            let txt_below_of_centre = 5.;
-            let r = 10.;
-            let v_stride = 30.;
+        if is_key_pressed(KeyCode::Escape) {
+            self.focus = None;
+        } else if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) || is_key_pressed(KeyCode::Space) {
+            if unlocked.contains(&focus) {
+                game_state.goto_level(focus);
+                self.focus = None;
+            }
+        } else if is_key_pressed(KeyCode::Left) {
+            self.focus = Some(buttons[pos.saturating_sub(1)].lev_idx);
+        } else if is_key_pressed(KeyCode::Right) {
+            self.focus = Some(buttons[(pos + 1).min(buttons.len() - 1)].lev_idx);
+        } else if let Some(row_step) = row_step {
+            let last_row = buttons.last().map_or(0, |button| button.row);
+            let row = buttons[pos].row.saturating_add_signed(row_step).min(last_row);
+            // Level in that row nearest to the one focused.
+            let dist = |button: &&LevButton| (button.x - buttons[pos].x).abs();
+            self.focus = buttons.iter().filter(|button| button.row == row)
+                .min_by(|a, b| dist(a).total_cmp(&dist(b)))
+                .map(|button| button.lev_idx);
+        }
+    }
 
-            let hold_for = 0.1;
+    // Scroll on: mouse wheel over chooser, or click on arrows. Also scroll to show focused
+    // level, and current level when it changes.
+    fn interact_scroll(&mut self, buttons: &[LevButton], coords: PRect, rows_visible: usize, current_level: u16) {
+        let n_rows = buttons.last().map_or(0, |button| button.row + 1);
+        let row_of = |lev_idx| buttons.iter().find(|button| button.lev_idx == lev_idx).map(|button| button.row);
+        let showing_row = |first_row: usize, row: usize| first_row.clamp((row + 1).saturating_sub(rows_visible), row);
 
-            if !is_mouse_button_down(MouseButton::Left) {
-                self.drag_origin = None;
+        if current_level != self.scrolled_to_level {
+            self.scrolled_to_level = current_level;
+            if let Some(row) = row_of(current_level) {
+                self.first_row = showing_row(self.first_row, row);
             }
+        }
+        if let Some(row) = self.focus.and_then(row_of) {
+            self.first_row = showing_row(self.first_row, row);
+        }
 
-            let init_x = coords.x + 25.;
-            let mut curr_x = init_x;
-            let init_y = coords.y + 20.;
-            for lev_idx in 1..=n_levs {
-                if lev_idx % buttons_per_row == 1 {
-                    curr_x = init_x;
-                } else {
-                    curr_x += h_stride;
-                }
-                let curr_y = init_y + ((lev_idx-1) / 10) as f32 * v_stride;
-
-                let rect = PRect { x: curr_x - r, y: curr_y - r, w: r * 2., h: r * 2.};
-                let mouse_in = rect.contains(mouse_position());
-
-                if mouse_in && is_mouse_button_down(MouseButton::Left) && game_state.save_game().get_unlocked_levels().contains(&lev_idx) {
-                    if let Some(drag_info) = &mut self.drag_origin {
-                        if drag_info.lev_idx == lev_idx && get_time() > drag_info.mouse_down_time + hold_for {
-                            game_state.goto_level(lev_idx);
-                            // Invalidate drag until mouse released
-                            drag_info.lev_idx = 0;
-                        }
-                    } else {
-                        self.drag_origin = Some(DragInfo { lev_idx, mouse_down_time: get_time() });
-                    }
-                }
+        let (up_arrow, down_arrow) = Self::scroll_arrows(coords);
+        let clicked = |rect: PRect| is_mouse_button_pressed(MouseButton::Left) && rect.contains(mouse_position());
+        let wheel = if coords.contains(mouse_position()) {mouse_wheel().1} else {0.};
+        if wheel > 0. || clicked(up_arrow) {
+            self.first_row = self.first_row.saturating_sub(1);
+        } else if wheel < 0. || clicked(down_arrow) {
+            self.first_row += 1;
+        }
+        self.first_row = self.first_row.min(n_rows.saturating_sub(rows_visible));
+    }
+
+    // Level name and chapter, and whether and how well it's been solved.
+    fn tooltip_lines<GameData: BaseGameData>(game_state: &mut GameData, progression: &Progression, button: &LevButton, unlocked: &HashSet<u16>) -> Vec<String> {
+        let lev_idx = button.lev_idx;
+        let name = match game_state.level_title(lev_idx) {
+            Some(title) => format!("Level {lev_idx}: {title}"),
+            None => format!("Level {lev_idx}"),
+        };
+        let chapter = &progression.chapters[button.chapter_idx].title;
+        let mut lines = vec![if chapter.is_empty() {name} else {format!("{name} ({chapter})")}];
+        lines.push(if !unlocked.contains(&lev_idx) {
+            "Locked".to_string()
+        } else if let Some(best) = game_state.save_game().get_best_prog(lev_idx) {
+            format!("Solved. Best: {} instrs", solution_len(&best))
+        } else {
+            "Not solved yet".to_string()
+        });
+        if button.bonus {
+            lines.push("Bonus level".to_string());
+        }
+        lines
+    }
+
+    // Box under level button, moved left if needed to fit on screen.
+    fn draw_tooltip(lines: &[String], button: &LevButton) {
+        let line_h = 18.;
+        let w = lines.iter().map(|line| measure_text(line, None, FONT_SZ as u16, 1.0).width).fold(0., f32::max) + 12.;
+        let h = lines.len() as f32 * line_h + 6.;
+        let x = (button.x - R).min(screen_width() - w - 2.);
+        let y = button.y + R + 6.;
+        draw_rectangle(x, y, w, h, Color::new(1., 1., 0.9, 1.));
+        draw_rectangle_lines(x, y, w, h, 1., DARKGRAY);
+        for (idx, line) in lines.iter().enumerate() {
+            draw_text(line, x + 6., y + (idx + 1) as f32 * line_h, FONT_SZ, DARKGRAY);
+        }
+    }
+
+    /// Draw and interact with level chooser. Keys are left alone unless `accept_keys`, e.g. while typing elsewhere.
+    pub fn do_frame<GameData: BaseGameData>(&mut self, game_state: &mut GameData, coords: PRect, accept_keys: bool) {
+        let progression = game_state.progression();
+        let unlocked = game_state.save_game().get_unlocked_levels();
+        let rows_visible = Self::rows_visible(coords);
+
+        let unscrolled = Self::layout(&progression, coords, self.first_row);
+        if accept_keys {
+            self.interact_keys(game_state, &unscrolled, &unlocked, rows_visible);
+        }
+        self.interact_scroll(&unscrolled, coords, rows_visible, game_state.get_current_level());
+
+        let buttons = Self::layout(&progression, coords, self.first_row);
+        let shown_rows = self.first_row..self.first_row + rows_visible;
+        let shown: Vec<LevButton> = buttons.iter().copied().filter(|button| shown_rows.contains(&button.row)).collect();
+        let n_rows = buttons.last().map_or(0, |button| button.row + 1);
+
+        let approx_half_char_width = 4.;
+
+        let txt_below_of_centre = 5.;
+
+        let hold_for = 0.1;
+
+        if !is_mouse_button_down(MouseButton::Left) {
+            self.drag_origin = None;
+        }
+
+        for (chapter_idx, band) in Self::chapter_bands(&shown) {
+            let shade = if chapter_idx % 2 == 0 {0.85} else {0.78};
+            draw_rectangle(band.x, band.y, band.w, band.h, Color::new(shade, shade, shade, 1.));
+        }
+
+        for (from, to) in progression.links() {
+            let find = |lev_idx| shown.iter().find(|button| button.lev_idx == lev_idx);
+            if let (Some(from), Some(to)) = (find(from), find(to)) {
+                let col = if unlocked.contains(&to.lev_idx) {DARKGRAY} else {GRAY};
+                Self::draw_link(from, to, col);
+            }
+        }
 
-                let mouse_over_state = if let Some(drag_info) = &mut self.drag_origin && drag_info.lev_idx == lev_idx {
-                    if mouse_in {
-                        MouseOverState::PressedOn
-                    } else {
-                        MouseOverState::PressedOff
+        let mut hovered = None;
+        for button in &shown {
+            let (lev_idx, curr_x, curr_y) = (button.lev_idx, button.x, button.y);
+
+            let rect = PRect { x: curr_x - R, y: curr_y - R, w: R * 2., h: R * 2.};
+            let mouse_in = rect.contains(mouse_position());
+
+            if mouse_in && is_mouse_button_down(MouseButton::Left) && unlocked.contains(&lev_idx) {
+                if let Some(drag_info) = &mut self.drag_origin {
+                    if drag_info.lev_idx == lev_idx && get_time() > drag_info.mouse_down_time + hold_for {
+                        game_state.goto_level(lev_idx);
+                        // Invalidate drag until mouse released
+                        drag_info.lev_idx = 0;
                     }
-                } else if self.drag_origin.is_none() && mouse_in {
-                    MouseOverState::Over
-                } else {
-                    MouseOverState::Neutral
-                };
-                let cols = if lev_idx == game_state.get_current_level() {
-                    Self::col_active(mouse_over_state)
-                } else if game_state.save_game().get_unlocked_levels().contains(&lev_idx) {
-                    Self::col_unlocked(mouse_over_state)
                 } else {
-                    Self::col_locked(mouse_over_state)
-                };
+                    self.drag_origin = Some(DragInfo { lev_idx, mouse_down_time: get_time() });
+                }
+            }
 
-                draw_circle(curr_x, curr_y, r, cols.fill);
-                draw_circle_lines(curr_x, curr_y, r, cols.border_width, cols.border);
+            let mouse_over_state = if let Some(drag_info) = &mut self.drag_origin && drag_info.lev_idx == lev_idx {
+                if mouse_in {
+                    MouseOverState::PressedOn
+                } else {
+                    MouseOverState::PressedOff
+                }
+            } else if self.drag_origin.is_none() && mouse_in {
+                hovered = Some(*button);
+                MouseOverState::Over
+            } else {
+                MouseOverState::Neutral
+            };
+            let cols = if lev_idx == game_state.get_current_level() {
+                Self::col_active(mouse_over_state)
+            } else if unlocked.contains(&lev_idx) {
+                Self::col_unlocked(mouse_over_state)
+            } else {
+                Self::col_locked(mouse_over_state)
+            };
 
-                let digits = if lev_idx < 10 {1.} else {2.};
-                let (text_x, text_y) = (curr_x - digits*approx_half_char_width, curr_y + txt_below_of_centre);
-                draw_text(format!("{lev_idx}").as_str(), text_x, text_y, 20., cols.text);
+            draw_circle(curr_x, curr_y, R, cols.fill);
+            draw_circle_lines(curr_x, curr_y, R, cols.border_width, cols.border);
+            if button.bonus {
+                // Bonus levels are optional.
+                draw_circle_lines(curr_x, curr_y, R + 2., 1., GOLD);
+            }
+            if self.focus == Some(lev_idx) {
+                draw_circle_lines(curr_x, curr_y, R + 4., 2., DARKBLUE);
             }
+            if unlocked.contains(&lev_idx) && game_state.save_game().get_best_prog(lev_idx).is_some() {
+                // Solved, not just unlocked.
+                draw_circle(curr_x + R * 0.75, curr_y - R * 0.75, 4., DARKGREEN);
+            }
+
+            let digits = if lev_idx < 10 {1.} else {2.};
+            let (text_x, text_y) = (curr_x - digits*approx_half_char_width, curr_y + txt_below_of_centre);
+            draw_text(format!("{lev_idx}").as_str(), text_x, text_y, 20., cols.text);
         }
+
+        let (up_arrow, down_arrow) = Self::scroll_arrows(coords);
+        if self.first_row > 0 {
+            Self::draw_scroll_arrow(up_arrow, true);
+        }
+        if self.first_row + rows_visible < n_rows {
+            Self::draw_scroll_arrow(down_arrow, false);
+        }
+
+        // Keys while focused, else title of chapter under mouse.
+        let hovered_band = Self::chapter_bands(&shown).into_iter().find(|(_, band)| band.contains(mouse_position()));
+        if self.focus.is_some() {
+            draw_text("Arrows: choose. Enter: go. Tab: done.", coords.x + 8., coords.y + coords.h - 6., FONT_SZ, DARKBLUE);
+        } else if let Some((chapter_idx, _)) = hovered_band {
+            draw_text(&progression.chapters[chapter_idx].title, coords.x + 8., coords.y + coords.h - 6., FONT_SZ, DARKGRAY);
+        }
+
+        // Unlocks shown are this profile's.
+        if let Some(profile) = game_state.save_game().current_profile() {
+            let text_w = measure_text(&profile, None, FONT_SZ as u16, 1.0).width;
+            draw_text(&profile, coords.x + coords.w - text_w - 26., coords.y + coords.h - 6., FONT_SZ, DARKGRAY);
+        }
+
+        let focused = shown.iter().find(|button| self.focus == Some(button.lev_idx)).copied();
+        if let Some(button) = hovered.or(focused) {
+            let lines = Self::tooltip_lines(game_state, &progression, &button, &unlocked);
+            Self::draw_tooltip(&lines, &button);
+        }
+    }
 }
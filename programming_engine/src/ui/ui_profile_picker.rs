@@ -0,0 +1,161 @@
+use macroquad::prelude::*;
+
+use super::ui_helpers::*;
+use crate::game_data::BaseGameData;
+use crate::savegame::BaseSaveGame;
+use crate::scene::{BaseScene, ProfileEdit, ProfilePicker};
+
+// Render and interact with profile list. Profiles are read from the save game each frame.
+pub struct UiProfilePicker {
+}
+
+const ROW_H: f32 = 40.;
+const FONT_SZ: f32 = 30.;
+
+impl UiProfilePicker
+{
+    pub fn do_frame<GameData: BaseGameData>(scene: &mut ProfilePicker, game_state: &mut GameData) {
+        let profiles = game_state.save_game().profiles();
+        scene.selected_idx = scene.selected_idx.min(profiles.len().saturating_sub(1));
+
+        if scene.edit.is_some() {
+            Self::interact_edit(scene, game_state);
+        } else {
+            Self::interact_list(scene, game_state, &profiles);
+        }
+
+        Self::draw(scene, &game_state.save_game().profiles());
+    }
+
+    fn list_x() -> f32 {
+        screen_width() * 0.25
+    }
+
+    fn row_rect(idx: usize) -> PRect {
+        PRect { x: Self::list_x(), y: 100. + idx as f32 * ROW_H, w: screen_width() * 0.5, h: ROW_H }
+    }
+
+    // Buttons below list, also available by key.
+    fn buttons(n_profiles: usize) -> Vec<(&'static str, PRect)> {
+        let y = Self::row_rect(n_profiles).y + 80.;
+        ["Play", "New", "Rename", "Delete"].into_iter().enumerate()
+            .map(|(idx, label)| (label, PRect { x: Self::list_x() + idx as f32 * 120., y, w: 110., h: ROW_H }))
+            .collect()
+    }
+
+    // Choose on: Up/Down keys, or click. Play on: Enter/Space, or click on chosen profile.
+    // New on: N. Rename on: R. Delete on: Delete key. Or click on buttons.
+    fn interact_list<GameData: BaseGameData>(scene: &mut ProfilePicker, game_state: &mut GameData, profiles: &[String]) {
+        let clicked = |rect: PRect| is_mouse_button_pressed(MouseButton::Left) && rect.contains(mouse_position());
+        let clicked_button = |label: &str| Self::buttons(profiles.len()).iter().any(|(button, rect)| *button == label && clicked(*rect));
+        let Some(selected) = profiles.get(scene.selected_idx).cloned() else {
+            return;
+        };
+
+        if is_key_pressed(KeyCode::Up) {
+            scene.selected_idx = scene.selected_idx.saturating_sub(1);
+        } else if is_key_pressed(KeyCode::Down) {
+            scene.selected_idx = (scene.selected_idx + 1).min(profiles.len() - 1);
+        } else if is_key_pressed(KeyCode::N) || clicked_button("New") {
+            Self::start_edit(scene, ProfileEdit::New { name: String::new() });
+        } else if is_key_pressed(KeyCode::R) || clicked_button("Rename") {
+            Self::start_edit(scene, ProfileEdit::Rename { old_name: selected.clone(), new_name: selected });
+        } else if is_key_pressed(KeyCode::Delete) || clicked_button("Delete") {
+            Self::start_edit(scene, ProfileEdit::ConfirmDelete { name: selected });
+        } else if matches!(was_key_pressed(), Some(KeyType::Ok)) || clicked_button("Play") || clicked(Self::row_rect(scene.selected_idx)) {
+            match game_state.save_game().select_profile(&selected) {
+                Ok(()) => scene.advance(InputCmd::Continue),
+                Err(msg) => scene.message = Some(msg),
+            }
+        } else if let Some(idx) = (0..profiles.len()).find(|idx| clicked(Self::row_rect(*idx))) {
+            scene.selected_idx = idx;
+        }
+    }
+
+    fn start_edit(scene: &mut ProfilePicker, edit: ProfileEdit) {
+        // Ignore whatever was typed before, including the key which started editing.
+        clear_input_queue();
+        scene.message = None;
+        scene.edit = Some(edit);
+    }
+
+    // Type name and confirm on: Enter. Cancel on: Escape.
+    fn interact_edit<GameData: BaseGameData>(scene: &mut ProfilePicker, game_state: &mut GameData) {
+        let save_game = game_state.save_game();
+        let confirm = is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter);
+        if is_key_pressed(KeyCode::Escape) {
+            scene.edit = None;
+            return;
+        }
+
+        let result = match scene.edit.as_mut().unwrap() {
+            ProfileEdit::New { name } | ProfileEdit::Rename { new_name: name, .. } => {
+                type_into(name);
+                if !confirm {
+                    return;
+                }
+                match scene.edit.clone().unwrap() {
+                    ProfileEdit::New { name } => save_game.create_profile(&name)
+                        .map(|()| save_game.profiles().len() - 1),
+                    ProfileEdit::Rename { old_name, new_name } => save_game.rename_profile(&old_name, &new_name)
+                        .map(|()| scene.selected_idx),
+                    ProfileEdit::ConfirmDelete { .. } => unreachable!(),
+                }
+            },
+            ProfileEdit::ConfirmDelete { name } => {
+                if !(confirm || is_key_pressed(KeyCode::Y)) {
+                    return;
+                }
+                save_game.delete_profile(name).map(|()| 0)
+            },
+        };
+        match result {
+            Ok(selected_idx) => {
+                scene.selected_idx = selected_idx;
+                scene.edit = None;
+                scene.message = None;
+            },
+            Err(msg) => scene.message = Some(msg),
+        }
+    }
+
+    fn draw(scene: &ProfilePicker, profiles: &[String]) {
+        clear_background_for_current_platform(WHITE);
+        draw_text("Who's playing?", Self::list_x(), 60., 40., DARKGRAY);
+
+        for (idx, profile) in profiles.iter().enumerate() {
+            let rect = Self::row_rect(idx);
+            let renaming = matches!(&scene.edit, Some(ProfileEdit::Rename { old_name, .. }) if old_name == profile);
+            if idx == scene.selected_idx {
+                draw_rectangle(rect.x, rect.y, rect.w, rect.h, YELLOW);
+            }
+            draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 1., DARKGRAY);
+            let txt = match &scene.edit {
+                Some(ProfileEdit::Rename { new_name, .. }) if renaming => format!("{new_name}_"),
+                _ => profile.clone(),
+            };
+            draw_text(&txt, rect.x + 10., rect.y + 28., FONT_SZ, DARKGRAY);
+        }
+
+        let prompt_y = Self::row_rect(profiles.len()).y + 30.;
+        let prompt = match &scene.edit {
+            None => "Enter to play. N: new, R: rename, Delete: delete.".to_string(),
+            Some(ProfileEdit::New { name }) => format!("New profile: {name}_"),
+            Some(ProfileEdit::Rename { .. }) => "Type new name. Enter to confirm, Escape to cancel.".to_string(),
+            Some(ProfileEdit::ConfirmDelete { name }) => format!("Delete {name} and all their progress? Enter to confirm, Escape to cancel."),
+        };
+        draw_text(&prompt, Self::list_x(), prompt_y, 24., DARKGRAY);
+        if let Some(msg) = &scene.message {
+            draw_text(msg, Self::list_x(), prompt_y + 30., 24., RED);
+        }
+
+        if scene.edit.is_none() {
+            for (label, rect) in Self::buttons(profiles.len()) {
+                let fill = if rect.contains(mouse_position()) {YELLOW} else {WHITE};
+                draw_rectangle(rect.x, rect.y, rect.w, rect.h, fill);
+                draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2., DARKGRAY);
+                draw_text(label, rect.x + 10., rect.y + 28., FONT_SZ, DARKGRAY);
+            }
+        }
+    }
+}
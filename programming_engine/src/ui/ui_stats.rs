@@ -0,0 +1,165 @@
+use macroquad::prelude::*;
+
+use super::ui_helpers::*;
+use crate::game_data::BaseGameData;
+use crate::savegame::{BaseSaveGame, LevelRecord};
+use crate::scene::{BaseScene, Stats};
+
+// Render and interact with stats and history for one level. Record is read from save game each frame.
+pub struct UiStats {
+}
+
+const ROW_H: f32 = 26.;
+const MARGIN: f32 = 40.;
+const CHART_Y: f32 = 220.;
+const CHART_H: f32 = 100.;
+const HISTORY_Y: f32 = CHART_Y + CHART_H + 50.;
+
+impl UiStats
+{
+    pub fn do_frame<GameData: BaseGameData>(scene: &mut Stats, game_state: &mut GameData) {
+        let num_levels = game_state.num_levels().max(1);
+        let record = game_state.save_game().get_level_record(scene.lev_idx).unwrap_or_default();
+        scene.selected_idx = scene.selected_idx.min(record.outcomes.len().saturating_sub(1));
+
+        Self::interact(scene, &record, num_levels);
+        Self::draw(scene, &record);
+    }
+
+    fn buttons() -> Vec<(&'static str, PRect)> {
+        ["< Prev", "Next >", "Load", "Replay", "Back"].into_iter().enumerate()
+            .map(|(idx, label)| (label, PRect { x: MARGIN + idx as f32 * 110., y: screen_height() - 50., w: 100., h: 36. }))
+            .collect()
+    }
+
+    fn history_rows_visible() -> usize {
+        ((screen_height() - HISTORY_Y - 60.) / ROW_H).max(1.) as usize
+    }
+
+    // First row of history shown, so that selected row is visible.
+    fn first_history_row(selected_idx: usize) -> usize {
+        (selected_idx + 1).saturating_sub(Self::history_rows_visible())
+    }
+
+    fn history_row_rect(row: usize) -> PRect {
+        PRect { x: MARGIN, y: HISTORY_Y + row as f32 * ROW_H, w: screen_width() - 2. * MARGIN, h: ROW_H }
+    }
+
+    // Back on: Escape. Change level on: Left/Right. Choose attempt on: Up/Down or click.
+    // Load chosen attempt's program into editor on: Enter/Space. Replay it on: R. Or click on buttons.
+    fn interact(scene: &mut Stats, record: &LevelRecord, num_levels: u16) {
+        let clicked = |rect: PRect| is_mouse_button_pressed(MouseButton::Left) && rect.contains(mouse_position());
+        let clicked_button = |label: &str| Self::buttons().iter().any(|(button, rect)| *button == label && clicked(*rect));
+        let key = was_key_pressed();
+
+        if matches!(key, Some(KeyType::Escape)) || clicked_button("Back") {
+            scene.advance(InputCmd::Cancel);
+        } else if is_key_pressed(KeyCode::Left) || clicked_button("< Prev") {
+            scene.lev_idx = (scene.lev_idx - 1).max(1);
+            scene.selected_idx = usize::MAX;
+        } else if is_key_pressed(KeyCode::Right) || clicked_button("Next >") {
+            scene.lev_idx = (scene.lev_idx + 1).min(num_levels);
+            scene.selected_idx = usize::MAX;
+        } else if is_key_pressed(KeyCode::Up) {
+            scene.selected_idx = scene.selected_idx.saturating_sub(1);
+        } else if is_key_pressed(KeyCode::Down) {
+            scene.selected_idx += 1;
+        } else if matches!(key, Some(KeyType::Ok)) || clicked_button("Load") {
+            if let Some(outcome) = record.outcomes.get(scene.selected_idx) {
+                scene.choose_prog(outcome.solution.clone());
+            }
+        } else if is_key_pressed(KeyCode::R) || clicked_button("Replay") {
+            if let Some(outcome) = record.outcomes.get(scene.selected_idx) {
+                scene.request_replay(outcome.solution.clone());
+            }
+        } else {
+            let first_row = Self::first_history_row(scene.selected_idx);
+            let n_rows = Self::history_rows_visible().min(record.outcomes.len() - first_row.min(record.outcomes.len()));
+            if let Some(row) = (0..n_rows).find(|row| clicked(Self::history_row_rect(*row))) {
+                scene.selected_idx = first_row + row;
+            }
+        }
+    }
+
+    fn draw(scene: &Stats, record: &LevelRecord) {
+        clear_background_for_current_platform(WHITE);
+        let stats = record.stats();
+
+        draw_text(&format!("Level {}: stats and history", scene.lev_idx), MARGIN, 50., 36., DARKGRAY);
+        draw_text("Left/Right: level. Up/Down: choose attempt. Enter: load its program. R: replay it. Escape: back.", MARGIN, 80., 20., GRAY);
+
+        let win_ratio = stats.win_ratio().map_or("-".to_string(), |ratio| format!("{:.0}%", ratio * 100.));
+        let summary = format!("Attempts: {}   Won: {}   Failed: {}   Win ratio: {win_ratio}", stats.attempts, stats.wins, stats.fails);
+        draw_text(&summary, MARGIN, 120., 26., DARKGRAY);
+        let first_solve = stats.secs_to_first_solve.map_or("Not solved yet".to_string(), |secs| format!("Time to first solve: {}", format_duration(secs)));
+        let best = record.best_solution.as_deref().map_or(String::new(), |best| format!("   Best: {best}"));
+        draw_text(&format!("{first_solve}{best}"), MARGIN, 150., 26., DARKGRAY);
+
+        if let Some(msg) = &scene.message {
+            draw_text(msg, MARGIN, CHART_Y - 40., 22., RED);
+        }
+
+        Self::draw_lens_chart(&stats.solution_lens, scene.selected_idx);
+        Self::draw_history(record, scene.selected_idx);
+
+        for (label, rect) in Self::buttons() {
+            let fill = if rect.contains(mouse_position()) {YELLOW} else {WHITE};
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, fill);
+            draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2., DARKGRAY);
+            draw_text(label, rect.x + 10., rect.y + 25., 24., DARKGRAY);
+        }
+    }
+
+    // Bar per attempt, height by program length, coloured by outcome.
+    fn draw_lens_chart(solution_lens: &[(String, usize)], selected_idx: usize) {
+        draw_text("Program length per attempt", MARGIN, CHART_Y - 10., 20., GRAY);
+        draw_line(MARGIN, CHART_Y + CHART_H, screen_width() - MARGIN, CHART_Y + CHART_H, 1., DARKGRAY);
+        let Some(max_len) = solution_lens.iter().map(|(_, len)| *len).max() else {
+            return;
+        };
+        let stride = ((screen_width() - 2. * MARGIN) / solution_lens.len() as f32).min(24.);
+        for (idx, (outcome, len)) in solution_lens.iter().enumerate() {
+            let h = CHART_H * (*len).max(1) as f32 / max_len.max(1) as f32;
+            let x = MARGIN + idx as f32 * stride;
+            draw_rectangle(x, CHART_Y + CHART_H - h, stride * 0.8, h, outcome_col(outcome));
+            if idx == selected_idx {
+                draw_rectangle_lines(x, CHART_Y + CHART_H - h, stride * 0.8, h, 2., DARKGRAY);
+            }
+        }
+    }
+
+    fn draw_history(record: &LevelRecord, selected_idx: usize) {
+        if record.outcomes.is_empty() {
+            draw_text("No attempts yet.", MARGIN, HISTORY_Y + 20., 24., DARKGRAY);
+            return;
+        }
+        let first_row = Self::first_history_row(selected_idx);
+        for (row, outcome) in record.outcomes.iter().skip(first_row).take(Self::history_rows_visible()).enumerate() {
+            let rect = Self::history_row_rect(row);
+            if first_row + row == selected_idx {
+                draw_rectangle(rect.x, rect.y, rect.w, rect.h, YELLOW);
+            }
+            let repeats = if outcome.repeats > 0 {format!(" x{}", outcome.repeats + 1)} else {String::new()};
+            draw_text(&outcome.datetime, rect.x + 5., rect.y + 19., 20., GRAY);
+            draw_text(&format!("{}{repeats}", outcome.outcome), rect.x + 230., rect.y + 19., 20., outcome_col(&outcome.outcome));
+            draw_text(&outcome.solution, rect.x + 310., rect.y + 19., 20., DARKGRAY);
+        }
+    }
+}
+
+// Outcome as recorded by CodingArena.
+fn outcome_col(outcome: &str) -> Color {
+    match outcome {
+        "Won" => DARKGREEN,
+        "Die" => RED,
+        _ => GRAY,
+    }
+}
+
+fn format_duration(secs: i64) -> String {
+    match secs {
+        ..60 => format!("{secs}s"),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
@@ -1,7 +1,5 @@
 mod pushpuzz;
 
-// TODO: Reinstate tests once compile errors from during prog puzz development are fixed.
-#[cfg(any())]
 #[cfg(test)]
 mod push_puzz_tests;
 
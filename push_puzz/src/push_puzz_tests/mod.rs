@@ -17,11 +17,14 @@ mod basic_tests {
         let test_key = HashMap::from([
             (' ', vec![ new_floor() ]),
             ('#', vec![ new_floor(), new_wall() ]),
-            ('>', vec![ new_floor(), new_fish(CoordDelta::from_xy(1,0)) ]),
-            ('<', vec![ new_floor(), new_fish(CoordDelta::from_xy(-1,0)) ]),
-            ('g', vec![ new_floor(), new_gawpie(CoordDelta::from_xy(1,0)) ]),
-            ('G', vec![ new_floor(), new_gawpie(CoordDelta::from_xy(-1,0)) ]),
+            ('>', vec![ new_floor(), new_fish(Facing::E) ]),
+            ('<', vec![ new_floor(), new_fish(Facing::W) ]),
+            ('g', vec![ new_floor(), new_gawpie(Facing::E) ]),
+            ('G', vec![ new_floor(), new_gawpie(Facing::W) ]),
+            ('s', vec![ new_floor(), new_scuttler(Facing::N) ]),
+            ('S', vec![ new_floor(), new_scuttler(Facing::E) ]),
             ('h', vec![ new_floor(), new_hero_crab() ]),
+            ('b', vec![ new_floor(), new_crate() ]),
             ('o', vec![ new_door_win() ]),
             ('@', vec![ new_floor(), new_door_closed() ]),
             ('_', vec![ new_floor(), new_door_open() ]),
@@ -44,6 +47,32 @@ mod basic_tests {
                 "h   #",
             ], test_key
             ),
+            3=> Scene::from_play_ascii_map(&[
+                "   ",
+                "h  ",
+                "   ",
+            ], test_key
+            ),
+            4=> Scene::from_play_ascii_map(&[
+                "#g  #",
+                "##  #",
+                "h   #",
+            ], test_key
+            ),
+            5=> Scene::from_play_ascii_map(&[
+                "#######",
+                "#h    #",
+                "#### ##",
+                "#s    #",
+                "#######",
+            ], test_key
+            ),
+            6=> Scene::from_play_ascii_map(&[
+                "######",
+                "#hb  #",
+                "######",
+            ], test_key
+            ),
             _ => panic!(),
         }
     }
@@ -57,69 +86,148 @@ mod basic_tests {
     #[test]
     fn basic_bounce() {
         let mut state = get_lev(1);
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#   >        @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#    >       @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#     >      @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#      >     @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#       >    @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#        >   @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#         >  @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#          > @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#           >@ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#          < @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#         <  @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#        <   @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#       <    @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#      <     @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#     <      @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#    <       @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#   <        @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#  <         @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "# <          @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#<           @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "# >          @ @");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[2], "#  >         @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#   >        @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#    >       @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#     >      @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#      >     @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#       >    @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#        >   @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#         >  @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#          > @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#           >@ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#          < @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#         <  @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#        <   @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#       <    @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#      <     @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#     <      @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#    <       @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#   <        @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#  <         @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "# <          @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#<           @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "# >          @ @");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[2], "#  >         @ @");
     }
 
     #[test]
     fn basic_drift() {
         // TODO: Test rotated version of map somehow
         let mut state = get_lev(2);
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[0], "# g #"); assert_eq!(&state.as_ascii_rows()[1], "#   #");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[0], "#  g#"); assert_eq!(&state.as_ascii_rows()[1], "#   #");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[0], "#   #"); assert_eq!(&state.as_ascii_rows()[1], "# G #");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[0], "#   #"); assert_eq!(&state.as_ascii_rows()[1], "#G  #");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[0], "#   #"); assert_eq!(&state.as_ascii_rows()[1], "# g #");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[0], "# g #"); assert_eq!(&state.as_ascii_rows()[1], "#   #");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[0], "#  g#"); assert_eq!(&state.as_ascii_rows()[1], "#   #");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[0], "#   #"); assert_eq!(&state.as_ascii_rows()[1], "# G #");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[0], "#   #"); assert_eq!(&state.as_ascii_rows()[1], "#G  #");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[0], "#   #"); assert_eq!(&state.as_ascii_rows()[1], "# g #");
+    }
+
+    #[test]
+    fn drift_needs_line_of_sight() {
+        // Same as basic_drift, but wall hides hero, so gawpie turns back without drifting sideways.
+        let mut state = get_lev(4);
+        let _ = state.advance(MoveCmd::Stay);
+        let _ = state.advance(MoveCmd::Stay);
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[0], "# G #"); assert_eq!(&state.as_ascii_rows()[1], "##  #");
+    }
+
+    #[test]
+    fn scuttle_follows_path() {
+        // Wall is between scuttler and hero. Neither direction towards hero is open, so it
+        // takes the first step of the route round.
+        let mut state = get_lev(5);
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[3], "# S   #");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[3], "#  S  #");
     }
 
     #[test]
     fn basic_move() {
         let mut state = get_lev(1);
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[4], "#       h    # #");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[4], "#       h    # #");
-        let _ = state.advance(Some(MoveCmd::Right)); assert_eq!(&state.as_ascii_rows()[4], "#        h   # #");
-        let _ = state.advance(Some(MoveCmd::Right)); assert_eq!(&state.as_ascii_rows()[4], "#         h  # #");
-        let _ = state.advance(Some(MoveCmd::Left)); assert_eq!(&state.as_ascii_rows()[4], "#        h   # #");
-        let _ = state.advance(Some(MoveCmd::Left)); assert_eq!(&state.as_ascii_rows()[4], "#       h    # #");
-        let _ = state.advance(Some(MoveCmd::Right)); assert_eq!(&state.as_ascii_rows()[4], "#        h   # #");
-        let _ = state.advance(Some(MoveCmd::Stay)); assert_eq!(&state.as_ascii_rows()[4], "#        h   # #");
-        let _ = state.advance(Some(MoveCmd::Right)); assert_eq!(&state.as_ascii_rows()[4], "#         h  # #");
-        let _ = state.advance(Some(MoveCmd::Right)); assert_eq!(&state.as_ascii_rows()[4], "#          h # #");
-        let _ = state.advance(Some(MoveCmd::Right)); assert_eq!(&state.as_ascii_rows()[4], "#           h# #");
-        let _ = state.advance(Some(MoveCmd::Right)); assert_eq!(&state.as_ascii_rows()[4], "#           h# #");
-        let _ = state.advance(Some(MoveCmd::Left)); assert_eq!(&state.as_ascii_rows()[4], "#          h # #");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[4], "#       h    # #");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[4], "#       h    # #");
+        let _ = state.advance(MoveCmd::Right); assert_eq!(&state.as_ascii_rows()[4], "#        h   # #");
+        let _ = state.advance(MoveCmd::Right); assert_eq!(&state.as_ascii_rows()[4], "#         h  # #");
+        let _ = state.advance(MoveCmd::Left); assert_eq!(&state.as_ascii_rows()[4], "#        h   # #");
+        let _ = state.advance(MoveCmd::Left); assert_eq!(&state.as_ascii_rows()[4], "#       h    # #");
+        let _ = state.advance(MoveCmd::Right); assert_eq!(&state.as_ascii_rows()[4], "#        h   # #");
+        let _ = state.advance(MoveCmd::Stay); assert_eq!(&state.as_ascii_rows()[4], "#        h   # #");
+        let _ = state.advance(MoveCmd::Right); assert_eq!(&state.as_ascii_rows()[4], "#         h  # #");
+        let _ = state.advance(MoveCmd::Right); assert_eq!(&state.as_ascii_rows()[4], "#          h # #");
+        let _ = state.advance(MoveCmd::Right); assert_eq!(&state.as_ascii_rows()[4], "#           h# #");
+        let _ = state.advance(MoveCmd::Right); assert_eq!(&state.as_ascii_rows()[4], "#           h# #");
+        let _ = state.advance(MoveCmd::Left); assert_eq!(&state.as_ascii_rows()[4], "#          h # #");
+    }
+
+    #[test]
+    fn arena_events() {
+        let mut state = get_lev(1);
+        let hero = state.as_arena().hero();
+        let hero_events = |state: &Scene<_>| state.as_arena().events().iter().filter(|event| event.obj() == Some(hero)).cloned().collect::<Vec<_>>();
+
+        let _ = state.advance(MoveCmd::Right);
+        assert_eq!(hero_events(&state), [ArenaEvent::Moved { obj: hero, from: MapCoord::from_xy(8, 4), to: MapCoord::from_xy(9, 4) }]);
+        // Fish moves too, and bounces off the wall after a while.
+        assert!(matches!(state.as_arena().events(), [_, ArenaEvent::Moved { .. }]));
+
+        for _ in 0..4 {
+            let _ = state.advance(MoveCmd::Right);
+        }
+        assert_eq!(hero_events(&state), [ArenaEvent::Blocked { obj: hero, pos: MapCoord::from_xy(12, 4), target: MapCoord::from_xy(13, 4) }]);
+    }
+
+    #[test]
+    fn push_crate() {
+        let mut state = get_lev(6);
+        let hero = state.as_arena().hero();
+        let _ = state.advance(MoveCmd::Right); assert_eq!(&state.as_ascii_rows()[1], "# hb #");
+        let crate_idx = state.as_arena().events().iter().find_map(|event| match event {
+            ArenaEvent::Pushed { obj, by, from, to } => {
+                assert_eq!((*by, *from, *to), (hero, MapCoord::from_xy(2, 1), MapCoord::from_xy(3, 1)));
+                Some(*obj)
+            },
+            _ => None,
+        }).expect("crate pushed");
+        assert_ne!(crate_idx, hero);
+
+        // Crate against the wall can't be pushed further.
+        let _ = state.advance(MoveCmd::Right); assert_eq!(&state.as_ascii_rows()[1], "#  hb#");
+        let _ = state.advance(MoveCmd::Right); assert_eq!(&state.as_ascii_rows()[1], "#  hb#");
+        assert!(matches!(state.as_arena().events(), [ArenaEvent::Blocked { obj, .. }] if *obj == hero));
+    }
+
+    #[test]
+    fn edge_of_map() {
+        // Hero starts on edge with no wall beside it.
+        let mut state = get_lev(3);
+        let _ = state.advance(MoveCmd::Left);
+        assert_eq!(&state.as_ascii_rows()[1], "h  ");
+
+        let mut state = Scene::Arena(state.as_arena().clone().with_edge_policy(EdgePolicy::Wrap));
+        let _ = state.advance(MoveCmd::Left);
+        assert_eq!(&state.as_ascii_rows()[1], "  h");
     }
 
     // TODO Test win
     // TODO: Test die
     // TODO: Test moving two objs into same Loc
 
+    #[test]
+    fn obj_registry_from_json() {
+        // Game objs loaded from objs.json behave like the sample objs.
+        let registry = super::super::pushpuzz::objs::obj_registry();
+        assert_eq!(registry.obj("Fish", Facing::W).unwrap().logical_props, new_fish(Facing::W).logical_props);
+        assert_eq!(registry.obj("Gawpie", Facing::E).unwrap().logical_props, new_gawpie(Facing::E).logical_props);
+        assert_eq!(registry.obj("Goal", Facing::N).unwrap().logical_props, new_door_win().logical_props);
+        assert_eq!(registry.obj("Crate", Facing::N).unwrap().logical_props, new_crate().logical_props);
+        assert_eq!(registry.map_key()[&'h'][1].logical_props, new_hero_crab().logical_props);
+        assert!(std::ptr::eq(registry, super::super::pushpuzz::objs::obj_registry()));
+    }
+
     #[test]
     fn clone_map_and_move() {
         let orig_curr_scene_state = get_lev(1);
         let mut state = orig_curr_scene_state.clone();
         log::trace!("Orig>> {orig_curr_scene_state:?}");
         log::trace!("Clone>> {state:?}");
-        let _ = state.advance(Some(MoveCmd::Right));
+        let _ = state.advance(MoveCmd::Right);
     }
 }
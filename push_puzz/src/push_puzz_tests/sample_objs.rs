@@ -21,7 +21,7 @@ pub fn new_hero_crab() -> FreeObj<SimpleCustomProps> {
     }
 }
 
-pub fn new_fish(dir: CoordDelta) -> FreeObj<SimpleCustomProps> {
+pub fn new_fish(dir: Facing) -> FreeObj<SimpleCustomProps> {
     FreeObj {
         logical_props: LogicalProps::<SimpleCustomProps> {
             name: "Fish".to_string(),
@@ -29,7 +29,7 @@ pub fn new_fish(dir: CoordDelta) -> FreeObj<SimpleCustomProps> {
             custom_props: SimpleCustomProps {
                 ai: SimpleAI::Bounce,
             },
-            dir: dir,
+            dir,
             effect: Effect::Kill,
             .. LogicalProps::<SimpleCustomProps>::defaults()
         },
@@ -40,7 +40,7 @@ pub fn new_fish(dir: CoordDelta) -> FreeObj<SimpleCustomProps> {
     }
 }
 
-pub fn new_gawpie(dir: CoordDelta) -> FreeObj<SimpleCustomProps> {
+pub fn new_gawpie(dir: Facing) -> FreeObj<SimpleCustomProps> {
     FreeObj {
         logical_props: LogicalProps::<SimpleCustomProps> {
             name: "Gawpie".to_string(),
@@ -48,7 +48,7 @@ pub fn new_gawpie(dir: CoordDelta) -> FreeObj<SimpleCustomProps> {
             custom_props: SimpleCustomProps {
                 ai: SimpleAI::Drift,
             },
-            dir: dir,
+            dir,
             effect: Effect::Kill,
             .. LogicalProps::<SimpleCustomProps>::defaults()
         },
@@ -59,6 +59,36 @@ pub fn new_gawpie(dir: CoordDelta) -> FreeObj<SimpleCustomProps> {
     }
 }
 
+pub fn new_scuttler(dir: Facing) -> FreeObj<SimpleCustomProps> {
+    FreeObj {
+        logical_props: LogicalProps::<SimpleCustomProps> {
+            name: "Scuttler".to_string(),
+            pass: Pass::Mov,
+            custom_props: SimpleCustomProps {
+                ai: SimpleAI::Scuttle,
+            },
+            dir,
+            effect: Effect::Kill,
+            .. LogicalProps::<SimpleCustomProps>::defaults()
+        },
+        visual_props: VisualProps::new_text_fill("SCUT".to_string(), Some(RED), Some(BLACK))
+    }
+}
+
+pub fn new_crate() -> FreeObj<SimpleCustomProps> {
+    FreeObj {
+        logical_props: LogicalProps::<SimpleCustomProps> {
+            name: "Crate".to_string(),
+            pass: Pass::Mov,
+            custom_props: SimpleCustomProps {
+                ai: SimpleAI::Pushed,
+            },
+            .. LogicalProps::<SimpleCustomProps>::defaults()
+        },
+        visual_props: VisualProps::new_text_fill("BOX".to_string(), Some(BROWN), Some(WHITE))
+    }
+}
+
 pub fn new_floor() -> FreeObj<SimpleCustomProps> {
     FreeObj {
         logical_props: LogicalProps::<SimpleCustomProps> {
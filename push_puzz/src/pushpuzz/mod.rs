@@ -19,7 +19,7 @@
 // TODO: Move imgs into data. Test that builds and preview html still work.
 
 mod levels;
-mod objs;
+pub mod objs;
 
 mod movement_logic;
 
@@ -29,7 +29,7 @@ use tile_engine::for_gamedata::*;
 
 #[derive(Debug)]
 pub struct PushpuzzGameData {
-    levset: levels::PushpuzzLevset,
+    levset: LevelSet<PushpuzzMovementLogic>,
 }
 
 impl BaseGameData for PushpuzzGameData {
@@ -38,15 +38,21 @@ impl BaseGameData for PushpuzzGameData {
 
     fn new() -> Self {
         PushpuzzGameData {
-            levset: levels::PushpuzzLevset::new()
+            levset: levels::level_set(),
         }
     }
 
+    // No save game, so every level counts as unlocked, and levels are played in order.
     fn advance_scene(&mut self, continuation: SceneConclusion) {
-        self.levset.advance_scene(continuation)
+        let unlocked = (1..=self.levset.num_levels()).collect();
+        self.levset.advance_scene(continuation, &unlocked)
     }
 
-    fn load_scene(&self) -> Scene<Self::MovementLogic> {
+    fn load_scene(&mut self) -> Scene<Self::MovementLogic> {
         self.levset.load_scene()
     }
+
+    fn get_level_str(&self) -> String {
+        self.levset.get_level_str()
+    }
 }
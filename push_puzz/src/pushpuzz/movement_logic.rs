@@ -11,18 +11,28 @@ impl BaseMovementLogic for PushpuzzMovementLogic {
     fn move_mov(map: &mut Arena<Self>, mov: RosterIndex, cmd: MoveCmd) -> SceneContinuation {
         let hero = map.hero();
         match map[mov].logical_props.custom_props.ai {
-            SimpleAI::Stay => {
+            SimpleAI::Stay | SimpleAI::Pushed => {
                 // Do nothing
             },
             SimpleAI::Hero => {
                 if cmd != MoveCmd::Stay {
-                    let target_pos = map[mov].pos() + cmd.as_dir();
+                    let pos = map[mov].pos();
+                    let target_pos = pos + cmd.as_dir();
                     if map.passable(target_pos) {
                         map.move_obj_to(mov, target_pos);
+                    } else if let Some(pushed) = Self::pushable_at(map, target_pos, cmd.as_dir()) {
+                        // Push obj ahead, then follow into the square it left.
+                        let from = map[pushed].pos();
+                        map.move_obj_to(pushed, target_pos + cmd.as_dir());
+                        map.push_event(ArenaEvent::Pushed { obj: pushed, by: mov, from, to: map[pushed].pos() });
+                        map.move_obj_to(mov, target_pos);
+                    } else {
+                        map.push_event(ArenaEvent::Blocked { obj: mov, pos, target: target_pos });
                     }
                 }
                 // TODO: Avoid needing to re-get the hero handle, make move function consume or update the rich_mov handle.
                 return if map.any_has_effect(map[mov].pos(), Effect::Win) {
+                    map.push_event(ArenaEvent::ReachedGoal { obj: mov, pos: map[mov].pos() });
                     SceneContinuation::Break(SceneConclusion::Win)
                 } else {
                     SceneContinuation::Continue(())
@@ -51,6 +61,7 @@ impl BaseMovementLogic for PushpuzzMovementLogic {
                 // Hero dies if mov moves onto hero
                 // TODO: Check at end of function? Or as part of obj?
                 if map[mov].logical_props.effect == Effect::Kill && map[mov].pos() == map[hero].pos() {
+                    map.push_event(ArenaEvent::Died { obj: hero, pos: map[hero].pos() });
                     return SceneContinuation::Break(SceneConclusion::Die);
                 }
             },
@@ -64,15 +75,17 @@ impl BaseMovementLogic for PushpuzzMovementLogic {
                 {
                     map[mov].logical_props.dir.reverse();
 
-                    // And if hero "visible" forward or sideways, move one sideways towards them, if passable.
-                    // TODO: Check for obstacles to vision.
+                    // And if hero visible forward or sideways, move one sideways towards them, if passable.
                     let hero_dir = map[mov].pos().dir_to(map[hero].pos());
-                    if map[mov].logical_props.dir.dx == 0 {
-                        if hero_dir.dy != -map[mov].logical_props.dir.dy {
+                    let dir = CoordDelta::from(map[mov].logical_props.dir);
+                    if !map.line_of_sight(map[mov].pos(), map[hero].pos()) {
+                        // Can't see hero, keep drifting straight.
+                    } else if dir.dx == 0 {
+                        if hero_dir.dy != -dir.dy {
                             drift_dir = CoordDelta::from_xy(hero_dir.dx, 0);
                         }
-                    } else if map[mov].logical_props.dir.dy == 0 {
-                        if hero_dir.dx != -map[mov].logical_props.dir.dx {
+                    } else if dir.dy == 0 {
+                        if hero_dir.dx != -dir.dx {
                             drift_dir = CoordDelta::from_xy(0, hero_dir.dy);
                         }
                     } else {
@@ -82,7 +95,7 @@ impl BaseMovementLogic for PushpuzzMovementLogic {
 
                 // Move. Provided next space is passable. If both sides are impassable, don't move.
                 // TODO: Animation for turning? At least avoiding wall?
-                let delta = map[mov].logical_props.dir + drift_dir;
+                let delta = CoordDelta::from(map[mov].logical_props.dir) + drift_dir;
                 if map.passable(map[mov].pos() + delta)
                 {
                     map.move_obj_to(mov, map[mov].pos() + delta);
@@ -90,6 +103,7 @@ impl BaseMovementLogic for PushpuzzMovementLogic {
 
                 // Hero dies if mov moves onto hero
                 if map[mov].logical_props.effect == Effect::Kill && map[mov].pos() == map[hero].pos() {
+                    map.push_event(ArenaEvent::Died { obj: hero, pos: map[hero].pos() });
                     return SceneContinuation::Break(SceneConclusion::Die);
                 }
             },
@@ -99,11 +113,12 @@ impl BaseMovementLogic for PushpuzzMovementLogic {
                 {
                     let hero_dir = map[mov].pos().dir_to(map[hero].pos());
                     let hero_delta = map[mov].pos().delta_to(map[hero].pos());
+                    let dir = CoordDelta::from(map[mov].logical_props.dir);
                     // Find whether x or y is more towards the hero
                     let x_longer_than_y = match hero_delta.dx.abs() - hero_delta.dy.abs() {
                         num if num > 0 => true,
                         num if num < 0 => false,
-                        _ => map[mov].logical_props.dir.dy.abs() < map[mov].logical_props.dir.dy.abs(),
+                        _ => dir.dy.abs() < dir.dy.abs(),
                     };
                     // dlongcoord is the orthogonal direction most towards the hero. dshortcoord is the other best.
                     let (dlongcoord, dshortcoord) = if x_longer_than_y {
@@ -111,15 +126,21 @@ impl BaseMovementLogic for PushpuzzMovementLogic {
                     } else {
                         (CoordDelta::from_xy(0, hero_dir.dy), CoordDelta::from_xy(hero_dir.dx, 0))
                     };
-                    // Prefer the directions "most" towards the hero first
-                    let try_dirs = vec![dlongcoord, dshortcoord, -dshortcoord, -dlongcoord];
+                    // Prefer the first step of a route to the hero, if there is one, then the
+                    // directions "most" towards the hero.
+                    let path_dir = map.shortest_path(map[mov].pos(), map[hero].pos(), |pos| map.passable(pos))
+                        .and_then(|path| path.first().map(|step| map[mov].pos().delta_to(*step)));
+                    let try_dirs: Vec<CoordDelta> = path_dir.into_iter()
+                        .chain([dlongcoord, dshortcoord, -dshortcoord, -dlongcoord])
+                        .collect();
                     // Try each direction in turn, use the first passable one.
                     // Can't be the same as original direction because that was impassable.
                     // If none are passable, stay in the same direction we started.
-                    if let Some(dir) = try_dirs.iter().find(|dir|
-                        map.passable(map[mov].pos() + **dir)
+                    // Directions with no Facing, e.g. towards hero in same row, are skipped.
+                    if let Some(facing) = try_dirs.iter().find_map(|dir|
+                        Facing::try_from(*dir).ok().filter(|_| map.passable(map[mov].pos() + *dir))
                     ) {
-                        map[mov].logical_props.dir = *dir;
+                        map[mov].logical_props.dir = facing;
                     }
                 }
 
@@ -131,10 +152,19 @@ impl BaseMovementLogic for PushpuzzMovementLogic {
 
                 // Hero dies if bot moves onto hero
                 if map[mov].logical_props.effect == Effect::Kill && map[mov].pos() == map[hero].pos() {
+                    map.push_event(ArenaEvent::Died { obj: hero, pos: map[hero].pos() });
                     return SceneContinuation::Break(SceneConclusion::Die);
                 }
             },
         }
         return SceneContinuation::Continue(());
     }
+}
+
+impl PushpuzzMovementLogic {
+    // Obj at pos which can be pushed along by delta, if any. Needs a passable square beyond it.
+    fn pushable_at(map: &Arena<Self>, pos: MapCoord, delta: CoordDelta) -> Option<RosterIndex> {
+        let pushed = map.get(pos)?.into_iter().find(|obj| obj.logical_props.custom_props.ai == SimpleAI::Pushed)?;
+        map.passable(pos + delta).then(|| pushed.roster_idx())
+    }
 }
\ No newline at end of file
@@ -24,7 +24,7 @@ struct Engine<GameData: BaseGameData> {
 
 impl<GameData: gamedata::BaseGameData> Engine<GameData> {
     pub fn new() -> Engine<GameData> {
-        let gamedata = GameData::new();
+        let mut gamedata = GameData::new();
         let scene = gamedata.load_scene();
         Engine::<GameData> {
             gamedata: gamedata,
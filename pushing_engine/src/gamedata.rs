@@ -53,7 +53,7 @@ pub trait BaseGameData {
 
     fn advance_scene(&mut self, continuation: SceneConclusion);
 
-    fn load_scene(&self) -> Scene<Self::MovementLogic>;
+    fn load_scene(&mut self) -> Scene<Self::MovementLogic>;
 
     fn load_next_scene(&mut self, continuation: SceneConclusion) -> Scene<Self::MovementLogic> {
         self.advance_scene(continuation);
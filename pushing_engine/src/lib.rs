@@ -12,11 +12,14 @@
 
 mod core_engine;
 mod gamedata;
+mod level_set;
 mod logging;
 mod map_coords;
+mod progression;
 pub mod input; // For engine_tests in push_puzz??
 mod scene;
 mod obj;
+mod obj_defs;
 mod ui;
 pub mod simple_custom_props;
 
@@ -27,12 +30,16 @@ pub use core_engine::run;
 // NB: Check which things ought to be exported, which shouldn't be needed.
 pub mod for_gamedata {
     pub use super::obj::*;
+    pub use super::obj_defs::*;
     pub use super::gamedata::*;
+    pub use super::level_set::*;
+    pub use super::progression::*;
     pub use super::scene::*;
     pub use super::map_coords::*;
     pub use super::simple_custom_props::*;
     pub use super::scene::Arena;
     pub use super::scene::arena::RosterIndex;
+    pub use super::scene::arena::EdgePolicy;
 }
 
 pub mod infra {
@@ -20,7 +20,7 @@ use derive_more::{Add, Mul, Neg};
 //
 // TODO: Is it useful to have a type for them? May just add clutter.
 
-#[derive(Copy, Clone, PartialEq, Debug, Add, Mul)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Add, Mul)]
 pub struct MapCoord {
     pub x: i16,
     pub y: i16,
@@ -47,13 +47,19 @@ impl Add<CoordDelta> for MapCoord {
     }
 }
 
+impl Add<Facing> for MapCoord {
+    type Output = MapCoord;
+    fn add(self, facing: Facing) -> MapCoord {
+        self + CoordDelta::from(facing)
+    }
+}
+
 impl std::fmt::Display for MapCoord {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "({},{})", self.x, self.y)
     }
 }
 
-// NB: Need separate "Facing" type which can easily be converted to a CoordDelta.
 #[derive(Add, Copy, Clone, PartialEq, Debug, Neg)] // , Add, Mul
 pub struct CoordDelta {
     pub dx: i16,
@@ -78,43 +84,91 @@ impl CoordDelta {
     fn reversed(self) -> Self {
         CoordDelta { dx: self.dx * -1, dy: self.dy * -1 }
     }
+}
 
-    // Cycles forward through:
-    // Facing N 0,-1
-    // Facing E 1, 0
-    // Facing S 0, -1
-    // Facing W -1, 0
-    fn rotated_r(&self) -> CoordDelta {
-        CoordDelta {
-            dx: -self.dy,
-            dy: self.dx,
-        }
+/// Direction an obj faces or moves in. Converts losslessly to a unit CoordDelta.
+///
+/// Variants are in clockwise order starting from north (up the screen).
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Facing {
+    #[default]
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl std::fmt::Display for Facing {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
     }
+}
 
-    // Reverse of rotated_r
-    fn rotated_l(&self) -> CoordDelta {
-        CoordDelta {
-            dx: self.dy,
-            dy: -self.dx,
-        }
+/// Parse from the same abbreviations as Display, e.g. "N", "SW".
+impl std::str::FromStr for Facing {
+    type Err = String;
+
+    fn from_str(txt: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|facing| facing.to_string() == txt).ok_or_else(|| format!("Unrecognised facing: {txt}"))
+    }
+}
+
+impl Facing {
+    pub const ALL: [Facing; 8] = [Self::N, Self::NE, Self::E, Self::SE, Self::S, Self::SW, Self::W, Self::NW];
+
+    /// Rotated by a number of eighths of a turn, clockwise if positive.
+    pub fn rotated_by(self, eighths: i16) -> Self {
+        Self::ALL[(self as i16 + eighths).rem_euclid(8) as usize]
     }
 
     pub fn rotate_r(&mut self) {
-        *self = self.rotated_r()
+        *self = self.rotated_by(2)
     }
 
     pub fn rotate_l(&mut self) {
-        *self = self.rotated_l()
+        *self = self.rotated_by(-2)
     }
 
-    // If one square U, R, D or L, return repr as angle in radians.
-    pub fn as_angle(&self) ->f32 {
-        match self {
-                CoordDelta{dx:1, dy:0} => std::f32::consts::PI / 2.,
-                CoordDelta{dx:0, dy: 1} => std::f32::consts::PI,
-                CoordDelta{dx:-1, dy: 0} => std::f32::consts::PI * 1.5,
-                _ => 0.
-            }
+    pub fn reverse(&mut self) {
+        *self = self.rotated_by(4)
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        self as i16 % 2 == 1
+    }
+
+    /// Angle in radians clockwise from north, as used to rotate textures.
+    pub fn as_angle(self) -> f32 {
+        self as i16 as f32 * std::f32::consts::PI / 4.
+    }
+}
+
+impl From<Facing> for CoordDelta {
+    fn from(facing: Facing) -> Self {
+        let (dx, dy) = match facing {
+            Facing::N => (0, -1),
+            Facing::NE => (1, -1),
+            Facing::E => (1, 0),
+            Facing::SE => (1, 1),
+            Facing::S => (0, 1),
+            Facing::SW => (-1, 1),
+            Facing::W => (-1, 0),
+            Facing::NW => (-1, -1),
+        };
+        CoordDelta::from_xy(dx, dy)
+    }
+}
+
+/// Only unit deltas, orthogonal or diagonal, have a Facing. Returns other deltas as the error.
+impl TryFrom<CoordDelta> for Facing {
+    type Error = CoordDelta;
+
+    fn try_from(delta: CoordDelta) -> Result<Self, Self::Error> {
+        Self::ALL.into_iter().find(|facing| CoordDelta::from(*facing) == delta).ok_or(delta)
     }
 }
 
@@ -138,7 +192,6 @@ pub enum MoveCmd {
 impl MoveCmd {
     pub fn as_dir(self: Self) -> CoordDelta {
         match self {
-            // NB: Could be mapped in terms of rotatable Facing type.
             Self::Stay  => CoordDelta::from_xy(0, 0),
             Self::Left  => CoordDelta::from_xy(-1, 0),
             Self::Right => CoordDelta::from_xy(1, 0),
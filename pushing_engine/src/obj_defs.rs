@@ -0,0 +1,188 @@
+// Object types defined in a data file instead of in game code.
+//
+// Each game has a json file listing its object types (logic and appearance)
+// and the ascii key used to draw its level maps. ObjRegistry loads the file
+// and produces FreeObjs, and the map key for Arena::from_map_and_key.
+//
+// Values like "pass" and "effect" are parsed with each engine's own FromStr impls.
+// Both engines have a copy of this file, as they don't share a crate. Keep the copies the same.
+//
+// Example:
+//
+// {
+//     "objs": [
+//         {"name": "Floor", "fill": "WHITE", "border": "LIGHTGRAY"},
+//         {"name": "Wall", "pass": "Solid", "fill": "DARKGRAY"},
+//         {"name": "Bot", "pass": "Mov", "ai": "Prog", "tex_paths": ["ferris.png"]}
+//     ],
+//     "key": {
+//         " ": ["Floor"],
+//         "#": ["Floor", "Wall"],
+//         ">": ["Floor", "Bot:E"]
+//     }
+// }
+
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use nanoserde::DeJson;
+
+use crate::for_gamedata::BaseCustomProps;
+use crate::map_coords::Facing;
+use crate::obj::{FreeObj, LogicalProps, VisualProps};
+use crate::simple_custom_props::{Effect, Pass};
+
+/// One object type as written in the definitions file.
+///
+/// Omitted fields take the defaults from LogicalProps and VisualProps. Colours
+/// are macroquad colour names like "DARKGRAY", or hex like "#FFD700".
+#[derive(Clone, Debug, DeJson)]
+pub struct ObjDef {
+    pub name: String,
+    pub pass: Option<String>,
+    pub effect: Option<String>,
+    /// Interpreted by the game, which converts it to CustomProps.
+    pub ai: Option<String>,
+    #[nserde(default)]
+    pub tex_paths: Vec<String>,
+    pub tex_scale: Option<f32>,
+    pub fill: Option<String>,
+    pub border: Option<String>,
+    pub text: Option<String>,
+    pub text_col: Option<String>,
+}
+
+#[derive(Clone, Debug, DeJson)]
+struct ObjDefsFile {
+    objs: Vec<ObjDef>,
+    // Map char to stack of obj names, lowest first. Names may be followed by
+    // the facing direction, e.g. "Bot:E".
+    key: HashMap<String, Vec<String>>,
+}
+
+/// Templates for each object type in a game, and the ascii key for its maps.
+#[derive(Clone, Debug)]
+pub struct ObjRegistry<CustomProps: BaseCustomProps> {
+    templates: HashMap<String, FreeObj<CustomProps>>,
+    map_key: HashMap<char, Vec<FreeObj<CustomProps>>>,
+}
+
+impl<CustomProps: BaseCustomProps> ObjRegistry<CustomProps> {
+    /// Load object definitions from json text.
+    ///
+    /// custom_props_for_ai converts an obj's "ai" field to the game's CustomProps,
+    /// or None if it's not recognised. Objs without "ai" get CustomProps::default().
+    pub fn from_json(json: &str, custom_props_for_ai: impl Fn(&str) -> Option<CustomProps>) -> Result<Self, String> {
+        let file = ObjDefsFile::deserialize_json(json).map_err(|err| format!("Invalid obj defs: {err}"))?;
+
+        let mut registry = Self { templates: HashMap::new(), map_key: HashMap::new() };
+        for def in &file.objs {
+            let obj = def.to_free_obj(&custom_props_for_ai)?;
+            registry.templates.insert(def.name.clone(), obj);
+        }
+
+        for (key_txt, stack) in &file.key {
+            let mut chars = key_txt.chars();
+            let (Some(ch), None) = (chars.next(), chars.next()) else {
+                return Err(format!("Map key '{key_txt}' should be a single char"));
+            };
+            let objs = stack.iter().map(|entry| registry.obj_from_key_entry(entry)).collect::<Result<Vec<_>, _>>()?;
+            registry.map_key.insert(ch, objs);
+        }
+
+        Ok(registry)
+    }
+
+    /// New obj of the named type, facing the given way, or None if no such type.
+    pub fn obj(&self, name: &str, dir: Facing) -> Option<FreeObj<CustomProps>> {
+        self.templates.get(name).map(|template| {
+            let mut obj = template.clone();
+            obj.logical_props.dir = dir;
+            obj
+        })
+    }
+
+    /// Ascii map key, as used by Arena::from_map_and_key.
+    pub fn map_key(&self) -> HashMap<char, Vec<FreeObj<CustomProps>>> {
+        self.map_key.clone()
+    }
+
+    fn obj_from_key_entry(&self, entry: &str) -> Result<FreeObj<CustomProps>, String> {
+        let (name, dir) = match entry.split_once(':') {
+            Some((name, facing_txt)) => (name, facing_txt.parse::<Facing>()?),
+            None => (entry, Facing::default()),
+        };
+        self.obj(name, dir).ok_or_else(|| format!("Map key uses undefined obj '{name}'"))
+    }
+}
+
+impl ObjDef {
+    fn to_free_obj<CustomProps: BaseCustomProps>(
+        &self,
+        custom_props_for_ai: &impl Fn(&str) -> Option<CustomProps>,
+    ) -> Result<FreeObj<CustomProps>, String> {
+        let custom_props = match &self.ai {
+            Some(ai) => custom_props_for_ai(ai).ok_or_else(|| format!("Obj '{}' has unrecognised ai '{ai}'", self.name))?,
+            None => CustomProps::default(),
+        };
+        let logical_defaults = LogicalProps::<CustomProps>::defaults();
+        let visual_defaults = VisualProps::defaults();
+
+        Ok(FreeObj {
+            logical_props: LogicalProps {
+                name: self.name.clone(),
+                pass: self.parse_field(&self.pass, |txt| txt.parse::<Pass>().ok())?.unwrap_or(logical_defaults.pass),
+                effect: self.parse_field(&self.effect, |txt| txt.parse::<Effect>().ok())?.unwrap_or(logical_defaults.effect),
+                custom_props,
+                ..logical_defaults
+            },
+            visual_props: VisualProps {
+                border: self.parse_field(&self.border, parse_colour)?,
+                fill: self.parse_field(&self.fill, parse_colour)?,
+                tex_paths: self.tex_paths.iter().map(|path| VisualProps::assets_path() + path).collect(),
+                tex_scale: self.tex_scale.unwrap_or(visual_defaults.tex_scale),
+                text: self.text.clone(),
+                text_col: self.parse_field(&self.text_col, parse_colour)?,
+            },
+        })
+    }
+
+    fn parse_field<T>(&self, field: &Option<String>, parse: fn(&str) -> Option<T>) -> Result<Option<T>, String> {
+        field.as_deref().map(|txt|
+            parse(txt).ok_or_else(|| format!("Obj '{}' has unrecognised value '{txt}'", self.name))
+        ).transpose()
+    }
+}
+
+fn parse_colour(txt: &str) -> Option<Color> {
+    match txt.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 => u32::from_str_radix(hex, 16).ok().map(Color::from_hex),
+        Some(_) => None,
+        None => parse_colour_name(txt),
+    }
+}
+
+fn parse_colour_name(txt: &str) -> Option<Color> {
+    let colour = match txt {
+        "WHITE" => WHITE,
+        "BLACK" => BLACK,
+        "LIGHTGRAY" => LIGHTGRAY,
+        "GRAY" => GRAY,
+        "DARKGRAY" => DARKGRAY,
+        "GOLD" => GOLD,
+        "YELLOW" => YELLOW,
+        "ORANGE" => ORANGE,
+        "RED" => RED,
+        "PINK" => PINK,
+        "GREEN" => GREEN,
+        "DARKGREEN" => DARKGREEN,
+        "SKYBLUE" => SKYBLUE,
+        "BLUE" => BLUE,
+        "DARKBLUE" => DARKBLUE,
+        "PURPLE" => PURPLE,
+        "BROWN" => BROWN,
+        "BLANK" => BLANK,
+        _ => return None,
+    };
+    Some(colour)
+}
@@ -1,5 +1,7 @@
 mod scene_base;
 pub mod arena;
+pub mod arena_event;
+pub mod arena_search;
 pub mod coding;
 pub mod splash;
 pub mod coding_arena;
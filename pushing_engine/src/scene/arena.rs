@@ -14,6 +14,7 @@ use std::ops::IndexMut;
 use culpa::try_fn;
 
 use super::scene_base::{BaseScene, SceneContinuation};
+use super::arena_event::ArenaEvent;
 use crate::simple_custom_props;
 use crate::for_gamedata;
 use for_gamedata::BaseMovementLogic;
@@ -28,6 +29,18 @@ pub struct RosterIndex {
     ros_idx: u16,
 }
 
+/// What happens to movs which try to move past the edge of the map.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum EdgePolicy {
+    /// Edge blocks movement like a wall.
+    #[default]
+    Solid,
+    /// Moving past the edge is impassable and has Effect::Kill.
+    Deadly,
+    /// Moving past one edge comes back in at the opposite edge.
+    Wrap,
+}
+
 /// Grid together with Ros. Those are two separate classes so they can more easily be borrowed separately.
 #[derive(Clone, Debug)]
 pub struct Arena<MovementLogic: for_gamedata::BaseMovementLogic> {
@@ -35,6 +48,9 @@ pub struct Arena<MovementLogic: for_gamedata::BaseMovementLogic> {
     roster: Roster,
     // Used to represent map as ascii for init and debugging. Not comprehensive.
     map_key: std::collections::HashMap<char, Vec<FreeObj<MovementLogic::CustomProps>>>,
+    // What happened during the most recent advance. Cleared at start of each advance.
+    events: Vec<ArenaEvent>,
+    edge_policy: EdgePolicy,
 }
 
 impl<MovementLogic : for_gamedata::BaseMovementLogic> BaseScene for Arena<MovementLogic>
@@ -43,25 +59,12 @@ impl<MovementLogic : for_gamedata::BaseMovementLogic> BaseScene for Arena<Moveme
         // TODO: Decide order of char, enemy. Before or after not quite right. Or need
         // to handle char moving onto enemy.
         // TODO: Consider: Maybe display char moving out of sync with enemy.
-        let hero = Roster::hero();
-
-        // Before movement, reset "prev". Will be overwritten if movement happens.
-        // Should be moved into obj_move*() fn.
-        self[hero].refs.prev_pos = self[hero].refs.pos;
-        self[hero].logical_props.prev_dir = self[hero].logical_props.dir;
+        self.events.clear();
 
-        MovementLogic::move_mov(self, hero, cmd)?;
-        log::trace!("After mov, hero facing changed {} -> {}", self[hero].logical_props.prev_dir, self[hero].logical_props.dir);
+        self.advance_mov(Roster::hero(), cmd)?;
 
         for mov in self.roster.all_movs() {
-            // Before movement, reset "prev". Will be overwritten if movement happens.
-            // Going through tmp is necessary to avoid two dynamic borrows at the same time..
-            // NOTE: If map is RefCell needs to be done in two steps else runtime panic.
-            // NOTE: And obj_at() is also incompatible with RefCell.
-            self[mov].refs.prev_pos = self[mov].refs.pos;
-            self[mov].logical_props.prev_dir = self[mov].logical_props.prev_dir;
-
-            MovementLogic::move_mov(self, mov, cmd)?;
+            self.advance_mov(mov, cmd)?;
         }
         SceneContinuation::Continue(())
     }
@@ -72,6 +75,27 @@ impl<MovementLogic : for_gamedata::BaseMovementLogic> BaseScene for Arena<Moveme
 }
 
 impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
+    /////////////////
+    /// Helpers for BaseScene fns
+    fn advance_mov(&mut self, mov: RosterIndex, cmd: MoveCmd) -> SceneContinuation {
+        // Before movement, reset "prev". Will be overwritten if movement happens.
+        // Going through tmp is necessary to avoid two dynamic borrows at the same time..
+        // NOTE: If map is RefCell needs to be done in two steps else runtime panic.
+        // NOTE: And obj_at() is also incompatible with RefCell.
+        self[mov].refs.prev_pos = self[mov].refs.pos;
+        let prev_dir = self[mov].logical_props.dir;
+
+        let continuation = MovementLogic::move_mov(self, mov, cmd);
+
+        // Movement logic changes facing directly, so record any rotation here.
+        let dir = self[mov].logical_props.dir;
+        if prev_dir != dir {
+            self.push_event(ArenaEvent::Rotated { obj: mov, from: prev_dir, to: dir });
+        }
+
+        continuation
+    }
+
     /////////////////
     /// Initialisers
     pub fn empty(w: u16, h: u16) -> Self {
@@ -79,6 +103,8 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
             map: Into::into(Grid::new(w, h)),
             roster: Roster::new(),
             map_key: std::collections::HashMap::new(),
+            events: vec![],
+            edge_policy: EdgePolicy::default(),
         }
     }
 
@@ -104,6 +130,11 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
         map
     }
 
+    /// Set what happens at map edges. Defaults to EdgePolicy::Solid.
+    pub fn with_edge_policy(self, edge_policy: EdgePolicy) -> Self {
+        Self { edge_policy, ..self }
+    }
+
     //////////////////////////////////////////////
     /// Exposed upward to front end of game engine
 
@@ -115,11 +146,46 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
         self.map.h()
     }
 
+    pub fn edge_policy(&self) -> EdgePolicy {
+        self.edge_policy
+    }
+
+    /// Coords of a square after wrapping past the map edge, if the map wraps.
+    ///
+    /// Other policies leave coords unchanged, so they may be outside the map.
+    pub fn wrap_pos(&self, pos: MapCoord) -> MapCoord {
+        match self.edge_policy {
+            EdgePolicy::Wrap => MapCoord::from_xy(
+                pos.x.rem_euclid(self.map_w() as i16),
+                pos.y.rem_euclid(self.map_h() as i16),
+            ),
+            EdgePolicy::Solid | EdgePolicy::Deadly => pos,
+        }
+    }
+
+    /// Square at pos, after wrapping, or None if it's past the map edge.
+    pub fn get(&self, pos: MapCoord) -> Option<&Loc<MovementLogic::CustomProps>> {
+        let pos = self.wrap_pos(pos);
+        self.in_bounds(pos).then(|| &self.map[pos])
+    }
+
     // TODO: Any better way to expose this for iterating?
     pub fn map_locs(&self) -> LocIterator<MovementLogic> {
         self.map.locs()
     }
 
+    /// Events which happened during the most recent advance.
+    pub fn events(&self) -> &[ArenaEvent] {
+        &self.events
+    }
+
+    /// Record event during advance. Used by engine, and by MovementLogic for
+    /// events the engine can't detect itself, e.g. being blocked.
+    pub fn push_event(&mut self, event: ArenaEvent) {
+        log::debug!("Arena event: {event:?}");
+        self.events.push(event);
+    }
+
     //////////////////////////////////////////////////////////////////////////////////
     /// Obj spawn and move fns.
     ///
@@ -152,7 +218,10 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
     /// Move obj to a new location.
     ///
     /// Update roster and backpos.curr_pos and backpos.prev_pos. Still untested for multiple movs.
+    ///
+    /// Target is wrapped past map edge if the map wraps.
     pub fn move_obj_to(&mut self, roster_idx: RosterIndex, target_pos: MapCoord) {
+        let target_pos = self.wrap_pos(target_pos);
         let orig_pos = self.roster[roster_idx].pos();
         let orig_h = self.roster[roster_idx].h;
 
@@ -183,6 +252,8 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
         self.roster[roster_idx].x = target_pos.x;
         self.roster[roster_idx].y = target_pos.y;
         self.roster[roster_idx].h = self.map[target_pos].len() as u16 -1;
+
+        self.push_event(ArenaEvent::Moved { obj: roster_idx, from: orig_pos, to: target_pos });
     }
 
     ///////////////////////////////////////////////////
@@ -202,12 +273,17 @@ impl<MovementLogic: BaseMovementLogic> Arena<MovementLogic> {
     }
 
     // NB: Should remove engine dependency on simple_custom_props
+    // Past the map edge only has Effect::Kill if edge is deadly.
     pub fn any_has_effect(&self, pos: MapCoord, sought_effect: simple_custom_props::Effect) -> bool {
-        self.map[pos].any_effect(sought_effect)
+        match self.get(pos) {
+            Some(loc) => loc.any_effect(sought_effect),
+            None => self.edge_policy == EdgePolicy::Deadly && sought_effect == simple_custom_props::Effect::Kill,
+        }
     }
 
+    // Past the map edge is never passable.
     pub fn passable(&self, pos: MapCoord) -> bool {
-        self.map[pos].passable()
+        self.get(pos).is_some_and(|loc| loc.passable())
     }
 
     //////////////////////////////////////////////////////
@@ -570,6 +646,11 @@ pub struct MapObj<CustomProps: for_gamedata::BaseCustomProps> {
 }
 
 impl<CustomProps: for_gamedata::BaseCustomProps> MapObj<CustomProps> {
+    /// Handle for movs. Non-mov objs all share a placeholder handle.
+    pub fn roster_idx(&self) -> RosterIndex {
+        self.refs.curr_roster_idx
+    }
+
     pub fn pos(&self) -> MapCoord {
         self.refs.pos
     }
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::ops::ControlFlow;
 
 pub use super::arena::Arena;
+pub use super::arena_event::*;
 pub use super::coding::*;
 pub use super::splash::*;
 pub use super::coding_arena::*;
@@ -20,6 +21,17 @@ pub enum SceneConclusion {
     Die,
 }
 
+// Same questions as programming_engine's SceneConclusion answers, so LevelSet is the same in both engines.
+impl SceneConclusion {
+    pub fn solved(&self) -> bool {
+        *self == SceneConclusion::Win
+    }
+
+    pub fn failed(&self) -> bool {
+        *self == SceneConclusion::Die
+    }
+}
+
 // After each tick, either Continue, or restart/start another level based on Conclusion.
 pub type SceneContinuation = ControlFlow<SceneConclusion, ()>;
 
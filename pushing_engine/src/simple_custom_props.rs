@@ -31,6 +31,20 @@ pub enum Pass {
     // INSERT: Obj, // Something which can be moved or maybe coexisted with, e.g. furniture
 }
 
+/// Parse from the variant name, e.g. "Solid", as used in obj defs files.
+impl std::str::FromStr for Pass {
+    type Err = String;
+
+    fn from_str(txt: &str) -> Result<Self, Self::Err> {
+        match txt {
+            "Empty" => Ok(Self::Empty),
+            "Solid" => Ok(Self::Solid),
+            "Mov" => Ok(Self::Mov),
+            _ => Err(format!("Unrecognised pass: {txt}")),
+        }
+    }
+}
+
 // Types of movement-control logic ents can use
 // TODO: Move into Pushpuzz. Make a cut-down SimpleAI for example game and testing.
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -42,6 +56,7 @@ pub enum SimpleAI {
     Bounce, // Move in direction, reverse direction at walls.
     Drift, // Move in direction, reverse direction at walls, move diagonally towards hero at reversal.
     Scuttle, // Move in direction, when hit wall change to move orthogonally towards hero.
+    Pushed, // No self movement, but hero pushes it along if the square beyond is passable.
 }
 
 // Effect when intersect with hero (as mov or stay)
@@ -53,3 +68,17 @@ pub enum Effect {
     // STUB: Can add effects like when ent dies
     // STUB: Could convert Win, Kill, to Progress(Win),... with enum Progress {Win, Lose}
 }
+
+/// Parse from the variant name, e.g. "Kill", as used in obj defs files.
+impl std::str::FromStr for Effect {
+    type Err = String;
+
+    fn from_str(txt: &str) -> Result<Self, Self::Err> {
+        match txt {
+            "Nothing" => Ok(Self::Nothing),
+            "Kill" => Ok(Self::Kill),
+            "Win" => Ok(Self::Win),
+            _ => Err(format!("Unrecognised effect: {txt}")),
+        }
+    }
+}